@@ -7,10 +7,7 @@ use rand::Rng;
 use std::error::Error;
 use std::fs::File;
 use std::sync::Arc;
-use trusty::tree::{
-    FeatureTreeBuilder, FeatureType, GradientBoostedDecisionTrees, PredictorConfig, VecTreeNodes,
-};
-use trusty::Objective;
+use trusty::tree::{FeatureTreeBuilder, FeatureType, GradientBoostedDecisionTrees, VecTreeNodes};
 
 type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
 
@@ -402,15 +399,13 @@ pub mod feature_tree {
             })
             .collect::<Vec<_>>();
 
-        GradientBoostedDecisionTrees {
-            trees,
-            feature_names: Arc::new(feature_names),
-            feature_types: Arc::new(feature_types),
-            base_score: 0.5,
-            objective: Objective::SquaredError,
-            config: PredictorConfig::default(),
-            required_features: (0..feature_count).collect(),
-        }
+        let mut gbdt = GradientBoostedDecisionTrees::default();
+        gbdt.trees = trees;
+        gbdt.feature_names = Arc::new(feature_names);
+        gbdt.feature_types = Arc::new(feature_types);
+        gbdt.base_score = 0.5;
+        gbdt.required_features = (0..feature_count).collect();
+        gbdt
     }
 
     pub fn create_feature_arrays(
@@ -15,7 +15,7 @@ use std::io::BufReader;
 use tokio::runtime::Runtime;
 use trusty::loader::ModelLoader;
 use trusty::predicates::{Condition, Predicate};
-use trusty::tree::GradientBoostedDecisionTrees;
+use trusty::tree::{GradientBoostedDecisionTrees, NodeLayout};
 
 const BATCHSIZE: usize = 8 * 1024;
 
@@ -109,6 +109,19 @@ fn benchmark_diamonds_prediction(c: &mut Criterion) -> Result<()> {
             .iter(|| async { predict_batch(&pruned_trees, &data_batches).unwrap() })
     });
 
+    let bfs_trees = trees.with_layout(NodeLayout::breadth_first());
+    let blocked_trees = trees.with_layout(NodeLayout::default());
+
+    c.bench_function("trusty/diamonds/bfs_layout", |b| {
+        b.to_async(&rt)
+            .iter(|| async { predict_batch(&bfs_trees, &data_batches).unwrap() })
+    });
+
+    c.bench_function("trusty/diamonds/blocked_layout", |b| {
+        b.to_async(&rt)
+            .iter(|| async { predict_batch(&blocked_trees, &data_batches).unwrap() })
+    });
+
     Ok(())
 }
 
@@ -144,6 +157,19 @@ fn benchmark_airline_prediction(c: &mut Criterion) -> Result<()> {
             .iter(|| async { predict_batch(&pruned_trees, &data_batches).unwrap() })
     });
 
+    let bfs_trees = trees.with_layout(NodeLayout::breadth_first());
+    let blocked_trees = trees.with_layout(NodeLayout::default());
+
+    group.bench_function("bfs_layout", |b| {
+        b.to_async(&rt)
+            .iter(|| async { predict_batch(&bfs_trees, &data_batches).unwrap() })
+    });
+
+    group.bench_function("blocked_layout", |b| {
+        b.to_async(&rt)
+            .iter(|| async { predict_batch(&blocked_trees, &data_batches).unwrap() })
+    });
+
     group.finish();
     Ok(())
 }
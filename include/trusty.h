@@ -0,0 +1,213 @@
+#ifndef TRUSTY_H
+#define TRUSTY_H
+
+/* Generated by cbindgen from src/capi.rs; do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Arrow C Data Interface, https://arrow.apache.org/docs/format/CDataInterface.html */
+#ifndef ARROW_C_DATA_INTERFACE
+#define ARROW_C_DATA_INTERFACE
+
+#define ARROW_FLAG_DICTIONARY_ORDERED 1
+#define ARROW_FLAG_NULLABLE 2
+#define ARROW_FLAG_MAP_KEYS_SORTED 4
+
+struct ArrowSchema {
+  const char *format;
+  const char *name;
+  const char *metadata;
+  int64_t flags;
+  int64_t n_children;
+  struct ArrowSchema **children;
+  struct ArrowSchema *dictionary;
+  void (*release)(struct ArrowSchema *);
+  void *private_data;
+};
+
+struct ArrowArray {
+  int64_t length;
+  int64_t null_count;
+  int64_t offset;
+  int64_t n_buffers;
+  int64_t n_children;
+  const void **buffers;
+  struct ArrowArray **children;
+  struct ArrowArray *dictionary;
+  void (*release)(struct ArrowArray *);
+  void *private_data;
+};
+
+#endif /* ARROW_C_DATA_INTERFACE */
+
+/**
+ * [`TrustyCondition::kind`] of a feature known to be below `value`.
+ */
+#define TRUSTY_CONDITION_KIND_LESS_THAN 0
+
+/**
+ * [`TrustyCondition::kind`] of a feature known to be at least `value`.
+ */
+#define TRUSTY_CONDITION_KIND_GREATER_THAN_OR_EQUAL 1
+
+/**
+ * Result of the fallible functions.
+ */
+typedef enum TrustyStatus {
+  TRUSTY_STATUS_OK = 0,
+  /**
+   * A null pointer, a string that is not UTF-8 or a predicate on an unknown feature.
+   */
+  TRUSTY_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * The model file could not be read.
+   */
+  TRUSTY_STATUS_IO = 2,
+  /**
+   * The model JSON is not a valid XGBoost model.
+   */
+  TRUSTY_STATUS_INVALID_MODEL = 3,
+  /**
+   * The input could not be imported or scored, e.g. because a feature column is missing.
+   */
+  TRUSTY_STATUS_ARROW = 4,
+  /**
+   * trusty panicked; this is a bug.
+   */
+  TRUSTY_STATUS_PANIC = 5,
+} TrustyStatus;
+
+/**
+ * Loaded model, owned by the caller until passed to [`trusty_model_free`].
+ */
+typedef struct TrustyModel TrustyModel;
+
+/**
+ * Condition on one feature that all rows scored by a pruned model satisfy.
+ */
+typedef struct TrustyCondition {
+  const char *feature;
+  /**
+   * One of the `TRUSTY_CONDITION_KIND_*` constants. It is a plain integer rather than an
+   * enum because it is read from caller memory, which may hold any value.
+   */
+  uint32_t kind;
+  double value;
+} TrustyCondition;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Message of the last failed call on this thread, or null if it succeeded. The string is
+ * valid until the next call on the same thread.
+ */
+const char *trusty_last_error(void);
+
+/**
+ * Writes the name of feature `index` of `model`, null-terminated, into `buffer` of
+ * `buffer_len` bytes and returns its length without the terminator. Nothing is written if the
+ * buffer is too small; returns 0 if `model` is null or `index` is out of range.
+ *
+ * # Safety
+ *
+ * `model` must be null or a live model, and `buffer` must point to `buffer_len` writable bytes.
+ */
+size_t trusty_model_feature_name(const TrustyModel *model,
+                                 size_t index,
+                                 char *buffer,
+                                 size_t buffer_len);
+
+/**
+ * Frees a model; null is ignored.
+ *
+ * # Safety
+ *
+ * `model` must come from this library and must not be used afterwards.
+ */
+void trusty_model_free(TrustyModel *model);
+
+/**
+ * Loads an XGBoost JSON model from the file at `path` into `*out`.
+ *
+ * # Safety
+ *
+ * `path` must be a null-terminated string and `out` a valid pointer.
+ */
+TrustyStatus trusty_model_load(const char *path, TrustyModel **out);
+
+/**
+ * Loads an XGBoost JSON model from `len` bytes at `data` into `*out`.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` must be a valid pointer.
+ */
+TrustyStatus trusty_model_load_bytes(const uint8_t *data, size_t len, TrustyModel **out);
+
+/**
+ * Number of features of `model`, or 0 if it is null.
+ *
+ * # Safety
+ *
+ * `model` must be null or a live model.
+ */
+size_t trusty_model_num_features(const TrustyModel *model);
+
+/**
+ * Number of nodes over all trees of `model`, or 0 if it is null.
+ *
+ * # Safety
+ *
+ * `model` must be null or a live model.
+ */
+size_t trusty_model_num_nodes(const TrustyModel *model);
+
+/**
+ * Number of trees of `model`, or 0 if it is null.
+ *
+ * # Safety
+ *
+ * `model` must be null or a live model.
+ */
+size_t trusty_model_num_trees(const TrustyModel *model);
+
+/**
+ * Scores the rows of a record batch, exported as a struct array with its schema, into a
+ * `float32` array.
+ *
+ * Columns are matched to the model's features by name and cast to their types; other columns
+ * are ignored. The struct array itself must not have null rows. The input array is moved:
+ * trusty releases it, also on failure. The schema is only borrowed. On success `out_array`
+ * and `out_schema` hold the predictions, which the caller releases.
+ *
+ * # Safety
+ *
+ * All pointers must be valid; `array` and `schema` must follow the Arrow C Data Interface.
+ */
+TrustyStatus trusty_model_predict(const TrustyModel *model,
+                                  struct ArrowArray *array,
+                                  const struct ArrowSchema *schema,
+                                  struct ArrowArray *out_array,
+                                  struct ArrowSchema *out_schema);
+
+/**
+ * Prunes `model` with `len` conditions into a new model in `*out`; `model` is unchanged.
+ *
+ * # Safety
+ *
+ * `model` must be a live model, `conditions` must point to `len` conditions with
+ * null-terminated feature names, and `out` must be a valid pointer.
+ */
+TrustyStatus trusty_model_prune(const TrustyModel *model,
+                                const TrustyCondition *conditions,
+                                size_t len,
+                                TrustyModel **out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TRUSTY_H */
@@ -1,8 +1,14 @@
 import importlib.metadata
+import importlib.util
 
-from quickgrove._internal import PyGradientBoostedDecisionTrees
+from quickgrove._internal import HAS_POLARS_PLUGIN, PyGradientBoostedDecisionTrees
 from quickgrove._internal import Feature as Feature
 from quickgrove._internal import json_load as json_load
+from quickgrove._internal import load as load
 
-__all__ = ['PyGradientBoostedDecisionTrees', 'Feature', 'json_load']
-__version__ = importlib.metadata.version(__package__)
\ No newline at end of file
+if HAS_POLARS_PLUGIN and importlib.util.find_spec("polars") is not None:
+    # Registers the `quickgrove` expression namespace.
+    import quickgrove.polars  # noqa: F401
+
+__all__ = ['PyGradientBoostedDecisionTrees', 'Feature', 'json_load', 'load']
+__version__ = importlib.metadata.version(__package__)
@@ -0,0 +1,54 @@
+"""Polars expressions scoring quickgrove models.
+
+Importing this module registers the ``quickgrove`` expression namespace::
+
+    import polars as pl
+    import quickgrove.polars  # noqa: F401
+
+    df.with_columns(prediction=pl.col("carat", "depth", "table").quickgrove.predict(model))
+    df.with_columns(prediction=pl.struct("carat", "depth").quickgrove.predict(model))
+
+Columns, or the fields of a single struct column, are matched to the model's features by
+name; features the trees do not use may be left out. Their Arrow buffers are handed to the
+model without copying.
+"""
+
+import hashlib
+from pathlib import Path
+
+import polars as pl
+from polars.plugins import register_plugin_function
+
+from quickgrove._internal import HAS_POLARS_PLUGIN
+
+if not HAS_POLARS_PLUGIN:
+    raise ImportError(
+        "quickgrove was built without the Polars plugin; "
+        "rebuild it with `maturin build --release --features polars`"
+    )
+
+_PLUGIN_PATH = Path(__file__).parent
+
+
+def predict(model, *exprs) -> pl.Expr:
+    """Expression scoring ``model`` on ``exprs``, which may expand to several columns."""
+    snapshot = model.to_bytes()
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="predict",
+        args=list(exprs),
+        # The plugin caches parsed models by digest, so it is computed once here rather than
+        # for every chunk the plugin is called on.
+        kwargs={"digest": hashlib.sha256(snapshot).hexdigest(), "model": snapshot},
+        is_elementwise=True,
+        input_wildcard_expansion=True,
+    )
+
+
+@pl.api.register_expr_namespace("quickgrove")
+class QuickgroveNamespace:
+    def __init__(self, expr: pl.Expr):
+        self._expr = expr
+
+    def predict(self, model) -> pl.Expr:
+        return predict(model, self._expr)
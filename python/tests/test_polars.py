@@ -0,0 +1,69 @@
+import numpy as np
+import pandas as pd
+import pyarrow as pa
+import pytest
+import quickgrove
+
+from quickgrove import Feature
+from pathlib import Path
+
+pl = pytest.importorskip("polars")
+if not quickgrove._internal.HAS_POLARS_PLUGIN:
+    pytest.skip("built without the polars feature", allow_module_level=True)
+
+TEST_DIR = Path(__file__).parent.parent.parent # ../../
+DATA = TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+MODEL = TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+
+
+def load():
+    df = pd.read_csv(DATA).drop(["target", "prediction"], axis=1)
+    model = quickgrove.json_load(MODEL)
+    expected = np.array(model.predict_batches([pa.RecordBatch.from_pandas(df)]))
+    return pl.from_pandas(df), model, expected
+
+
+def test_predict_columns():
+    df, model, expected = load()
+    result = df.select(prediction=pl.col(model.feature_names).quickgrove.predict(model))
+    assert result["prediction"].dtype == pl.Float32
+    np.testing.assert_array_equal(result["prediction"].to_numpy(), expected)
+
+    # Columns are matched by name, and other columns are ignored.
+    shuffled = df.select(reversed(df.columns)).with_columns(extra=pl.lit(1.0))
+    result = shuffled.select(pl.all().quickgrove.predict(model).alias("prediction"))
+    np.testing.assert_array_equal(result["prediction"].to_numpy(), expected)
+
+
+def test_predict_struct_and_lazy():
+    df, model, expected = load()
+    result = (
+        df.lazy()
+        .with_columns(prediction=pl.struct(pl.all()).quickgrove.predict(model))
+        .collect()
+    )
+    np.testing.assert_array_equal(result["prediction"].to_numpy(), expected)
+
+
+def test_predict_pruned_model():
+    df, model, _ = load()
+    df = df.filter(pl.col("carat") < 0.2)
+    pruned = model.prune([Feature("carat") < 0.2])
+    expected = np.array(pruned.predict_batches([df.to_arrow().to_batches()[0]]))
+    result = df.select(pl.all().quickgrove.predict(pruned).alias("prediction"))
+    np.testing.assert_array_equal(result["prediction"].to_numpy(), expected)
+
+
+def test_predict_tuned_model():
+    df, model, _ = load()
+    # The plugin receives the whole model, including the engine and layout chosen here.
+    tuned = model.autotune(df.head(256).to_arrow().to_batches()[0])
+    expected = np.array(tuned.predict_batches([df.to_arrow().to_batches()[0]]))
+    result = df.select(pl.all().quickgrove.predict(tuned).alias("prediction"))
+    np.testing.assert_array_equal(result["prediction"].to_numpy(), expected)
+
+
+def test_missing_feature():
+    df, model, _ = load()
+    with pytest.raises(pl.exceptions.ComputeError, match="Missing feature column: carat"):
+        df.drop("carat").select(pl.all().quickgrove.predict(model))
@@ -1,4 +1,6 @@
+import json
 import pandas as pd
+import pytest
 import numpy as np
 import pyarrow as pa
 import quickgrove
@@ -54,6 +56,9 @@ def test_tree_info():
     assert isinstance(str(tree), str)
     assert "VecTree:" in str(tree)
     assert "Leaf (weight:" in str(tree)
+    assert "cover =" not in tree.to_dot()
+    assert "gain =" in tree.to_dot(show_stats=True)
+    assert "<br>cover =" in tree.to_mermaid(show_stats=True)
     
     try:
         model.tree_info(999)
@@ -80,7 +85,7 @@ def test_prediction_chunking():
 
     chunk_configs = [
         (32, 4),
-        (64, 8),  # default
+        (64, 8),
         (128, 16),
         (256, 32)
     ]
@@ -93,3 +98,207 @@ def test_prediction_chunking():
             decimal=3,
             err_msg=f"Failed with row_chunk={row_chunk}, tree_chunk={tree_chunk}"
         )
+
+
+def test_prediction_threads():
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    )
+    model = quickgrove.json_load(
+        TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+    )
+    df = df.drop(["target", "prediction"], axis=1)
+    batch = pa.RecordBatch.from_pandas(df)
+
+    expected = np.array(model.predict_batches([batch]))
+    for n_threads in [1, 2, 4]:
+        predictions = model.predict_batches([batch], n_threads=n_threads)
+        np.testing.assert_array_equal(
+            np.array(predictions),
+            expected,
+            err_msg=f"Failed with n_threads={n_threads}"
+        )
+
+
+def test_f64_accumulation():
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    )
+    model = quickgrove.json_load(
+        TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+    )
+    actual_preds = df["prediction"].copy().to_numpy()
+    df = df.drop(["target", "prediction"], axis=1)
+    batch = pa.RecordBatch.from_pandas(df)
+
+    expected = np.array(model.predict_batches([batch], accumulation="f64"))
+    np.testing.assert_allclose(expected, actual_preds, rtol=2e-6)
+    for engine in ["traversal", "quickscorer", "simd"]:
+        for tree_chunk_size in [1, 8, 64]:
+            predictions = model.predict_batches(
+                [batch],
+                engine=engine,
+                tree_chunk_size=tree_chunk_size,
+                accumulation="f64",
+                n_threads=2,
+            )
+            np.testing.assert_array_equal(np.array(predictions), expected)
+
+
+def test_invalid_prediction_inputs():
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    )
+    model = quickgrove.json_load(
+        TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+    )
+    df = df.drop(["target", "prediction"], axis=1)
+    batch = pa.RecordBatch.from_pandas(df)
+
+    with pytest.raises(ValueError, match="row_chunk_size"):
+        model.predict_batches([batch], row_chunk_size=0)
+    with pytest.raises(ValueError, match="tree_chunk_size"):
+        model.predict_batches([batch], tree_chunk_size=0)
+    with pytest.raises(ValueError, match="feature arrays"):
+        model.predict_arrays([])
+
+
+def test_autotune(tmp_path):
+    model_path = tmp_path / "model.json"
+    model_path.write_text(
+        (TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json").read_text()
+    )
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    )
+    actual_preds = df["prediction"].copy().to_list()
+    df = df.drop(["target", "prediction"], axis=1)
+    batch = pa.RecordBatch.from_pandas(df)
+
+    model = quickgrove.json_load(model_path)
+    tuned = model.autotune(batch, cache_path=model_path)
+    reloaded = quickgrove.json_load(model_path)
+    for m in [tuned, reloaded]:
+        np.testing.assert_array_almost_equal(
+            np.array(m.predict_batches([batch])), np.array(actual_preds), decimal=3
+        )
+    np.testing.assert_array_equal(
+        np.array(tuned.predict_batches([batch])),
+        np.array(reloaded.predict_batches([batch])),
+    )
+
+
+def test_numpy_and_pandas_inputs():
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    )
+    model = quickgrove.json_load(
+        TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+    )
+    df = df.drop(["target", "prediction"], axis=1)
+    df.loc[0, "carat"] = np.nan
+    expected = np.array(model.predict_batches([pa.RecordBatch.from_pandas(df)]))
+
+    def readonly(array):
+        array = array.copy(order="K")
+        array.setflags(write=False)
+        return array
+
+    matrix = df[model.feature_names].to_numpy(dtype=np.float64)
+    strided = np.repeat(matrix, 2, axis=1)[:, ::2]
+    # Arrays are read in place, read-only ones without holding the GIL.
+    for array in [
+        matrix,
+        np.asfortranarray(matrix),
+        matrix.astype(np.float32),
+        np.asfortranarray(matrix.astype(np.float32)),
+        strided,
+        readonly(matrix),
+        readonly(np.asfortranarray(matrix.astype(np.float32))),
+        readonly(strided),
+    ]:
+        for predict in [model.predict_batches, model.predict_arrays]:
+            predictions = predict(array)
+            assert isinstance(predictions, np.ndarray)
+            assert predictions.dtype == np.float32
+            assert predictions.flags.writeable
+            np.testing.assert_array_equal(predictions, expected)
+            predictions[0] = 0.0
+
+    # Reversed views have negative strides and are copied instead.
+    for reversed_rows in [matrix[::-1], readonly(matrix)[::-1]]:
+        predictions = model.predict_batches(reversed_rows)
+        np.testing.assert_array_equal(predictions, expected[::-1])
+
+    # Named inputs are aligned to feature_names and return pyarrow arrays.
+    shuffled = df.iloc[:, ::-1].assign(extra="ignored")
+    columns = {name: shuffled[name].to_numpy() for name in shuffled.columns}
+    for data in [shuffled, columns]:
+        predictions = model.predict_batches(data, n_threads=2)
+        assert isinstance(predictions, pa.Array)
+        np.testing.assert_array_equal(np.array(predictions), expected)
+        np.testing.assert_array_equal(np.array(model.predict_arrays(data)), expected)
+
+    with pytest.raises(ValueError, match="Missing feature column: carat"):
+        model.predict_batches(df.drop(columns="carat"))
+    with pytest.raises(ValueError, match="2-D"):
+        model.predict_batches(matrix[0])
+    with pytest.raises(ValueError, match="columns"):
+        model.predict_batches(matrix[:, :3])
+    with pytest.raises(TypeError, match="float32 or float64"):
+        model.predict_batches(matrix.astype(np.int64))
+
+
+def test_pickle_copy_and_save(tmp_path):
+    import copy
+    import pickle
+
+    df = pd.read_csv(
+        TEST_DIR / "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv"
+    ).query("carat < 0.2")
+    model = quickgrove.json_load(
+        TEST_DIR / "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json"
+    )
+    df = df.drop(["target", "prediction"], axis=1)
+    batch = pa.RecordBatch.from_pandas(df)
+    pruned = model.prune([Feature("carat") < 0.2]).autotune(batch)
+    expected = np.array(pruned.predict_batches([batch]))
+
+    path = tmp_path / "model.trusty"
+    pruned.save(path)
+    restored = [
+        pickle.loads(pickle.dumps(pruned)),
+        copy.copy(pruned),
+        copy.deepcopy(pruned),
+        quickgrove.load(path),
+        quickgrove.PyGradientBoostedDecisionTrees.load(path),
+    ]
+    for other in restored:
+        assert isinstance(other, quickgrove.PyGradientBoostedDecisionTrees)
+        assert other.required_features == pruned.required_features
+        assert other.feature_names == pruned.feature_names
+        assert str(other) == str(pruned)
+        np.testing.assert_array_equal(np.array(other.predict_batches([batch])), expected)
+
+    assert len(pickle.dumps(pruned)) < len(pruned.to_xgboost_json())
+    with pytest.raises(ValueError, match="not a trusty model snapshot"):
+        quickgrove.PyGradientBoostedDecisionTrees.from_bytes(b"not a model")
+
+
+@pytest.mark.parametrize("objective", ["reg_squarederror", "binary_logistic"])
+def test_to_xgboost_json_loads_in_xgboost(tmp_path, objective):
+    xgb = pytest.importorskip("xgboost")
+    model_path = TEST_DIR / f"tests/models/{objective}/diamonds_model_trees_100_mixed.json"
+    df = pd.read_csv(
+        TEST_DIR / f"tests/data/{objective}/diamonds_data_filtered_trees_100_mixed.csv"
+    ).drop(["target", "prediction"], axis=1)
+    exported_path = tmp_path / "model.json"
+    exported_path.write_text(quickgrove.json_load(model_path).to_xgboost_json())
+
+    original = xgb.Booster(model_file=str(model_path))
+    exported = xgb.Booster(model_file=str(exported_path))
+    for booster in (original, exported):
+        config = json.loads(booster.save_config())
+        assert config["learner"]["objective"]["name"] == objective.replace("_", ":", 1)
+    dmatrix = xgb.DMatrix(df)
+    np.testing.assert_allclose(exported.predict(dmatrix), original.predict(dmatrix), rtol=1e-6)
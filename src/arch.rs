@@ -1,8 +1,30 @@
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+/// Widest vector instruction set usable for multi-row tree traversal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    /// 16 rows per step with AVX-512F gathers and mask compares.
+    Avx512,
+    /// 8 rows per step with AVX2 gathers.
+    Avx2,
+    /// 8 rows per step in plain Rust.
+    Portable,
+}
+
+impl SimdLevel {
+    /// Number of rows advanced through a tree together.
+    pub fn lanes(&self) -> usize {
+        match self {
+            SimdLevel::Avx512 => 16,
+            SimdLevel::Avx2 | SimdLevel::Portable => 8,
+        }
+    }
+}
+
 pub struct CpuFeatures {
     has_prefetch: bool,
+    simd_level: SimdLevel,
 }
 impl Default for CpuFeatures {
     fn default() -> Self {
@@ -16,16 +38,32 @@ impl CpuFeatures {
         #[cfg(target_arch = "x86_64")]
         {
             let has_prefetch = is_x86_feature_detected!("sse");
-            Self { has_prefetch }
+            let simd_level = if is_x86_feature_detected!("avx512f") {
+                SimdLevel::Avx512
+            } else if is_x86_feature_detected!("avx2") {
+                SimdLevel::Avx2
+            } else {
+                SimdLevel::Portable
+            };
+            Self {
+                has_prefetch,
+                simd_level,
+            }
         }
         #[cfg(not(target_arch = "x86_64"))]
         {
             Self {
                 has_prefetch: false,
+                simd_level: SimdLevel::Portable,
             }
         }
     }
 
+    #[inline]
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd_level
+    }
+
     #[inline]
     pub fn prefetch<T>(&self, _ptr: *const T) {
         if self.has_prefetch {
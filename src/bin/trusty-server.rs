@@ -0,0 +1,99 @@
+//! HTTP inference server for one or more models; see [`trusty::server`] for the endpoints.
+//!
+//! Built with the `server` feature: `cargo install trusty --features server`. With the `flight`
+//! feature as well, `--flight-addr` also serves the models over Arrow Flight.
+
+use clap::Parser;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use trusty::server::{router, serve, BatchOptions};
+use trusty::{GradientBoostedDecisionTrees, ModelLoader};
+
+#[derive(Parser)]
+#[command(
+    name = "trusty-server",
+    version,
+    about = "Serve XGBoost models over HTTP"
+)]
+struct Args {
+    /// XGBoost JSON models, as `name=path` or `path` to name the model after the file stem.
+    #[arg(required = true)]
+    models: Vec<String>,
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+    /// Requests to a model stop being merged once the batch has this many rows.
+    #[arg(long, default_value_t = BatchOptions::default().max_batch_rows)]
+    max_batch_rows: usize,
+    /// Milliseconds to wait for more requests to merge into a batch.
+    #[arg(long, default_value_t = 2)]
+    max_delay_ms: u64,
+    /// Requests waiting per model before new ones are rejected.
+    #[arg(long, default_value_t = BatchOptions::default().queue_size)]
+    queue_size: usize,
+    /// Also serve the models over Arrow Flight on this address.
+    #[cfg(feature = "flight")]
+    #[arg(long)]
+    flight_addr: Option<String>,
+}
+
+fn load_model(spec: &str) -> Result<(String, Arc<GradientBoostedDecisionTrees>), Box<dyn Error>> {
+    let (name, path) = match spec.split_once('=') {
+        Some((name, path)) => (name.to_string(), path),
+        None => {
+            let name = Path::new(spec)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| format!("Cannot name the model at {}", spec))?;
+            (name.to_string(), spec)
+        }
+    };
+    let model =
+        GradientBoostedDecisionTrees::json_load(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok((name, Arc::new(model)))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let models = args
+        .models
+        .iter()
+        .map(|spec| load_model(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    let names: Vec<String> = models.iter().map(|(name, _)| name.clone()).collect();
+    let mut seen = HashSet::new();
+    if let Some(name) = names.iter().find(|name| !seen.insert(name.as_str())) {
+        return Err(format!("Two models are named {}; name them with name=path", name).into());
+    }
+
+    let options = BatchOptions {
+        max_batch_rows: args.max_batch_rows,
+        max_delay: Duration::from_millis(args.max_delay_ms),
+        queue_size: args.queue_size,
+    };
+    #[cfg(feature = "flight")]
+    if let Some(addr) = &args.flight_addr {
+        let listener = TcpListener::bind(addr).await?;
+        eprintln!("Serving Arrow Flight on grpc://{}", listener.local_addr()?);
+        let service = trusty::flight::TrustyFlightService::new(models.clone());
+        tokio::spawn(async move {
+            if let Err(e) = trusty::flight::serve(listener, service).await {
+                eprintln!("error: Arrow Flight server stopped: {}", e);
+            }
+        });
+    }
+
+    let listener = TcpListener::bind(&args.addr).await?;
+    eprintln!(
+        "Serving {} on http://{}",
+        names.join(", "),
+        listener.local_addr()?
+    );
+    serve(listener, router(models, options)).await?;
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use arrow::csv;
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Seek};
+use std::path::Path;
+
+/// Rows read to infer the column types of a CSV file.
+const CSV_INFERENCE_ROWS: usize = 1000;
+
+/// File formats for inputs and outputs, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Parquet,
+    /// The Arrow IPC file format, also known as Feather v2.
+    Ipc,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("csv") => Ok(Format::Csv),
+            Some("parquet") | Some("pq") => Ok(Format::Parquet),
+            Some("arrow") | Some("ipc") | Some("feather") => Ok(Format::Ipc),
+            _ => Err(format!(
+                "Cannot tell the format of {}: expected a .csv, .parquet or .arrow file",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Opens `path` as a stream of batches of at most `batch_size` rows.
+pub fn open_reader(
+    path: &Path,
+    batch_size: usize,
+) -> Result<Box<dyn RecordBatchReader>, Box<dyn Error>> {
+    let mut file = File::open(path)?;
+    let reader: Box<dyn RecordBatchReader> = match Format::from_path(path)? {
+        Format::Csv => {
+            let (schema, _) = csv::reader::Format::default()
+                .with_header(true)
+                .infer_schema(&mut file, Some(CSV_INFERENCE_ROWS))?;
+            file.rewind()?;
+            Box::new(
+                csv::ReaderBuilder::new(schema.into())
+                    .with_header(true)
+                    .with_batch_size(batch_size)
+                    .build(file)?,
+            )
+        }
+        Format::Parquet => Box::new(
+            ParquetRecordBatchReaderBuilder::try_new(file)?
+                .with_batch_size(batch_size)
+                .build()?,
+        ),
+        // IPC files keep the batch sizes they were written with.
+        Format::Ipc => Box::new(FileReader::try_new(BufReader::new(file), None)?),
+    };
+    Ok(reader)
+}
+
+/// Writes batches to a file in one of the supported formats.
+pub enum BatchWriter {
+    Csv(Box<csv::Writer<File>>),
+    Parquet(ArrowWriter<File>),
+    Ipc(FileWriter<File>),
+}
+
+impl BatchWriter {
+    pub fn try_new(path: &Path, schema: SchemaRef) -> Result<Self, Box<dyn Error>> {
+        let format = Format::from_path(path)?;
+        let file = File::create(path)?;
+        Ok(match format {
+            Format::Csv => BatchWriter::Csv(Box::new(
+                csv::WriterBuilder::new().with_header(true).build(file),
+            )),
+            Format::Parquet => BatchWriter::Parquet(ArrowWriter::try_new(file, schema, None)?),
+            Format::Ipc => BatchWriter::Ipc(FileWriter::try_new(file, &schema)?),
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), Box<dyn Error>> {
+        match self {
+            BatchWriter::Csv(writer) => writer.write(batch)?,
+            BatchWriter::Parquet(writer) => writer.write(batch)?,
+            BatchWriter::Ipc(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    /// Writes the file footer, if the format has one.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            BatchWriter::Csv(_) => {}
+            BatchWriter::Parquet(writer) => {
+                writer.close()?;
+            }
+            BatchWriter::Ipc(mut writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
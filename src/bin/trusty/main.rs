@@ -0,0 +1,290 @@
+//! Command-line tool to score files with a model and to inspect, prune and validate models.
+//!
+//! Built with the `cli` feature: `cargo install trusty --features cli`.
+
+mod io;
+
+use arrow::record_batch::RecordBatchReader;
+use clap::{Args, Parser, Subcommand};
+use io::{open_reader, BatchWriter};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use trusty::{
+    Condition, GradientBoostedDecisionTrees, ImportanceType, ModelLoader, Objective, OutputColumns,
+    Parallelism, Predicate,
+};
+
+#[derive(Parser)]
+#[command(name = "trusty", version, about = "Score data with XGBoost models")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scores a CSV, Parquet or Arrow IPC file and writes the predictions.
+    Predict(PredictArgs),
+    /// Prints the model summary, per-tree details and feature importance.
+    Inspect(InspectArgs),
+    /// Prunes the model with predicates on its features and writes the pruned model.
+    Prune(PruneArgs),
+    /// Loads models and reports whether they pass the loader's checks.
+    Validate(ValidateArgs),
+}
+
+#[derive(Args)]
+struct PredictArgs {
+    /// XGBoost JSON model.
+    model: PathBuf,
+    /// Input file; the format follows the extension (.csv, .parquet or .arrow).
+    input: PathBuf,
+    /// Output file; the format follows the extension (.csv, .parquet or .arrow).
+    output: PathBuf,
+    /// Write raw scores, before the objective's link function (e.g. the logistic sigmoid).
+    #[arg(long)]
+    margin: bool,
+    /// Number of threads; 0 uses one per core. Defaults to rayon's global pool.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Rows scored together. Defaults to the model's configuration.
+    #[arg(long)]
+    row_chunk_size: Option<usize>,
+    /// Trees evaluated together. Defaults to the model's configuration.
+    #[arg(long)]
+    tree_chunk_size: Option<usize>,
+    /// Rows read from CSV and Parquet inputs per batch.
+    #[arg(long, default_value_t = 8192)]
+    batch_size: usize,
+    /// Name of the prediction column.
+    #[arg(long, default_value = "prediction")]
+    column: String,
+    /// Write the input columns followed by the prediction column.
+    #[arg(long)]
+    append: bool,
+}
+
+#[derive(Args)]
+struct InspectArgs {
+    /// XGBoost JSON model.
+    model: PathBuf,
+    /// Print the structure of every tree.
+    #[arg(long)]
+    trees: bool,
+    /// Importance measure: weight, gain, cover, total_gain or total_cover.
+    #[arg(long, default_value = "gain")]
+    importance: ImportanceType,
+}
+
+#[derive(Args)]
+struct PruneArgs {
+    /// XGBoost JSON model.
+    model: PathBuf,
+    /// Path of the pruned XGBoost JSON model.
+    output: PathBuf,
+    /// Predicate such as `carat<0.2` or `depth>=60`; repeat to combine them.
+    #[arg(short, long = "predicate", required = true)]
+    predicates: Vec<String>,
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    /// XGBoost JSON models.
+    #[arg(required = true)]
+    models: Vec<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Predict(args) => predict(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Prune(args) => prune(args),
+        Command::Validate(args) => validate(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_model(path: &Path) -> Result<GradientBoostedDecisionTrees, Box<dyn Error>> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| format!("Model path is not valid UTF-8: {}", path.display()))?;
+    Ok(GradientBoostedDecisionTrees::json_load(path_str)
+        .map_err(|e| format!("{}: {}", path.display(), e))?)
+}
+
+fn predict(args: PredictArgs) -> Result<(), Box<dyn Error>> {
+    let mut model = load_model(&args.model)?;
+    if args.margin {
+        // The squared error objective leaves scores untouched.
+        model.objective = Objective::SquaredError;
+    }
+    let mut config = model.config().clone();
+    if let Some(row_chunk_size) = args.row_chunk_size {
+        config = config.with_row_chunk_size(row_chunk_size)?;
+    }
+    if let Some(tree_chunk_size) = args.tree_chunk_size {
+        config = config.with_tree_chunk_size(tree_chunk_size)?;
+    }
+    if let Some(threads) = args.threads {
+        config = config.with_parallelism(Parallelism::threads(threads)?);
+    }
+    model.set_config(config);
+
+    let reader = open_reader(&args.input, args.batch_size)?;
+    let columns = if args.append {
+        OutputColumns::Append
+    } else {
+        OutputColumns::Prediction
+    };
+    let stream = model
+        .predict_stream(reader, columns)
+        .with_column_name(&args.column);
+    let mut writer = BatchWriter::try_new(&args.output, stream.schema())?;
+    for batch in stream {
+        writer.write(&batch?)?;
+    }
+    writer.finish()
+}
+
+fn inspect(args: InspectArgs) -> Result<(), Box<dyn Error>> {
+    let model = load_model(&args.model)?;
+    print!("{}", model);
+    println!("Objective: {:?}", model.objective);
+    println!("Base score: {}", model.base_score);
+    println!(
+        "Features used: {} of {}",
+        model.required_features.len(),
+        model.feature_names.len()
+    );
+
+    if args.trees {
+        // Tree structures refer to features by their compacted index.
+        println!("\nFeature indices:");
+        for (index, name) in model.tree_feature_names().iter().enumerate() {
+            println!("  split_{}: {}", index, name);
+        }
+    }
+    println!("\nTrees:");
+    for (index, tree) in model.trees.iter().enumerate() {
+        let nodes = tree.num_nodes();
+        // Every split has two children.
+        let leaves = nodes.div_ceil(2);
+        println!(
+            "  {}: depth {}, {} nodes, {} leaves",
+            index,
+            tree.depth(),
+            nodes,
+            leaves
+        );
+        if args.trees {
+            for line in tree.to_string().lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    let mut importance: Vec<_> = model
+        .feature_importance(args.importance)
+        .into_iter()
+        .collect();
+    importance.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    println!("\nFeature importance ({}):", args.importance);
+    for (name, value) in importance {
+        println!("  {}: {:.6}", name, value);
+    }
+    Ok(())
+}
+
+/// Parses `name<value` or `name>=value`, the two conditions that pruning supports.
+fn parse_predicate(text: &str) -> Result<(String, Condition), String> {
+    let invalid = || {
+        format!(
+            "Invalid predicate {:?}: expected `feature<value` or `feature>=value`",
+            text
+        )
+    };
+    let (name, value, greater_or_equal) = if let Some((name, value)) = text.split_once(">=") {
+        (name, value, true)
+    } else if let Some((name, value)) = text.split_once('<') {
+        (name, value, false)
+    } else {
+        return Err(invalid());
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+    let condition = if greater_or_equal {
+        Condition::GreaterThanOrEqual(value)
+    } else {
+        Condition::LessThan(value)
+    };
+    Ok((name.to_string(), condition))
+}
+
+fn prune(args: PruneArgs) -> Result<(), Box<dyn Error>> {
+    let model = load_model(&args.model)?;
+    let mut predicate = Predicate::new();
+    for text in &args.predicates {
+        let (name, condition) = parse_predicate(text)?;
+        if !model.feature_names.contains(&name) {
+            return Err(format!("Unknown feature in predicate {:?}: {}", text, name).into());
+        }
+        predicate.add_condition(name, condition);
+    }
+
+    let pruned = model.prune(&predicate);
+    serde_json::to_writer(
+        BufWriter::new(File::create(&args.output)?),
+        &pruned.to_xgboost_json(),
+    )?;
+    println!(
+        "Pruned {} trees with {} nodes to {} trees with {} nodes",
+        model.num_trees(),
+        model
+            .trees
+            .iter()
+            .map(|tree| tree.num_nodes())
+            .sum::<usize>(),
+        pruned.num_trees(),
+        pruned
+            .trees
+            .iter()
+            .map(|tree| tree.num_nodes())
+            .sum::<usize>(),
+    );
+    Ok(())
+}
+
+fn validate(args: ValidateArgs) -> Result<(), Box<dyn Error>> {
+    let mut failures = 0;
+    for path in &args.models {
+        match load_model(path) {
+            Ok(model) => println!(
+                "{}: ok ({} trees, {} of {} features used)",
+                path.display(),
+                model.num_trees(),
+                model.required_features.len(),
+                model.feature_names.len()
+            ),
+            Err(e) => {
+                println!("{}", e);
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format!("{} of {} models are invalid", failures, args.models.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,524 @@
+//! C ABI over the Arrow C Data Interface, enabled by the `capi` cargo feature.
+//!
+//! The declarations are in `include/trusty.h`. Functions return a [`TrustyStatus`]; after a
+//! failure, [`trusty_last_error`] describes it. Models are opaque pointers released with
+//! [`trusty_model_free`], and output arrays are released through their `release` callbacks as
+//! the C Data Interface specifies.
+
+use crate::loader::{ModelError, ModelLoader};
+use crate::predicates::{Condition, Predicate};
+use crate::tree::GradientBoostedDecisionTrees;
+use arrow::array::{Array, ArrayRef, StructArray};
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use arrow::ffi::{from_ffi, to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+/// Result of the fallible functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustyStatus {
+    Ok = 0,
+    /// A null pointer, a string that is not UTF-8 or a predicate on an unknown feature.
+    InvalidArgument = 1,
+    /// The model file could not be read.
+    Io = 2,
+    /// The model JSON is not a valid XGBoost model.
+    InvalidModel = 3,
+    /// The input could not be imported or scored, e.g. because a feature column is missing.
+    Arrow = 4,
+    /// trusty panicked; this is a bug.
+    Panic = 5,
+}
+
+/// Loaded model, owned by the caller until passed to [`trusty_model_free`].
+pub struct TrustyModel(GradientBoostedDecisionTrees);
+
+/// [`TrustyCondition::kind`] of a feature known to be below `value`.
+pub const TRUSTY_CONDITION_KIND_LESS_THAN: u32 = 0;
+/// [`TrustyCondition::kind`] of a feature known to be at least `value`.
+pub const TRUSTY_CONDITION_KIND_GREATER_THAN_OR_EQUAL: u32 = 1;
+
+/// Condition on one feature that all rows scored by a pruned model satisfy.
+#[repr(C)]
+pub struct TrustyCondition {
+    pub feature: *const c_char,
+    /// One of the `TRUSTY_CONDITION_KIND_*` constants. It is a plain integer rather than an
+    /// enum because it is read from caller memory, which may hold any value.
+    pub kind: u32,
+    pub value: f64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct Error {
+    status: TrustyStatus,
+    message: String,
+}
+
+impl Error {
+    fn invalid_argument(message: impl Into<String>) -> Self {
+        Self {
+            status: TrustyStatus::InvalidArgument,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ModelError> for Error {
+    fn from(e: ModelError) -> Self {
+        let status = match e {
+            ModelError::IoError(_) => TrustyStatus::Io,
+            _ => TrustyStatus::InvalidModel,
+        };
+        Self {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(e: ArrowError) -> Self {
+        Self {
+            status: TrustyStatus::Arrow,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Runs `f`, recording its error or panic for [`trusty_last_error`].
+fn ffi_call(f: impl FnOnce() -> Result<(), Error>) -> TrustyStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (TrustyStatus::Ok, None),
+        Ok(Err(e)) => (e.status, Some(e.message)),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            (TrustyStatus::Panic, Some(format!("panic: {}", message)))
+        }
+    };
+    let message = message.map(|m| CString::new(m.replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if s.is_null() {
+        return Err(Error::invalid_argument(format!("{} is null", name)));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Error::invalid_argument(format!("{} is not valid UTF-8", name)))
+}
+
+unsafe fn model_ref<'a>(
+    model: *const TrustyModel,
+) -> Result<&'a GradientBoostedDecisionTrees, Error> {
+    model
+        .as_ref()
+        .map(|model| &model.0)
+        .ok_or_else(|| Error::invalid_argument("model is null"))
+}
+
+unsafe fn write_model(
+    out: *mut *mut TrustyModel,
+    model: GradientBoostedDecisionTrees,
+) -> Result<(), Error> {
+    if out.is_null() {
+        return Err(Error::invalid_argument("out is null"));
+    }
+    *out = Box::into_raw(Box::new(TrustyModel(model)));
+    Ok(())
+}
+
+/// Message of the last failed call on this thread, or null if it succeeded. The string is
+/// valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn trusty_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Loads an XGBoost JSON model from the file at `path` into `*out`.
+///
+/// # Safety
+///
+/// `path` must be a null-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_load(
+    path: *const c_char,
+    out: *mut *mut TrustyModel,
+) -> TrustyStatus {
+    ffi_call(|| {
+        let path = read_str(path, "path")?;
+        let model = GradientBoostedDecisionTrees::json_load(path)?;
+        write_model(out, model)
+    })
+}
+
+/// Loads an XGBoost JSON model from `len` bytes at `data` into `*out`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_load_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut TrustyModel,
+) -> TrustyStatus {
+    ffi_call(|| {
+        if data.is_null() {
+            return Err(Error::invalid_argument("data is null"));
+        }
+        let bytes = std::slice::from_raw_parts(data, len);
+        let json = serde_json::from_slice(bytes).map_err(ModelError::from)?;
+        let model = GradientBoostedDecisionTrees::json_loads(&json)?;
+        write_model(out, model)
+    })
+}
+
+/// Frees a model; null is ignored.
+///
+/// # Safety
+///
+/// `model` must come from this library and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_free(model: *mut TrustyModel) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
+
+/// Number of trees of `model`, or 0 if it is null.
+///
+/// # Safety
+///
+/// `model` must be null or a live model.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_num_trees(model: *const TrustyModel) -> usize {
+    model_ref(model).map_or(0, |model| model.num_trees())
+}
+
+/// Number of nodes over all trees of `model`, or 0 if it is null.
+///
+/// # Safety
+///
+/// `model` must be null or a live model.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_num_nodes(model: *const TrustyModel) -> usize {
+    model_ref(model).map_or(0, |model| {
+        model.trees.iter().map(|tree| tree.num_nodes()).sum()
+    })
+}
+
+/// Number of features of `model`, or 0 if it is null.
+///
+/// # Safety
+///
+/// `model` must be null or a live model.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_num_features(model: *const TrustyModel) -> usize {
+    model_ref(model).map_or(0, |model| model.feature_names.len())
+}
+
+/// Writes the name of feature `index` of `model`, null-terminated, into `buffer` of
+/// `buffer_len` bytes and returns its length without the terminator. Nothing is written if the
+/// buffer is too small; returns 0 if `model` is null or `index` is out of range.
+///
+/// # Safety
+///
+/// `model` must be null or a live model, and `buffer` must point to `buffer_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_feature_name(
+    model: *const TrustyModel,
+    index: usize,
+    buffer: *mut c_char,
+    buffer_len: usize,
+) -> usize {
+    let Some(name) = model_ref(model)
+        .ok()
+        .and_then(|model| model.feature_names.get(index))
+    else {
+        return 0;
+    };
+    if !buffer.is_null() && name.len() < buffer_len {
+        ptr::copy_nonoverlapping(name.as_ptr(), buffer.cast(), name.len());
+        *buffer.add(name.len()) = 0;
+    }
+    name.len()
+}
+
+/// Scores the rows of a record batch, exported as a struct array with its schema, into a
+/// `float32` array.
+///
+/// Columns are matched to the model's features by name and cast to their types; other columns
+/// are ignored. The struct array itself must not have null rows. The input array is moved:
+/// trusty releases it, also on failure. The schema is only borrowed. On success `out_array`
+/// and `out_schema` hold the predictions, which the caller releases.
+///
+/// # Safety
+///
+/// All pointers must be valid; `array` and `schema` must follow the Arrow C Data Interface.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_predict(
+    model: *const TrustyModel,
+    array: *mut FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> TrustyStatus {
+    ffi_call(|| {
+        if array.is_null() {
+            return Err(Error::invalid_argument("array is null"));
+        }
+        let array = FFI_ArrowArray::from_raw(array);
+        let model = model_ref(model)?;
+        let schema = schema
+            .as_ref()
+            .ok_or_else(|| Error::invalid_argument("schema is null"))?;
+        if out_array.is_null() || out_schema.is_null() {
+            return Err(Error::invalid_argument("output is null"));
+        }
+
+        let data = from_ffi(array, schema)?;
+        if !matches!(data.data_type(), DataType::Struct(_)) {
+            return Err(Error::invalid_argument(format!(
+                "Expected a struct array of feature columns, got {}",
+                data.data_type()
+            )));
+        }
+        if data.null_count() > 0 {
+            return Err(Error::invalid_argument(
+                "The struct array of feature columns must not contain null rows",
+            ));
+        }
+        let batch = RecordBatch::from(StructArray::from(data));
+        let predictions = if model.required_features.is_empty() {
+            model.predict_dense(&[], batch.num_rows(), 0)?
+        } else {
+            let features: Vec<ArrayRef> = model.feature_arrays_by_name(&batch)?;
+            model.predict_arrays(&features)?
+        };
+
+        let predictions: ArrayRef = Arc::new(predictions);
+        let (ffi_array, ffi_schema) = to_ffi(&predictions.to_data())?;
+        ptr::write(out_array, ffi_array);
+        ptr::write(out_schema, ffi_schema);
+        Ok(())
+    })
+}
+
+/// Prunes `model` with `len` conditions into a new model in `*out`; `model` is unchanged.
+///
+/// # Safety
+///
+/// `model` must be a live model, `conditions` must point to `len` conditions with
+/// null-terminated feature names, and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn trusty_model_prune(
+    model: *const TrustyModel,
+    conditions: *const TrustyCondition,
+    len: usize,
+    out: *mut *mut TrustyModel,
+) -> TrustyStatus {
+    ffi_call(|| {
+        let model = model_ref(model)?;
+        if conditions.is_null() && len > 0 {
+            return Err(Error::invalid_argument("conditions is null"));
+        }
+        let conditions = if len == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(conditions, len)
+        };
+
+        let mut predicate = Predicate::new();
+        for condition in conditions {
+            let feature = read_str(condition.feature, "feature")?;
+            if !model.feature_names.iter().any(|name| name == feature) {
+                return Err(Error::invalid_argument(format!(
+                    "Unknown feature: {}",
+                    feature
+                )));
+            }
+            let condition_value = match condition.kind {
+                TRUSTY_CONDITION_KIND_LESS_THAN => Condition::LessThan(condition.value),
+                TRUSTY_CONDITION_KIND_GREATER_THAN_OR_EQUAL => {
+                    Condition::GreaterThanOrEqual(condition.value)
+                }
+                kind => {
+                    return Err(Error::invalid_argument(format!(
+                        "Unknown condition kind: {}",
+                        kind
+                    )))
+                }
+            };
+            predicate.add_condition(feature.to_string(), condition_value);
+        }
+        write_model(out, model.prune(&predicate))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float32Array, StructArray};
+    use arrow::buffer::NullBuffer;
+    use arrow::datatypes::Field;
+
+    fn load() -> *mut TrustyModel {
+        let path =
+            CString::new("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")
+                .unwrap();
+        let mut model = ptr::null_mut();
+        let status = unsafe { trusty_model_load(path.as_ptr(), &mut model) };
+        assert_eq!(status, TrustyStatus::Ok);
+        model
+    }
+
+    fn last_error() -> String {
+        let message = trusty_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_header_declares_exports() {
+        let header = include_str!("../include/trusty.h");
+        let exports: Vec<&str> = include_str!("capi.rs")
+            .lines()
+            .filter_map(|line| line.split_once("extern \"C\" fn "))
+            .filter_map(|(_, rest)| rest.split_once('('))
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(exports.len(), 10);
+        for name in exports {
+            assert!(header.contains(&format!("{}(", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut model = ptr::null_mut();
+        let path = CString::new("missing.json").unwrap();
+        let status = unsafe { trusty_model_load(path.as_ptr(), &mut model) };
+        assert_eq!(status, TrustyStatus::Io);
+        assert!(last_error().starts_with("Model IO Error"));
+        assert!(model.is_null());
+
+        let status = unsafe { trusty_model_load(ptr::null(), &mut model) };
+        assert_eq!(status, TrustyStatus::InvalidArgument);
+
+        let json = b"{\"learner\": {}}";
+        let status = unsafe { trusty_model_load_bytes(json.as_ptr(), json.len(), &mut model) };
+        assert_eq!(status, TrustyStatus::InvalidModel);
+
+        let model = load();
+        assert!(trusty_last_error().is_null());
+        assert_eq!(unsafe { trusty_model_num_trees(model) }, 100);
+        let mut buffer = [0 as c_char; 16];
+        let len = unsafe { trusty_model_feature_name(model, 0, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(len, 5);
+        assert_eq!(
+            unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_str(),
+            Ok("carat")
+        );
+        unsafe { trusty_model_free(model) };
+    }
+
+    #[test]
+    fn test_predict_and_prune() {
+        let model = load();
+        let rust_model = unsafe { &(*model).0 };
+        let columns: Vec<(Arc<Field>, ArrayRef)> = rust_model
+            .feature_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values: Float32Array =
+                    (0..4).map(|row| Some((row + i) as f32 * 0.25)).collect();
+                let field = Arc::new(Field::new(name, DataType::Float32, true));
+                (field, Arc::new(values) as ArrayRef)
+            })
+            .collect();
+        let batch = RecordBatch::from(StructArray::from(columns));
+        let expected = rust_model
+            .predict_arrays(&rust_model.feature_arrays_by_name(&batch).unwrap())
+            .unwrap();
+
+        let input: ArrayRef = Arc::new(StructArray::from(batch));
+        let (mut array, schema) = to_ffi(&input.to_data()).unwrap();
+        let mut out_array = FFI_ArrowArray::empty();
+        let mut out_schema = FFI_ArrowSchema::empty();
+        let status = unsafe {
+            trusty_model_predict(model, &mut array, &schema, &mut out_array, &mut out_schema)
+        };
+        assert_eq!(status, TrustyStatus::Ok);
+        assert!(array.is_released());
+        let output = unsafe { from_ffi(out_array, &out_schema) }.unwrap();
+        assert_eq!(Float32Array::from(output), expected);
+
+        let (fields, columns, _) = StructArray::from(input.to_data()).into_parts();
+        let nulls = NullBuffer::from(vec![true, false, true, true]);
+        let input: ArrayRef = Arc::new(StructArray::new(fields, columns, Some(nulls)));
+        let (mut array, schema) = to_ffi(&input.to_data()).unwrap();
+        let mut out_array = FFI_ArrowArray::empty();
+        let mut out_schema = FFI_ArrowSchema::empty();
+        let status = unsafe {
+            trusty_model_predict(model, &mut array, &schema, &mut out_array, &mut out_schema)
+        };
+        assert_eq!(status, TrustyStatus::InvalidArgument);
+        assert!(array.is_released());
+        assert!(out_array.is_released());
+
+        let feature = CString::new("carat").unwrap();
+        let conditions = [TrustyCondition {
+            feature: feature.as_ptr(),
+            kind: TRUSTY_CONDITION_KIND_LESS_THAN,
+            value: 0.3,
+        }];
+        let mut pruned = ptr::null_mut();
+        let status = unsafe { trusty_model_prune(model, conditions.as_ptr(), 1, &mut pruned) };
+        assert_eq!(status, TrustyStatus::Ok);
+        assert!(unsafe { trusty_model_num_nodes(pruned) < trusty_model_num_nodes(model) });
+
+        let unknown = CString::new("colour").unwrap();
+        let conditions = [TrustyCondition {
+            feature: unknown.as_ptr(),
+            kind: TRUSTY_CONDITION_KIND_GREATER_THAN_OR_EQUAL,
+            value: 1.0,
+        }];
+        let mut other = ptr::null_mut();
+        let status = unsafe { trusty_model_prune(model, conditions.as_ptr(), 1, &mut other) };
+        assert_eq!(status, TrustyStatus::InvalidArgument);
+        assert_eq!(last_error(), "Unknown feature: colour");
+
+        let conditions = [TrustyCondition {
+            feature: feature.as_ptr(),
+            kind: 7,
+            value: 1.0,
+        }];
+        let status = unsafe { trusty_model_prune(model, conditions.as_ptr(), 1, &mut other) };
+        assert_eq!(status, TrustyStatus::InvalidArgument);
+        assert_eq!(last_error(), "Unknown condition kind: 7");
+        assert!(other.is_null());
+
+        unsafe {
+            trusty_model_free(pruned);
+            trusty_model_free(model);
+        }
+    }
+}
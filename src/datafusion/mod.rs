@@ -0,0 +1,7 @@
+//! DataFusion integration, enabled by the `datafusion` cargo feature.
+
+mod pruning;
+mod udf;
+
+pub use pruning::PruneModelUdfs;
+pub use udf::{register_model_dir, TrustyUdf};
@@ -0,0 +1,651 @@
+use super::udf::TrustyUdf;
+use crate::predicates::{Condition, Predicate};
+use arrow::datatypes::DataType;
+use datafusion::common::config::ConfigOptions;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{Column, Result, ScalarValue};
+use datafusion::logical_expr::expr::ScalarFunction;
+use datafusion::logical_expr::utils::split_conjunction;
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Cast, Expr, ExprSchemable, Filter, LogicalPlan, Operator, Projection,
+    ScalarUDF, ScalarUDFImpl, TryCast,
+};
+use datafusion::optimizer::analyzer::AnalyzerRule;
+use std::sync::Arc;
+
+/// Analyzer rule that prunes the models of [`TrustyUdf`] calls using the query's filters.
+///
+/// Filter conjuncts comparing a column with a literal (`<`, `<=`, `>`, `>=`, `=`, `BETWEEN`,
+/// and bare or negated boolean columns) are traced back through projections and aliases to
+/// the column they read. A UDF call in a projection or filter whose arguments read such a
+/// column is replaced by a call to the pruned model, with the arguments of features the pruned
+/// trees no longer use dropped. Filters below the call always apply; filters above it apply
+/// when only projections, sorts, aliases and other filters lie in between, since the UDF values
+/// of the rows they discard are never observed.
+///
+/// Register it with `ctx.add_analyzer_rule(Arc::new(PruneModelUdfs))`.
+#[derive(Debug, Default)]
+pub struct PruneModelUdfs;
+
+impl AnalyzerRule for PruneModelUdfs {
+    fn analyze(&self, plan: LogicalPlan, _config: &ConfigOptions) -> Result<LogicalPlan> {
+        Ok(rewrite_plan(plan, &[])?.data)
+    }
+
+    fn name(&self) -> &str {
+        "prune_model_udfs"
+    }
+}
+
+/// What holds for every row that matters, on the column a value is read from.
+#[derive(Debug, Clone)]
+struct Fact {
+    column: Column,
+    kind: FactKind,
+}
+
+#[derive(Debug, Clone)]
+enum FactKind {
+    Condition(Condition),
+    /// The condition holds unless the value is NaN. DataFusion orders NaN above every number,
+    /// so NaN passes `col > v` and `col >= v`, while the model routes it as missing.
+    ConditionOrNan(Condition),
+    /// The value is not NaN, as after `NOT isnan(col)`.
+    NotNan,
+}
+
+/// Rewrites `plan`, where `above` holds for the rows whose output is kept by the operators above.
+fn rewrite_plan(plan: LogicalPlan, above: &[Fact]) -> Result<Transformed<LogicalPlan>> {
+    let mut facts = above.to_vec();
+    if let LogicalPlan::Filter(filter) = &plan {
+        facts.extend(conjunct_facts(&filter.predicate, &filter.input));
+    }
+    // Limits, aggregates, joins and windows combine or select rows using the values of rows
+    // that filters above would discard, so those filters don't carry through them.
+    let inherited: &[Fact] = match &plan {
+        LogicalPlan::Filter(_)
+        | LogicalPlan::Projection(_)
+        | LogicalPlan::Sort(_)
+        | LogicalPlan::SubqueryAlias(_) => &facts,
+        _ => &[],
+    };
+    let plan = plan.map_children(|input| rewrite_plan(input, inherited))?;
+    plan.transform_data(|plan| rewrite_calls(plan, &facts))
+}
+
+fn rewrite_calls(plan: LogicalPlan, above: &[Fact]) -> Result<Transformed<LogicalPlan>> {
+    match plan {
+        LogicalPlan::Projection(projection) => {
+            let facts = [above, &below_facts(&projection.input)].concat();
+            let mut changed = false;
+            let mut exprs = Vec::with_capacity(projection.expr.len());
+            for (i, expr) in projection.expr.iter().enumerate() {
+                let rewritten = prune_calls(expr.clone(), &projection.input, &facts)?;
+                changed |= rewritten.transformed;
+                exprs.push(match rewritten.data {
+                    // New arguments change the derived name that operators above refer to.
+                    expr if rewritten.transformed && !matches!(expr, Expr::Alias(_)) => {
+                        let (qualifier, field) = projection.schema.qualified_field(i);
+                        expr.alias_qualified(qualifier.cloned(), field.name())
+                    }
+                    expr => expr,
+                });
+            }
+            if !changed {
+                return Ok(Transformed::no(LogicalPlan::Projection(projection)));
+            }
+            let projection = Projection::try_new(exprs, Arc::clone(&projection.input))?;
+            Ok(Transformed::yes(LogicalPlan::Projection(projection)))
+        }
+        LogicalPlan::Filter(filter) => {
+            let facts = [above, &below_facts(&filter.input)].concat();
+            let rewritten = prune_calls(filter.predicate.clone(), &filter.input, &facts)?;
+            if !rewritten.transformed {
+                return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+            }
+            let filter = Filter::try_new(rewritten.data, Arc::clone(&filter.input))?;
+            Ok(Transformed::yes(LogicalPlan::Filter(filter)))
+        }
+        plan => Ok(Transformed::no(plan)),
+    }
+}
+
+/// Replaces the model UDF calls in `expr`, which is evaluated on the rows of `input`.
+fn prune_calls(expr: Expr, input: &LogicalPlan, facts: &[Fact]) -> Result<Transformed<Expr>> {
+    if facts.is_empty() {
+        return Ok(Transformed::no(expr));
+    }
+    expr.transform_up(|expr| {
+        if let Expr::ScalarFunction(call) = &expr {
+            if let Some(pruned) = pruned_call(call, input, facts) {
+                return Ok(Transformed::yes(pruned));
+            }
+        }
+        Ok(Transformed::no(expr))
+    })
+}
+
+fn pruned_call(call: &ScalarFunction, input: &LogicalPlan, facts: &[Fact]) -> Option<Expr> {
+    let udf = call.func.inner().as_any().downcast_ref::<TrustyUdf>()?;
+    // Multi-output models are left as they are.
+    let model = udf.model()?;
+    let parameters = udf.parameter_names();
+    if parameters.len() != call.args.len() {
+        return None;
+    }
+
+    let mut predicate = Predicate::new();
+    for (arg, name) in call.args.iter().zip(parameters) {
+        let Some((column, data_type)) = source_column(arg, input) else {
+            continue;
+        };
+        let facts: Vec<&Fact> = facts.iter().filter(|fact| fact.column == column).collect();
+        // Conditions and `NotNan` are false for NaN, so any of them rules it out.
+        let not_nan = !data_type.is_floating()
+            || facts
+                .iter()
+                .any(|fact| !matches!(fact.kind, FactKind::ConditionOrNan(_)));
+        for fact in facts {
+            let condition = match &fact.kind {
+                FactKind::Condition(condition) => condition,
+                FactKind::ConditionOrNan(condition) if not_nan => condition,
+                _ => continue,
+            };
+            let condition = read_as_f32(condition.clone(), &data_type);
+            predicate.add_condition(name.to_string(), condition);
+        }
+    }
+    if predicate.conditions.is_empty() {
+        return None;
+    }
+
+    let pruned = model.prune(&predicate);
+    let args = call
+        .args
+        .iter()
+        .zip(model.original_feature_indices())
+        .filter(|(_, feature)| pruned.required_features.contains(feature))
+        .map(|(arg, _)| arg.clone())
+        .collect();
+    let func = ScalarUDF::from(TrustyUdf::new(udf.name(), pruned));
+    Some(Expr::ScalarFunction(ScalarFunction::new_udf(
+        Arc::new(func),
+        args,
+    )))
+}
+
+/// Facts established by the filters in `plan` and the row-preserving operators below it.
+fn below_facts(plan: &LogicalPlan) -> Vec<Fact> {
+    match plan {
+        LogicalPlan::Filter(filter) => {
+            let mut facts = conjunct_facts(&filter.predicate, &filter.input);
+            facts.extend(below_facts(&filter.input));
+            facts
+        }
+        LogicalPlan::Projection(_)
+        | LogicalPlan::Sort(_)
+        | LogicalPlan::Limit(_)
+        | LogicalPlan::SubqueryAlias(_) => {
+            plan.inputs().into_iter().flat_map(below_facts).collect()
+        }
+        _ => vec![],
+    }
+}
+
+fn conjunct_facts(predicate: &Expr, input: &LogicalPlan) -> Vec<Fact> {
+    split_conjunction(predicate)
+        .into_iter()
+        .flat_map(conditions)
+        .filter_map(|(expr, kind)| {
+            Some(Fact {
+                column: source_column(expr, input)?.0,
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Facts that a conjunct establishes about the expressions it compares with literals.
+fn conditions(conjunct: &Expr) -> Vec<(&Expr, FactKind)> {
+    match conjunct {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let (expr, op, value) = match (left.as_ref(), right.as_ref()) {
+                (expr, Expr::Literal(value)) => (expr, *op, value),
+                (Expr::Literal(value), expr) => match op.swap() {
+                    Some(op) => (expr, op, value),
+                    None => return vec![],
+                },
+                _ => return vec![],
+            };
+            let Some(value) = literal_value(value) else {
+                return vec![];
+            };
+            let kinds = match op {
+                Operator::Lt => vec![FactKind::Condition(Condition::LessThan(value))],
+                Operator::LtEq => vec![FactKind::Condition(Condition::LessThan(next_up(value)))],
+                Operator::Gt => vec![FactKind::ConditionOrNan(Condition::GreaterThanOrEqual(
+                    next_up(value),
+                ))],
+                Operator::GtEq => vec![FactKind::ConditionOrNan(Condition::GreaterThanOrEqual(
+                    value,
+                ))],
+                Operator::Eq => equal_to(value),
+                _ => vec![],
+            };
+            kinds.into_iter().map(|kind| (expr, kind)).collect()
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => match (low.as_ref(), high.as_ref()) {
+            (Expr::Literal(low), Expr::Literal(high)) => {
+                // NaN fails the upper bound, so both bounds hold.
+                match (literal_value(low), literal_value(high)) {
+                    (Some(low), Some(high)) => vec![
+                        (
+                            expr.as_ref(),
+                            FactKind::Condition(Condition::GreaterThanOrEqual(low)),
+                        ),
+                        (
+                            expr.as_ref(),
+                            FactKind::Condition(Condition::LessThan(next_up(high))),
+                        ),
+                    ],
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        },
+        // Indicator features read true as 1 and false as 0.
+        Expr::Column(_) => equal_to(1.0)
+            .into_iter()
+            .map(|kind| (conjunct, kind))
+            .collect(),
+        Expr::Not(expr) if matches!(expr.as_ref(), Expr::Column(_)) => equal_to(0.0)
+            .into_iter()
+            .map(|kind| (expr.as_ref(), kind))
+            .collect(),
+        Expr::Not(expr) => match expr.as_ref() {
+            Expr::ScalarFunction(ScalarFunction { func, args }) if func.name() == "isnan" => {
+                match args.as_slice() {
+                    [arg] => vec![(arg, FactKind::NotNan)],
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn equal_to(value: f64) -> Vec<FactKind> {
+    vec![
+        FactKind::Condition(Condition::GreaterThanOrEqual(value)),
+        FactKind::Condition(Condition::LessThan(next_up(value))),
+    ]
+}
+
+/// Smallest `f64` above `value`, so that `x <= value` becomes `x < next_up(value)`.
+fn next_up(value: f64) -> f64 {
+    if value.is_nan() || value == f64::INFINITY {
+        return value;
+    }
+    if value == 0.0 {
+        return f64::from_bits(1);
+    }
+    let bits = value.to_bits();
+    f64::from_bits(if value > 0.0 { bits + 1 } else { bits - 1 })
+}
+
+fn literal_value(value: &ScalarValue) -> Option<f64> {
+    let value = match value {
+        ScalarValue::Float32(Some(v)) => *v as f64,
+        ScalarValue::Float64(Some(v)) => *v,
+        ScalarValue::Int8(Some(v)) => *v as f64,
+        ScalarValue::Int16(Some(v)) => *v as f64,
+        ScalarValue::Int32(Some(v)) => *v as f64,
+        ScalarValue::Int64(Some(v)) => *v as f64,
+        ScalarValue::UInt8(Some(v)) => *v as f64,
+        ScalarValue::UInt16(Some(v)) => *v as f64,
+        ScalarValue::UInt32(Some(v)) => *v as f64,
+        ScalarValue::UInt64(Some(v)) => *v as f64,
+        ScalarValue::Boolean(Some(v)) => f64::from(u8::from(*v)),
+        _ => return None,
+    };
+    (!value.is_nan()).then_some(value)
+}
+
+/// Column of `plan`'s output that `expr` reads, and the column's type. Only casts to floating
+/// point that keep every value are looked through, so `expr` and the column are equal.
+fn source_column(expr: &Expr, plan: &LogicalPlan) -> Option<(Column, DataType)> {
+    match expr {
+        Expr::Column(column) => {
+            let data_type = plan.schema().field_from_column(column).ok()?.data_type();
+            Some((resolve_column(column, plan)?, data_type.clone()))
+        }
+        Expr::Cast(Cast { expr, data_type }) | Expr::TryCast(TryCast { expr, data_type }) => {
+            let from = expr.get_type(plan.schema().as_ref()).ok()?;
+            let exact = match data_type {
+                DataType::Float32 => is_f32_exact(&from),
+                DataType::Float64 => {
+                    is_f32_exact(&from)
+                        || matches!(from, DataType::Float64 | DataType::Int32 | DataType::UInt32)
+                }
+                _ => false,
+            };
+            if exact {
+                source_column(expr, plan)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether every value of `data_type` is an `f32`, so the model reads it unchanged.
+fn is_f32_exact(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::Float16
+            | DataType::Float32
+    )
+}
+
+/// Turns `condition` on the values of a column of type `data_type` into a condition on the
+/// `f32` values the model reads from it.
+///
+/// Rounding to `f32` preserves order only non-strictly: `b < 0.49999999999` allows `b` values
+/// that round to 0.5. The bounds are therefore widened to the nearest `f32` outside of them.
+/// Integers below 2^24 in magnitude are exact, so integer bounds in that range stay as they are.
+fn read_as_f32(condition: Condition, data_type: &DataType) -> Condition {
+    const EXACT_INTEGERS: f64 = (1 << 24) as f64;
+    let bound = match condition {
+        Condition::LessThan(v) | Condition::GreaterThanOrEqual(v) => v,
+    };
+    if is_f32_exact(data_type) || (data_type.is_integer() && bound.abs() < EXACT_INTEGERS) {
+        return condition;
+    }
+    match condition {
+        // `b < v` gives `f32(b) <= f32(v)`, and `f32(v)` is at most the first `f32` above `v`.
+        Condition::LessThan(v) => Condition::LessThan(next_up(f32_at_or_above(v))),
+        Condition::GreaterThanOrEqual(v) => Condition::GreaterThanOrEqual(-f32_at_or_above(-v)),
+    }
+}
+
+/// Smallest `f32` at or above `value`, as an `f64`.
+fn f32_at_or_above(value: f64) -> f64 {
+    let rounded = value as f32;
+    if (rounded as f64) >= value || rounded == f32::INFINITY {
+        return rounded as f64;
+    }
+    let bits = rounded.to_bits();
+    let up = if rounded == 0.0 {
+        f32::from_bits(1)
+    } else if rounded > 0.0 {
+        f32::from_bits(bits + 1)
+    } else {
+        f32::from_bits(bits - 1)
+    };
+    up as f64
+}
+
+/// Traces a column of `plan` down through projections, filters, sorts, limits and aliases to
+/// the operator that computes it, so that columns from different levels of the plan compare.
+fn resolve_column(column: &Column, plan: &LogicalPlan) -> Option<Column> {
+    let index = plan.schema().index_of_column(column).ok()?;
+    match plan {
+        LogicalPlan::Projection(projection) => {
+            let mut expr = &projection.expr[index];
+            while let Expr::Alias(alias) = expr {
+                expr = &alias.expr;
+            }
+            match expr {
+                Expr::Column(column) => resolve_column(column, &projection.input),
+                _ => None,
+            }
+        }
+        LogicalPlan::Filter(_)
+        | LogicalPlan::Sort(_)
+        | LogicalPlan::Limit(_)
+        | LogicalPlan::SubqueryAlias(_) => {
+            let input = plan.inputs()[0];
+            let column = Column::from(input.schema().qualified_field(index));
+            resolve_column(&column, input)
+        }
+        _ => Some(Column::from(plan.schema().qualified_field(index))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{FeatureType, GradientBoostedDecisionTrees, VecTreeNodes};
+    use arrow::array::{Array, Float32Array, Float64Array, Int64Array};
+    use arrow::datatypes::{Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::tree_node::TreeNodeRecursion;
+    use datafusion::prelude::SessionContext;
+
+    // score(b, c): b < 0.5 (missing goes left) -> -1, else c < 10 -> 2, else 5.
+    fn model() -> GradientBoostedDecisionTrees {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, 1, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 10.0, 0.0, 0.0])
+            .children(
+                vec![1, u32::MAX, 3, u32::MAX, u32::MAX],
+                vec![2, u32::MAX, 4, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, -1.0, 0.0, 2.0, 5.0])
+            .default_left(vec![true, false, false, false, false])
+            .build()
+            .unwrap();
+        GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["b".into(), "c".into()]),
+            feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Int]),
+            required_features: [0, 1].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    async fn context() -> Result<SessionContext> {
+        let ctx = SessionContext::new();
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("b", DataType::Float32, true),
+                Field::new("c", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Float32Array::from(vec![
+                    Some(0.0),
+                    Some(1.0),
+                    Some(2.0),
+                    None,
+                ])),
+                Arc::new(Int64Array::from(vec![20, 5, 20, 5])),
+            ],
+        )?;
+        ctx.register_batch("t", batch)?;
+        ctx.register_udf(ScalarUDF::from(TrustyUdf::new("score", model())));
+        ctx.add_analyzer_rule(Arc::new(PruneModelUdfs));
+        Ok(ctx)
+    }
+
+    /// Parameters of every model UDF call in the optimized plan, and the query's first column.
+    async fn run(ctx: &SessionContext, sql: &str) -> Result<(Vec<Vec<String>>, Vec<f32>)> {
+        let df = ctx.sql(sql).await?;
+        let plan = df.clone().into_optimized_plan()?;
+        let mut calls = Vec::new();
+        plan.apply(|node| {
+            node.apply_expressions(|expr| {
+                expr.apply(|expr| {
+                    if let Expr::ScalarFunction(call) = expr {
+                        if let Some(udf) = call.func.inner().as_any().downcast_ref::<TrustyUdf>() {
+                            let names = udf.parameter_names().into_iter().map(String::from);
+                            calls.push(names.collect());
+                        }
+                    }
+                    Ok(TreeNodeRecursion::Continue)
+                })
+            })
+        })?;
+
+        let mut values = Vec::new();
+        for batch in df.collect().await? {
+            let column = batch.column(0);
+            let column = column.as_any().downcast_ref::<Float32Array>().unwrap();
+            values.extend(column.values().iter().copied());
+        }
+        Ok((calls, values))
+    }
+
+    #[tokio::test]
+    async fn test_filters_below_and_above_prune_the_model() -> Result<()> {
+        let ctx = context().await?;
+
+        let (calls, values) = run(&ctx, "SELECT score(b, c) FROM t WHERE b >= 0.5").await?;
+        assert_eq!(calls, [["c"]]);
+        assert_eq!(values, [2.0, 5.0]);
+
+        let (calls, values) = run(&ctx, "SELECT score(b, c) FROM t WHERE c < 10").await?;
+        assert_eq!(calls, [["b"]]);
+        assert_eq!(values, [2.0, -1.0]);
+
+        let sql = "SELECT s FROM (SELECT score(b, c) AS s, b AS x FROM t) AS q WHERE x > 0.5";
+        let (calls, values) = run(&ctx, sql).await?;
+        assert_eq!(calls, [["c"]]);
+        assert_eq!(values, [2.0, 5.0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_float64_bounds_allow_for_f32_rounding() -> Result<()> {
+        let ctx = context().await?;
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("b", DataType::Float64, false),
+                Field::new("c", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Float64Array::from(vec![0.4999999999, 1.0, 0.2])),
+                Arc::new(Int64Array::from(vec![20, 5, 20])),
+            ],
+        )?;
+        ctx.register_batch("t64", batch)?;
+
+        // 0.4999999999 passes the filter but reads as 0.5 in f32, which goes right.
+        let sql = "SELECT score(b, c) FROM t64 WHERE b < 0.49999999999";
+        let (calls, values) = run(&ctx, sql).await?;
+        assert_eq!(calls, [["b", "c"]]);
+        assert_eq!(values, [5.0, -1.0]);
+
+        let (calls, values) = run(&ctx, "SELECT score(b, c) FROM t64 WHERE b >= 0.75").await?;
+        assert_eq!(calls, [["c"]]);
+        assert_eq!(values, [2.0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lower_bounds_need_nan_ruled_out() -> Result<()> {
+        let ctx = context().await?;
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("b", DataType::Float32, false),
+                Field::new("c", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Float32Array::from(vec![f32::NAN, 1.0, 0.0])),
+                Arc::new(Int64Array::from(vec![5, 20, 20])),
+            ],
+        )?;
+        ctx.register_batch("tnan", batch)?;
+
+        // NaN passes the filter and goes left in the model, as a missing value.
+        let (calls, values) = run(&ctx, "SELECT score(b, c) FROM tnan WHERE b >= 0.5").await?;
+        assert_eq!(calls, [["b", "c"]]);
+        assert_eq!(values, [-1.0, 5.0]);
+
+        for sql in [
+            "SELECT score(b, c) FROM tnan WHERE b > 0.5 AND NOT isnan(b)",
+            "SELECT score(b, c) FROM tnan WHERE b >= 0.5 AND b < 2",
+            "SELECT score(b, c) FROM tnan WHERE b BETWEEN 0.5 AND 2",
+        ] {
+            let (calls, values) = run(&ctx, sql).await?;
+            assert_eq!(calls, [["c"]], "{}", sql);
+            assert_eq!(values, [5.0], "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_filters_are_ignored() -> Result<()> {
+        let ctx = context().await?;
+        for sql in [
+            "SELECT score(b, c) FROM t WHERE b >= 0.5 OR c < 10",
+            "SELECT score(b, c) FROM t WHERE b + 1 >= 1.5",
+            // The limit picks rows by score, including rows the outer filter drops.
+            "SELECT s FROM (SELECT score(b, c) AS s, b FROM t ORDER BY s LIMIT 2) AS q WHERE b >= 0.5",
+        ] {
+            let (calls, _) = run(&ctx, sql).await?;
+            assert_eq!(calls, [["b", "c"]], "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditions() {
+        let b = || Box::new(Expr::Column(Column::from_name("b")));
+        let literal = |v: f64| Box::new(Expr::Literal(ScalarValue::Float64(Some(v))));
+        let compare = |left, op, right| Expr::BinaryExpr(BinaryExpr { left, op, right });
+
+        let le = conditions(&compare(b(), Operator::LtEq, literal(2.0)));
+        assert!(matches!(
+            le[..],
+            [(_, FactKind::Condition(Condition::LessThan(v)))] if v > 2.0 && v < 2.0 + 1e-12
+        ));
+        let flipped = conditions(&compare(literal(2.0), Operator::Lt, b()));
+        assert!(matches!(
+            flipped[..],
+            [(_, FactKind::ConditionOrNan(Condition::GreaterThanOrEqual(v)))] if v > 2.0
+        ));
+        assert!(conditions(&compare(b(), Operator::NotEq, literal(2.0))).is_empty());
+        assert_eq!(conditions(&Expr::Not(b())).len(), 2);
+        assert_eq!(next_up(-1.0), -1.0 + f64::EPSILON / 2.0);
+    }
+
+    #[test]
+    fn test_read_as_f32() {
+        let below_half = Condition::LessThan(0.49999999999);
+        assert!(matches!(
+            read_as_f32(below_half.clone(), &DataType::Float64),
+            Condition::LessThan(v) if v == next_up(0.5)
+        ));
+        assert!(matches!(
+            read_as_f32(below_half, &DataType::Float32),
+            Condition::LessThan(v) if v == 0.49999999999
+        ));
+        assert!(matches!(
+            read_as_f32(Condition::GreaterThanOrEqual(0.50000000001), &DataType::Float64),
+            Condition::GreaterThanOrEqual(v) if v == 0.5
+        ));
+        assert!(matches!(
+            read_as_f32(Condition::GreaterThanOrEqual(-0.1), &DataType::Float64),
+            Condition::GreaterThanOrEqual(v) if v == -(0.1_f32 as f64)
+        ));
+        assert!(matches!(
+            read_as_f32(Condition::LessThan(10.0), &DataType::Int64),
+            Condition::LessThan(v) if v == 10.0
+        ));
+        assert_eq!(f32_at_or_above(0.0), 0.0);
+        assert_eq!(f32_at_or_above(1e-50), f32::from_bits(1) as f64);
+        assert_eq!(f32_at_or_above(-1e-50), 0.0);
+    }
+}
@@ -0,0 +1,525 @@
+use crate::loader::{ModelError, ModelLoader, XGBoostParser};
+use crate::tree::{FeatureType, GradientBoostedDecisionTrees, MultiOutputTrees};
+use arrow::array::ArrayRef;
+use arrow::datatypes::DataType;
+use datafusion::common::{plan_err, Column, DataFusionError, Result, ScalarValue};
+use datafusion::logical_expr::{
+    ColumnarValue, Expr, ScalarUDF, ScalarUDFImpl, Signature, Volatility,
+};
+use datafusion::prelude::SessionContext;
+use std::any::Any;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Scalar UDF that scores rows with a model.
+///
+/// The UDF takes one argument per feature used by the trees, in the order of
+/// [`original_feature_indices`](GradientBoostedDecisionTrees::original_feature_indices), and
+/// names its parameters after those features. SQL calls pass the arguments in that order, while
+/// [`call_with_named_args`](Self::call_with_named_args) binds expressions to parameters by name.
+/// Arguments are cast to the feature's type (`Float32`, `Int64` or `Boolean`) during planning.
+///
+/// Single-output models return a `Float32` score. [`MultiOutputTrees`], such as multiclass
+/// models, return a `FixedSizeList` of one `Float32` per output.
+#[derive(Debug, Clone)]
+pub struct TrustyUdf {
+    name: String,
+    model: UdfModel,
+    signature: Signature,
+}
+
+#[derive(Debug, Clone)]
+enum UdfModel {
+    Single(Arc<GradientBoostedDecisionTrees>),
+    Multi(Arc<MultiOutputTrees>),
+}
+
+impl UdfModel {
+    fn original_feature_indices(&self) -> Vec<usize> {
+        match self {
+            UdfModel::Single(model) => model.original_feature_indices(),
+            UdfModel::Multi(model) => model.original_feature_indices(),
+        }
+    }
+
+    fn feature_names(&self) -> &[String] {
+        match self {
+            UdfModel::Single(model) => &model.feature_names,
+            UdfModel::Multi(model) => model.feature_names(),
+        }
+    }
+
+    fn feature_types(&self) -> &[FeatureType] {
+        match self {
+            UdfModel::Single(model) => &model.feature_types,
+            UdfModel::Multi(model) => model.feature_types(),
+        }
+    }
+
+    fn output_type(&self) -> DataType {
+        match self {
+            UdfModel::Single(_) => DataType::Float32,
+            UdfModel::Multi(model) => model.output_type(),
+        }
+    }
+
+    fn predict(&self, arrays: &[ArrayRef], num_rows: usize) -> Result<ArrayRef> {
+        let predictions: ArrayRef = match self {
+            UdfModel::Single(model) if arrays.is_empty() => {
+                Arc::new(model.predict_dense(&[], num_rows, 0)?)
+            }
+            UdfModel::Single(model) => Arc::new(model.predict_arrays(arrays)?),
+            UdfModel::Multi(model) => Arc::new(model.predict_rows(arrays, num_rows)?),
+        };
+        Ok(predictions)
+    }
+}
+
+impl TrustyUdf {
+    pub fn new(name: impl Into<String>, model: GradientBoostedDecisionTrees) -> Self {
+        Self::from_arc(name, Arc::new(model))
+    }
+
+    pub fn from_arc(name: impl Into<String>, model: Arc<GradientBoostedDecisionTrees>) -> Self {
+        Self::with_model(name, UdfModel::Single(model))
+    }
+
+    pub fn multi_output(name: impl Into<String>, model: Arc<MultiOutputTrees>) -> Self {
+        Self::with_model(name, UdfModel::Multi(model))
+    }
+
+    fn with_model(name: impl Into<String>, model: UdfModel) -> Self {
+        // User-defined signatures reject calls without arguments.
+        let signature = if model.original_feature_indices().is_empty() {
+            Signature::exact(vec![], Volatility::Immutable)
+        } else {
+            Signature::user_defined(Volatility::Immutable)
+        };
+        Self {
+            name: name.into(),
+            model,
+            signature,
+        }
+    }
+
+    /// The model scored by the UDF, unless it is a [`MultiOutputTrees`].
+    pub fn model(&self) -> Option<&Arc<GradientBoostedDecisionTrees>> {
+        match &self.model {
+            UdfModel::Single(model) => Some(model),
+            UdfModel::Multi(_) => None,
+        }
+    }
+
+    pub fn multi_output_model(&self) -> Option<&Arc<MultiOutputTrees>> {
+        match &self.model {
+            UdfModel::Single(_) => None,
+            UdfModel::Multi(model) => Some(model),
+        }
+    }
+
+    /// Names of the UDF's parameters, in argument order.
+    pub fn parameter_names(&self) -> Vec<&str> {
+        let names = self.model.feature_names();
+        self.model
+            .original_feature_indices()
+            .into_iter()
+            .map(|index| names[index].as_str())
+            .collect()
+    }
+
+    /// Calls the UDF with each argument bound to the parameter it is named after: columns by
+    /// their name and other expressions by their alias, in any order. Parameters without an
+    /// argument read the column of the same name.
+    pub fn call_with_named_args(&self, args: Vec<Expr>) -> Result<Expr> {
+        let parameters = self.parameter_names();
+        let mut bound: Vec<Option<Expr>> = vec![None; parameters.len()];
+        for arg in args {
+            let name = match &arg {
+                Expr::Alias(alias) => alias.name.clone(),
+                Expr::Column(column) => column.name.clone(),
+                _ => {
+                    return plan_err!(
+                        "Arguments of {} must be columns or aliased expressions, got {}",
+                        self.name,
+                        arg
+                    )
+                }
+            };
+            let Some(position) = parameters.iter().position(|parameter| *parameter == name) else {
+                return plan_err!(
+                    "{} has no parameter named {} (expected one of {})",
+                    self.name,
+                    name,
+                    parameters.join(", ")
+                );
+            };
+            if bound[position].is_some() {
+                return plan_err!("{} got several arguments named {}", self.name, name);
+            }
+            bound[position] = Some(arg.unalias());
+        }
+        let args = bound
+            .into_iter()
+            .zip(&parameters)
+            .map(|(arg, name)| arg.unwrap_or_else(|| Expr::Column(Column::from_name(*name))))
+            .collect();
+        Ok(ScalarUDF::from(self.clone()).call(args))
+    }
+
+    /// Calls the UDF with each parameter bound to the column of the same name.
+    pub fn call_by_name(&self) -> Expr {
+        let args = self
+            .parameter_names()
+            .into_iter()
+            .map(|name| Expr::Column(Column::from_name(name)))
+            .collect();
+        ScalarUDF::from(self.clone()).call(args)
+    }
+
+    fn parameter_types(&self) -> Vec<DataType> {
+        let types = self.model.feature_types();
+        self.model
+            .original_feature_indices()
+            .into_iter()
+            .map(|index| match types[index] {
+                FeatureType::Float => DataType::Float32,
+                FeatureType::Int => DataType::Int64,
+                FeatureType::Indicator => DataType::Boolean,
+            })
+            .collect()
+    }
+}
+
+impl ScalarUDFImpl for TrustyUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(self.model.output_type())
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        let parameters = self.parameter_types();
+        if arg_types.len() != parameters.len() {
+            return plan_err!(
+                "{} expects {} arguments ({}), got {}",
+                self.name,
+                parameters.len(),
+                self.parameter_names().join(", "),
+                arg_types.len()
+            );
+        }
+        Ok(parameters)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let scalar_args = args
+            .iter()
+            .all(|arg| matches!(arg, ColumnarValue::Scalar(_)));
+        let arrays = ColumnarValue::values_to_arrays(args)?;
+        let num_rows = arrays.first().map_or(1, |array| array.len());
+        let predictions = self.model.predict(&arrays, num_rows)?;
+        if scalar_args {
+            return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &predictions,
+                0,
+            )?));
+        }
+        Ok(ColumnarValue::Array(predictions))
+    }
+
+    /// Models that use no features, such as fully pruned ones, predict a constant.
+    fn invoke_no_args(&self, number_rows: usize) -> Result<ColumnarValue> {
+        Ok(ColumnarValue::Array(self.model.predict(&[], number_rows)?))
+    }
+}
+
+/// Registers every `*.json` model in `dir` as a UDF named after the file stem, and returns
+/// the registered names in sorted order. Models with several outputs per row are loaded as
+/// [`MultiOutputTrees`].
+pub fn register_model_dir(ctx: &SessionContext, dir: impl AsRef<Path>) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut names = Vec::with_capacity(paths.len());
+    for path in paths {
+        let (Some(name), Some(path_str)) =
+            (path.file_stem().and_then(|s| s.to_str()), path.to_str())
+        else {
+            return plan_err!("Model path is not valid UTF-8: {}", path.display());
+        };
+        let udf = load_udf(name, path_str).map_err(|e| DataFusionError::External(Box::new(e)))?;
+        ctx.register_udf(ScalarUDF::from(udf));
+        names.push(name.to_string());
+    }
+    Ok(names)
+}
+
+fn load_udf(name: &str, path: &str) -> Result<TrustyUdf, ModelError> {
+    let data = std::fs::read_to_string(path).map_err(|e| ModelError::IoError(e.to_string()))?;
+    let json: serde_json::Value = serde_json::from_str(&data)?;
+    if XGBoostParser::parse_num_outputs(&json)? > 1 {
+        let model = MultiOutputTrees::json_loads(&json)?;
+        Ok(TrustyUdf::multi_output(name, Arc::new(model)))
+    } else {
+        Ok(TrustyUdf::new(
+            name,
+            GradientBoostedDecisionTrees::json_loads(&json)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::VecTreeNodes;
+    use arrow::array::{Array, BooleanArray, Float32Array, Float64Array, Int64Array};
+    use arrow::datatypes::{Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::prelude::{col, lit};
+
+    // Uses "b" (float) and "c" (int) of three features.
+    fn model() -> GradientBoostedDecisionTrees {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, 1, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 10.0, 0.0, 0.0])
+            .children(
+                vec![1, u32::MAX, 3, u32::MAX, u32::MAX],
+                vec![2, u32::MAX, 4, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, -1.0, 0.0, 2.0, 5.0])
+            .default_left(vec![true, false, false, false, false])
+            .build()
+            .unwrap();
+        GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["a".into(), "b".into(), "c".into()]),
+            feature_types: Arc::new(vec![
+                FeatureType::Float,
+                FeatureType::Float,
+                FeatureType::Int,
+            ]),
+            required_features: [1, 2].into_iter().collect(),
+            base_score: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parameters_and_coercion() {
+        let udf = TrustyUdf::new("score", model());
+        assert_eq!(udf.parameter_names(), ["b", "c"]);
+        assert_eq!(
+            udf.coerce_types(&[DataType::Float64, DataType::Int32])
+                .unwrap(),
+            [DataType::Float32, DataType::Int64]
+        );
+        assert!(udf.coerce_types(&[DataType::Float32]).is_err());
+        assert_eq!(udf.return_type(&[]).unwrap(), DataType::Float32);
+    }
+
+    #[test]
+    fn test_invoke_scalars_and_arrays() {
+        let udf = TrustyUdf::new("score", model());
+        let scalars = [
+            ColumnarValue::Scalar(ScalarValue::Float32(Some(1.0))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(20))),
+        ];
+        match udf.invoke(&scalars).unwrap() {
+            ColumnarValue::Scalar(value) => assert_eq!(value, ScalarValue::Float32(Some(5.5))),
+            ColumnarValue::Array(_) => panic!("expected a scalar"),
+        }
+
+        let mixed = [
+            ColumnarValue::Array(Arc::new(Float32Array::from(vec![
+                Some(0.0),
+                Some(1.0),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(5))),
+        ];
+        let ColumnarValue::Array(predictions) = udf.invoke(&mixed).unwrap() else {
+            panic!("expected an array");
+        };
+        let predictions = predictions.as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(predictions.values(), &[-0.5, 2.5, -0.5]);
+    }
+
+    #[test]
+    fn test_invoke_without_features() {
+        let leaf = VecTreeNodes::builder()
+            .split_indices(vec![-1])
+            .split_conditions(vec![0.0])
+            .children(vec![u32::MAX], vec![u32::MAX])
+            .base_weights(vec![1.5])
+            .default_left(vec![false])
+            .build()
+            .unwrap();
+        let udf = TrustyUdf::new(
+            "constant",
+            GradientBoostedDecisionTrees {
+                trees: vec![leaf],
+                base_score: 0.5,
+                ..Default::default()
+            },
+        );
+        assert!(udf.parameter_names().is_empty());
+        let ColumnarValue::Array(predictions) = udf.invoke_no_args(3).unwrap() else {
+            panic!("expected an array");
+        };
+        let predictions = predictions.as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(predictions.values(), &[2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_call_with_named_args() {
+        let udf = TrustyUdf::new("score", model());
+        let Expr::ScalarFunction(call) = udf
+            .call_with_named_args(vec![(col("x") + lit(1)).alias("c")])
+            .unwrap()
+        else {
+            panic!("expected a function call");
+        };
+        assert_eq!(call.args, [col("b"), col("x") + lit(1)]);
+
+        for args in [
+            vec![lit(1.0)],
+            vec![col("a")],
+            vec![col("b"), lit(1.0).alias("b")],
+        ] {
+            assert!(udf.call_with_named_args(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_register_model_dir_with_multi_output_models() -> Result<()> {
+        let path = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path)?).unwrap();
+        json["learner"]["objective"]["name"] = "multi:softprob".into();
+        json["learner"]["learner_model_param"]["num_class"] = "4".into();
+        let trees = &mut json["learner"]["gradient_booster"]["model"];
+        let num_trees = trees["trees"].as_array().unwrap().len();
+        trees["tree_info"] = (0..num_trees).map(|tree| tree % 4).collect();
+
+        let dir = std::env::temp_dir().join(format!("trusty-udf-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("multiclass.json"), json.to_string())?;
+        let ctx = SessionContext::new();
+        let names = register_model_dir(&ctx, &dir);
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(names?, ["multiclass"]);
+
+        let state = ctx.state();
+        let func = &state.scalar_functions()["multiclass"];
+        let udf = func.inner().as_any().downcast_ref::<TrustyUdf>().unwrap();
+        assert!(udf.model().is_none());
+        let model = udf.multi_output_model().unwrap();
+        assert_eq!(udf.return_type(&[])?, model.output_type());
+        assert!(matches!(
+            udf.return_type(&[])?,
+            DataType::FixedSizeList(_, 4)
+        ));
+
+        let arrays: Vec<ArrayRef> = udf
+            .parameter_types()
+            .iter()
+            .map(|data_type| -> ArrayRef {
+                match data_type {
+                    DataType::Float32 => {
+                        Arc::new(Float32Array::from(vec![Some(0.3), None, Some(2.0)]))
+                    }
+                    DataType::Int64 => Arc::new(Int64Array::from(vec![0, 1, 2])),
+                    _ => Arc::new(BooleanArray::from(vec![true, false, true])),
+                }
+            })
+            .collect();
+        let expected = model.predict_arrays(&arrays)?;
+        let args: Vec<ColumnarValue> = arrays.iter().cloned().map(ColumnarValue::Array).collect();
+        let ColumnarValue::Array(predictions) = udf.invoke(&args)? else {
+            panic!("expected an array");
+        };
+        assert_eq!(predictions.as_ref(), &expected as &dyn Array);
+
+        let scalars: Vec<ColumnarValue> = arrays
+            .iter()
+            .map(|array| ScalarValue::try_from_array(array, 0).map(ColumnarValue::Scalar))
+            .collect::<Result<_>>()?;
+        match udf.invoke(&scalars)? {
+            ColumnarValue::Scalar(value) => {
+                assert_eq!(value, ScalarValue::try_from_array(&expected, 0)?)
+            }
+            ColumnarValue::Array(_) => panic!("expected a scalar"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sql_call() -> Result<()> {
+        let ctx = SessionContext::new();
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Float64, false),
+                Field::new("b", DataType::Float64, true),
+                Field::new("c", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Float64Array::from(vec![9.0, 9.0, 9.0])),
+                Arc::new(Float64Array::from(vec![Some(0.0), Some(1.0), None])),
+                Arc::new(Int64Array::from(vec![5, 20, 5])),
+            ],
+        )?;
+        ctx.register_batch("t", batch)?;
+        let udf = TrustyUdf::new("score", model());
+        ctx.register_udf(ScalarUDF::from(udf.clone()));
+
+        let sql = ctx
+            .sql("SELECT score(b, c) FROM t")
+            .await?
+            .collect()
+            .await?;
+        let expr = ctx
+            .table("t")
+            .await?
+            .select(vec![udf.call_by_name()])?
+            .collect()
+            .await?;
+        let named = ctx
+            .table("t")
+            .await?
+            .select(vec![udf.call_with_named_args(vec![
+                col("c"),
+                (col("b") * lit(1.0)).alias("b"),
+            ])?])?
+            .collect()
+            .await?;
+        for batches in [sql, expr, named] {
+            let predictions = batches[0]
+                .column(0)
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .unwrap()
+                .clone();
+            assert_eq!(predictions.values(), &[-0.5, 5.5, -0.5]);
+        }
+        Ok(())
+    }
+}
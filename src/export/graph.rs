@@ -0,0 +1,278 @@
+use crate::tree::{GradientBoostedDecisionTrees, Traversable, VecTreeNodes};
+use std::fmt::Write;
+
+/// Options for [`to_dot_with_options`](GradientBoostedDecisionTrees::to_dot_with_options) and
+/// [`to_mermaid_with_options`](GradientBoostedDecisionTrees::to_mermaid_with_options).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GraphOptions {
+    /// Adds the gain and cover of splits and the cover of leaves to their labels, for trees
+    /// loaded with node statistics.
+    pub show_stats: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl Format {
+    fn escape(&self, label: &str) -> String {
+        match self {
+            Format::Dot => label.replace('\\', "\\\\").replace('"', "\\\""),
+            Format::Mermaid => label
+                .replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;"),
+        }
+    }
+
+    fn line_break(&self) -> &'static str {
+        match self {
+            Format::Dot => "\\n",
+            Format::Mermaid => "<br>",
+        }
+    }
+
+    fn node(&self, id: &str, lines: &[String], is_leaf: bool) -> String {
+        let lines: Vec<String> = lines.iter().map(|line| self.escape(line)).collect();
+        let label = lines.join(self.line_break());
+        match (self, is_leaf) {
+            (Format::Dot, false) => format!("{} [label=\"{}\"];", id, label),
+            (Format::Dot, true) => format!("{} [label=\"{}\", shape=box];", id, label),
+            (Format::Mermaid, false) => format!("{}{{\"{}\"}}", id, label),
+            (Format::Mermaid, true) => format!("{}[\"{}\"]", id, label),
+        }
+    }
+
+    fn edge(&self, from: &str, to: &str, label: &str) -> String {
+        match self {
+            Format::Dot => format!("{} -> {} [label=\"{}\"];", from, to, label),
+            Format::Mermaid => format!("{} -->|{}| {}", from, label, to),
+        }
+    }
+}
+
+impl VecTreeNodes {
+    /// Renders the tree as a Graphviz DOT digraph.
+    ///
+    /// `feature_names` is indexed by the feature indices stored in the nodes, see
+    /// [`GradientBoostedDecisionTrees::tree_feature_names`].
+    pub fn to_dot(&self, feature_names: &[String]) -> String {
+        self.to_dot_with_options(feature_names, &GraphOptions::default())
+    }
+
+    pub fn to_dot_with_options(&self, feature_names: &[String], options: &GraphOptions) -> String {
+        let mut out = String::from("digraph tree {\n");
+        let writer = TreeWriter {
+            prefix: "n",
+            feature_names,
+            format: Format::Dot,
+            options,
+            indent: "    ",
+        };
+        writer.write_tree(&mut out, self);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the tree as a Mermaid flowchart.
+    ///
+    /// `feature_names` is indexed by the feature indices stored in the nodes, see
+    /// [`GradientBoostedDecisionTrees::tree_feature_names`].
+    pub fn to_mermaid(&self, feature_names: &[String]) -> String {
+        self.to_mermaid_with_options(feature_names, &GraphOptions::default())
+    }
+
+    pub fn to_mermaid_with_options(
+        &self,
+        feature_names: &[String],
+        options: &GraphOptions,
+    ) -> String {
+        let mut out = String::from("flowchart TD\n");
+        let writer = TreeWriter {
+            prefix: "n",
+            feature_names,
+            format: Format::Mermaid,
+            options,
+            indent: "    ",
+        };
+        writer.write_tree(&mut out, self);
+        out
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Renders every tree as a Graphviz DOT digraph, one cluster per tree.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with_options(&GraphOptions::default())
+    }
+
+    pub fn to_dot_with_options(&self, options: &GraphOptions) -> String {
+        let feature_names = self.tree_feature_names();
+        let mut out = String::from("digraph model {\n");
+        for (tree_idx, tree) in self.trees.iter().enumerate() {
+            let _ = writeln!(out, "    subgraph cluster_{} {{", tree_idx);
+            let _ = writeln!(out, "        label=\"tree {}\";", tree_idx);
+            let prefix = format!("t{}n", tree_idx);
+            let writer = TreeWriter {
+                prefix: &prefix,
+                feature_names: &feature_names,
+                format: Format::Dot,
+                options,
+                indent: "        ",
+            };
+            writer.write_tree(&mut out, tree);
+            out.push_str("    }\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders every tree as a Mermaid flowchart, one subgraph per tree.
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_with_options(&GraphOptions::default())
+    }
+
+    pub fn to_mermaid_with_options(&self, options: &GraphOptions) -> String {
+        let feature_names = self.tree_feature_names();
+        let mut out = String::from("flowchart TD\n");
+        for (tree_idx, tree) in self.trees.iter().enumerate() {
+            let _ = writeln!(out, "    subgraph tree_{}", tree_idx);
+            let prefix = format!("t{}n", tree_idx);
+            let writer = TreeWriter {
+                prefix: &prefix,
+                feature_names: &feature_names,
+                format: Format::Mermaid,
+                options,
+                indent: "        ",
+            };
+            writer.write_tree(&mut out, tree);
+            out.push_str("    end\n");
+        }
+        out
+    }
+}
+
+struct TreeWriter<'a> {
+    prefix: &'a str,
+    feature_names: &'a [String],
+    format: Format,
+    options: &'a GraphOptions,
+    indent: &'a str,
+}
+
+impl TreeWriter<'_> {
+    fn write_tree(&self, out: &mut String, tree: &VecTreeNodes) {
+        if !tree.is_empty() {
+            self.write_node(out, tree, tree.get_root_index());
+        }
+    }
+
+    fn write_node(&self, out: &mut String, tree: &VecTreeNodes, idx: usize) {
+        let (format, indent) = (self.format, self.indent);
+        let node = &tree.nodes[idx];
+        let id = format!("{}{}", self.prefix, idx);
+        let stats = tree.get_stats(idx).filter(|_| self.options.show_stats);
+        if node.is_leaf() {
+            let mut lines = vec![format!("leaf = {}", node.weight())];
+            if let Some(stats) = stats {
+                lines.push(format!("cover = {}", stats.cover));
+            }
+            let _ = writeln!(out, "{}{}", indent, format.node(&id, &lines, true));
+            return;
+        }
+
+        let feature_index = node.feature_index() as usize;
+        let mut lines = vec![match self.feature_names.get(feature_index) {
+            Some(name) => format!("{} < {}", name, node.split_value()),
+            None => format!("f{} < {}", feature_index, node.split_value()),
+        }];
+        if let Some(stats) = stats {
+            lines.push(format!("gain = {}, cover = {}", stats.gain, stats.cover));
+        }
+        let _ = writeln!(out, "{}{}", indent, format.node(&id, &lines, false));
+
+        let (yes_label, no_label) = if node.default_left() {
+            ("yes, missing", "no")
+        } else {
+            ("yes", "no, missing")
+        };
+        for (child_idx, edge_label) in [(node.left(), yes_label), (node.right(), no_label)] {
+            let child_id = format!("{}{}", self.prefix, child_idx);
+            let _ = writeln!(out, "{}{}", indent, format.edge(&id, &child_id, edge_label));
+            self.write_node(out, tree, child_idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> VecTreeNodes {
+        VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 2.5])
+            .default_left(vec![true, false, false])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let names = vec!["carat \"ct\"".to_string()];
+        let expected = "digraph tree {\n\
+            \x20   n0 [label=\"carat \\\"ct\\\" < 0.5\"];\n\
+            \x20   n0 -> n1 [label=\"yes, missing\"];\n\
+            \x20   n1 [label=\"leaf = -1\", shape=box];\n\
+            \x20   n0 -> n2 [label=\"no\"];\n\
+            \x20   n2 [label=\"leaf = 2.5\", shape=box];\n\
+            }\n";
+        assert_eq!(sample_tree().to_dot(&names), expected);
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let names = vec!["carat".to_string()];
+        let expected = "flowchart TD\n\
+            \x20   n0{\"carat #lt; 0.5\"}\n\
+            \x20   n0 -->|yes, missing| n1\n\
+            \x20   n1[\"leaf = -1\"]\n\
+            \x20   n0 -->|no| n2\n\
+            \x20   n2[\"leaf = 2.5\"]\n";
+        assert_eq!(sample_tree().to_mermaid(&names), expected);
+    }
+
+    #[test]
+    fn test_show_stats() {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 2.5])
+            .default_left(vec![true, false, false])
+            .loss_changes(vec![12.5, 0.0, 0.0])
+            .sum_hessians(vec![10.0, 4.0, 6.0])
+            .build()
+            .unwrap();
+        let names = vec!["carat".to_string()];
+        let options = GraphOptions { show_stats: true };
+
+        let dot = tree.to_dot_with_options(&names, &options);
+        assert!(dot.contains("n0 [label=\"carat < 0.5\\ngain = 12.5, cover = 10\"];"));
+        assert!(dot.contains("n1 [label=\"leaf = -1\\ncover = 4\", shape=box];"));
+        let mermaid = tree.to_mermaid_with_options(&names, &options);
+        assert!(mermaid.contains("n0{\"carat #lt; 0.5<br>gain = 12.5, cover = 10\"}"));
+        assert!(mermaid.contains("n2[\"leaf = 2.5<br>cover = 6\"]"));
+
+        // Trees without statistics render as without the option.
+        assert_eq!(
+            sample_tree().to_dot_with_options(&names, &options),
+            sample_tree().to_dot(&names)
+        );
+        assert_eq!(tree.to_mermaid(&names), sample_tree().to_mermaid(&names));
+    }
+}
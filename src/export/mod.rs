@@ -0,0 +1,6 @@
+mod graph;
+pub use graph::GraphOptions;
+mod source;
+mod sql;
+pub use sql::SqlDialect;
+mod xgboost;
@@ -0,0 +1,160 @@
+use crate::objective::Objective;
+use crate::tree::{GradientBoostedDecisionTrees, Traversable, VecTreeNodes};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    C,
+}
+
+impl Language {
+    fn float_literal(&self, value: f32) -> String {
+        // `{:?}` prints the shortest representation that round-trips through f32.
+        match self {
+            Language::Rust => format!("{:?}_f32", value),
+            Language::C => format!("{:?}f", value),
+        }
+    }
+
+    fn condition(&self, feature_index: usize, op: &str, threshold: f32) -> String {
+        let threshold = self.float_literal(threshold);
+        match self {
+            Language::Rust => format!("f[{}] {} {}", feature_index, op, threshold),
+            Language::C => format!("(f[{}] {} {})", feature_index, op, threshold),
+        }
+    }
+
+    fn leaf(&self, weight: f32) -> String {
+        match self {
+            Language::Rust => self.float_literal(weight),
+            Language::C => format!("return {};", self.float_literal(weight)),
+        }
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Generates a self-contained Rust module exposing `pub fn predict(features: &[f32]) -> f32`.
+    ///
+    /// `features` is indexed by position in `feature_names`; NaN marks a missing value.
+    pub fn to_rust_source(&self) -> String {
+        let lang = Language::Rust;
+        let mut out = String::new();
+        out.push_str("// Generated by trusty. Do not edit.\n");
+        out.push_str("#![allow(clippy::all)]\n\n");
+        let _ = writeln!(
+            out,
+            "pub const NUM_FEATURES: usize = {};\n",
+            self.feature_names.len()
+        );
+
+        let original_indices = self.original_feature_indices();
+        for (tree_idx, tree) in self.trees.iter().enumerate() {
+            let _ = writeln!(out, "#[inline(always)]");
+            let _ = writeln!(out, "fn tree_{}(f: &[f32]) -> f32 {{", tree_idx);
+            write_tree(&mut out, tree, &original_indices, lang);
+            out.push_str("}\n\n");
+        }
+
+        out.push_str("pub fn predict(features: &[f32]) -> f32 {\n");
+        out.push_str("    assert!(features.len() >= NUM_FEATURES);\n");
+        let _ = writeln!(
+            out,
+            "    let mut score = {};",
+            lang.float_literal(self.base_score)
+        );
+        for tree_idx in 0..self.trees.len() {
+            let _ = writeln!(out, "    score += tree_{}(features);", tree_idx);
+        }
+        match self.objective {
+            Objective::SquaredError => out.push_str("    score\n"),
+            Objective::Logistic => {
+                out.push_str("    1.0_f32 / (1.0_f32 + (-score).exp())\n");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Generates a C translation unit exposing `float predict(const float* features)`.
+    ///
+    /// `features` is indexed by position in `feature_names`; NaN marks a missing value.
+    /// The output needs to be linked against libm for logistic objectives.
+    pub fn to_c_source(&self) -> String {
+        let lang = Language::C;
+        let mut out = String::new();
+        out.push_str("/* Generated by trusty. Do not edit. */\n");
+        out.push_str("#include <math.h>\n\n");
+        let _ = writeln!(
+            out,
+            "#define TRUSTY_NUM_FEATURES {}\n",
+            self.feature_names.len()
+        );
+        out.push_str("float predict(const float* features);\n\n");
+
+        let original_indices = self.original_feature_indices();
+        for (tree_idx, tree) in self.trees.iter().enumerate() {
+            let _ = writeln!(out, "static float tree_{}(const float* f) {{", tree_idx);
+            write_tree(&mut out, tree, &original_indices, lang);
+            out.push_str("}\n\n");
+        }
+
+        out.push_str("float predict(const float* features) {\n");
+        let _ = writeln!(
+            out,
+            "    float score = {};",
+            lang.float_literal(self.base_score)
+        );
+        for tree_idx in 0..self.trees.len() {
+            let _ = writeln!(out, "    score += tree_{}(features);", tree_idx);
+        }
+        match self.objective {
+            Objective::SquaredError => out.push_str("    return score;\n"),
+            Objective::Logistic => {
+                out.push_str("    return 1.0f / (1.0f + expf(-score));\n");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn write_tree(out: &mut String, tree: &VecTreeNodes, original_indices: &[usize], lang: Language) {
+    fn write_node(
+        out: &mut String,
+        tree: &VecTreeNodes,
+        idx: usize,
+        original_indices: &[usize],
+        lang: Language,
+        depth: usize,
+    ) {
+        let indent = "    ".repeat(depth);
+        let node = &tree.nodes[idx];
+        if node.is_leaf() {
+            let _ = writeln!(out, "{}{}", indent, lang.leaf(node.weight()));
+            return;
+        }
+
+        let feature_index = original_indices[node.feature_index() as usize];
+        // A comparison with NaN is false, so missing values take the else branch,
+        // which is always the node's default direction.
+        let (op, then_idx, else_idx) = if node.default_left() {
+            (">=", node.right(), node.left())
+        } else {
+            ("<", node.left(), node.right())
+        };
+
+        let condition = lang.condition(feature_index, op, node.split_value());
+        let _ = writeln!(out, "{}if {} {{", indent, condition);
+        write_node(out, tree, then_idx, original_indices, lang, depth + 1);
+        let _ = writeln!(out, "{}}} else {{", indent);
+        write_node(out, tree, else_idx, original_indices, lang, depth + 1);
+        let _ = writeln!(out, "{}}}", indent);
+    }
+
+    if tree.is_empty() {
+        let _ = writeln!(out, "    {}", lang.leaf(0.0));
+    } else {
+        write_node(out, tree, tree.get_root_index(), original_indices, lang, 1);
+    }
+}
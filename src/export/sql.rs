@@ -0,0 +1,297 @@
+use crate::objective::Objective;
+use crate::tree::{
+    Accumulation, FeatureType, GradientBoostedDecisionTrees, Traversable, VecTreeNodes,
+};
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    DuckDb,
+    Postgres,
+    Ansi,
+}
+
+impl SqlDialect {
+    fn double(&self, expr: &str) -> String {
+        match self {
+            SqlDialect::DuckDb => format!("({})::DOUBLE", expr),
+            SqlDialect::Postgres => format!("({})::DOUBLE PRECISION", expr),
+            SqlDialect::Ansi => format!("CAST({} AS DOUBLE PRECISION)", expr),
+        }
+    }
+
+    fn real(&self, expr: &str) -> String {
+        match self {
+            SqlDialect::DuckDb | SqlDialect::Postgres => format!("({})::REAL", expr),
+            SqlDialect::Ansi => format!("CAST({} AS REAL)", expr),
+        }
+    }
+
+    fn float_literal(&self, value: f32) -> String {
+        // Widening to f64 is exact, so the literal compares equal to the f32 threshold.
+        self.double(&format!("{:?}", value as f64))
+    }
+
+    fn real_literal(&self, value: f32) -> String {
+        self.real(&format!("{:?}", value as f64))
+    }
+
+    fn exp(&self, expr: &str) -> String {
+        match self {
+            SqlDialect::DuckDb | SqlDialect::Postgres => format!("exp({})", expr),
+            SqlDialect::Ansi => format!("EXP({})", expr),
+        }
+    }
+
+    /// A predicate that holds for NaN. DuckDB and Postgres order NaN above every number, so
+    /// NaN would otherwise take the `>=` branch. ANSI SQL has no NaN; `x <> x` covers engines
+    /// that compare floats as IEEE 754 does.
+    fn is_nan(&self, expr: &str) -> String {
+        match self {
+            SqlDialect::DuckDb => format!("isnan({})", expr),
+            SqlDialect::Postgres => format!("{} = {}", expr, self.real("'NaN'")),
+            SqlDialect::Ansi => format!("{} <> {}", expr, expr),
+        }
+    }
+
+    /// Numeric features are read as REAL, as the engines round them to `f32` before comparing.
+    fn column(&self, name: &str, feature_type: &FeatureType) -> SqlColumn {
+        let quoted = format!("\"{}\"", name.replace('"', "\"\""));
+        match feature_type {
+            FeatureType::Indicator => SqlColumn {
+                expr: format!("CAST({} AS INTEGER)", quoted),
+                nan: None,
+            },
+            FeatureType::Float | FeatureType::Int => {
+                let expr = self.real(&quoted);
+                SqlColumn {
+                    nan: Some(self.is_nan(&expr)),
+                    expr,
+                }
+            }
+        }
+    }
+}
+
+struct SqlColumn {
+    expr: String,
+    /// Predicate for a NaN input, which has to follow the default direction like NULL.
+    nan: Option<String>,
+}
+
+impl FromStr for SqlDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "duckdb" => Ok(SqlDialect::DuckDb),
+            "postgres" | "postgresql" => Ok(SqlDialect::Postgres),
+            "ansi" => Ok(SqlDialect::Ansi),
+            unsupported => Err(format!(
+                "Unsupported SQL dialect: {}. Supported dialects are: duckdb, postgres, ansi",
+                unsupported
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SqlDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlDialect::DuckDb => write!(f, "duckdb"),
+            SqlDialect::Postgres => write!(f, "postgres"),
+            SqlDialect::Ansi => write!(f, "ansi"),
+        }
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Renders the model as a single SQL scalar expression over the feature columns.
+    ///
+    /// Every tree becomes a nested `CASE`. Splits are written so that NULL and NaN inputs fall
+    /// through to the `ELSE` branch, which is always the node's default direction. The trees
+    /// are added up as the model's [`Accumulation`] does: in REAL, per chunk of
+    /// `tree_chunk_size` trees, by default, or one by one in DOUBLE PRECISION for
+    /// `Accumulation::F64`.
+    pub fn to_sql(&self, dialect: SqlDialect) -> String {
+        let original_indices = self.original_feature_indices();
+        let columns: Vec<SqlColumn> = original_indices
+            .iter()
+            .map(|&idx| dialect.column(&self.feature_names[idx], &self.feature_types[idx]))
+            .collect();
+
+        let margin = match self.config.accumulation() {
+            Accumulation::Chunked => {
+                let leaf = |value: f32| dialect.real_literal(value);
+                let mut margin = leaf(self.base_score);
+                for tree_chunk in self.trees.chunks(self.config.tree_chunk_size()) {
+                    margin.push_str("\n  + (");
+                    for (i, tree) in tree_chunk.iter().enumerate() {
+                        if i > 0 {
+                            margin.push_str("\n    + ");
+                        }
+                        write_tree(&mut margin, tree, &columns, dialect, &leaf);
+                    }
+                    margin.push(')');
+                }
+                dialect.double(&margin)
+            }
+            Accumulation::F64 => {
+                let leaf = |value: f32| dialect.float_literal(value);
+                let mut margin = leaf(self.base_score);
+                for tree in &self.trees {
+                    margin.push_str("\n  + ");
+                    write_tree(&mut margin, tree, &columns, dialect, &leaf);
+                }
+                margin
+            }
+        };
+
+        match self.objective {
+            Objective::SquaredError => margin,
+            Objective::Logistic => format!(
+                "{one} / ({one} + {exp})",
+                one = dialect.float_literal(1.0),
+                exp = dialect.exp(&format!("-({})", margin))
+            ),
+        }
+    }
+}
+
+fn write_tree(
+    out: &mut String,
+    tree: &VecTreeNodes,
+    columns: &[SqlColumn],
+    dialect: SqlDialect,
+    leaf: &dyn Fn(f32) -> String,
+) {
+    fn write_node(
+        out: &mut String,
+        tree: &VecTreeNodes,
+        idx: usize,
+        columns: &[SqlColumn],
+        dialect: SqlDialect,
+        leaf: &dyn Fn(f32) -> String,
+    ) {
+        let node = &tree.nodes[idx];
+        if node.is_leaf() {
+            out.push_str(&leaf(node.weight()));
+            return;
+        }
+
+        let column = &columns[node.feature_index() as usize];
+        let threshold = dialect.float_literal(node.split_value());
+        // `NULL >= x` and `NULL < x` are both NULL, so NULL rows take the ELSE branch. NaN is
+        // never `< x`, so only the `>=` test has to exclude it.
+        let (condition, then_idx, else_idx) = if node.default_left() {
+            let condition = match &column.nan {
+                Some(nan) => format!("{} >= {} AND NOT ({})", column.expr, threshold, nan),
+                None => format!("{} >= {}", column.expr, threshold),
+            };
+            (condition, node.right(), node.left())
+        } else {
+            (
+                format!("{} < {}", column.expr, threshold),
+                node.left(),
+                node.right(),
+            )
+        };
+
+        let _ = write!(out, "CASE WHEN {} THEN ", condition);
+        write_node(out, tree, then_idx, columns, dialect, leaf);
+        out.push_str(" ELSE ");
+        write_node(out, tree, else_idx, columns, dialect, leaf);
+        out.push_str(" END");
+    }
+
+    if tree.is_empty() {
+        out.push_str(&leaf(0.0));
+    } else {
+        write_node(out, tree, tree.get_root_index(), columns, dialect, leaf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{FeatureTreeBuilder, PredictorConfig};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    fn create_model(objective: Objective) -> GradientBoostedDecisionTrees {
+        //          [age < 30]
+        //         /          \
+        //    [-1.0]        [is_member]
+        //                  /          \
+        //               [0.0]        [1.0]
+        let tree = FeatureTreeBuilder::new()
+            .split_indices(vec![0, -1, 1, -1, -1])
+            .split_conditions(vec![30.0, 0.0, 0.5, 0.0, 0.0])
+            .children(
+                vec![1, u32::MAX, 3, u32::MAX, u32::MAX],
+                vec![2, u32::MAX, 4, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, -1.0, 0.0, 0.0, 1.0])
+            .default_left(vec![true, false, false, false, false])
+            .build()
+            .unwrap();
+
+        GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["age".to_string(), "is_member".to_string()]),
+            feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Indicator]),
+            base_score: 0.5,
+            objective,
+            objective_name: None,
+            config: PredictorConfig::default(),
+            required_features: HashSet::from([0, 1]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_sql_squared_error() {
+        let sql = create_model(Objective::SquaredError).to_sql(SqlDialect::DuckDb);
+        assert_eq!(
+            sql,
+            "((0.5)::REAL\n  + (CASE WHEN (\"age\")::REAL >= (30.0)::DOUBLE \
+             AND NOT (isnan((\"age\")::REAL)) THEN \
+             CASE WHEN CAST(\"is_member\" AS INTEGER) < (0.5)::DOUBLE THEN (0.0)::REAL \
+             ELSE (1.0)::REAL END ELSE (-1.0)::REAL END))::DOUBLE"
+        );
+    }
+
+    #[test]
+    fn test_to_sql_f64_accumulation() {
+        let mut model = create_model(Objective::SquaredError);
+        model.set_config(PredictorConfig::default().with_accumulation(Accumulation::F64));
+        let sql = model.to_sql(SqlDialect::DuckDb);
+        assert!(sql.starts_with("(0.5)::DOUBLE\n  + CASE WHEN (\"age\")::REAL >= (30.0)::DOUBLE"));
+        assert!(sql.ends_with("ELSE (-1.0)::DOUBLE END"));
+    }
+
+    #[test]
+    fn test_to_sql_logistic_dialects() {
+        let model = create_model(Objective::Logistic);
+
+        let postgres = model.to_sql(SqlDialect::Postgres);
+        assert!(postgres.starts_with("(1.0)::DOUBLE PRECISION / ((1.0)::DOUBLE PRECISION + exp(-("));
+        assert!(postgres.contains("AND NOT ((\"age\")::REAL = ('NaN')::REAL)"));
+
+        let ansi = model.to_sql(SqlDialect::Ansi);
+        assert!(ansi.contains("EXP(-(CAST(CAST(0.5 AS REAL)"));
+        assert!(ansi.contains(
+            "CAST(\"age\" AS REAL) >= CAST(30.0 AS DOUBLE PRECISION) \
+             AND NOT (CAST(\"age\" AS REAL) <> CAST(\"age\" AS REAL))"
+        ));
+    }
+
+    #[test]
+    fn test_sql_dialect_from_str() {
+        assert_eq!("DuckDB".parse::<SqlDialect>(), Ok(SqlDialect::DuckDb));
+        assert_eq!("postgresql".parse::<SqlDialect>(), Ok(SqlDialect::Postgres));
+        assert!("oracle".parse::<SqlDialect>().is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::tree::{GradientBoostedDecisionTrees, Traversable, VecTreeNodes};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+/// XGBoost marks the root's parent with `i32::MAX`.
+const NO_PARENT: i64 = i32::MAX as i64;
+
+impl GradientBoostedDecisionTrees {
+    /// Serializes the model into XGBoost's JSON model format.
+    ///
+    /// The result can be written to disk and loaded by XGBoost, or read back with
+    /// [`ModelLoader::json_loads`](crate::loader::ModelLoader::json_loads). Feature indices are
+    /// written against the full `feature_names` list, so pruned models stay compatible with the
+    /// original input schema.
+    pub fn to_xgboost_json(&self) -> Value {
+        let original_indices = self.original_feature_indices();
+        let num_features = self.feature_names.len().to_string();
+
+        let trees: Vec<Value> = self
+            .trees
+            .iter()
+            .enumerate()
+            .map(|(tree_id, tree)| tree_to_json(tree, tree_id, &original_indices, &num_features))
+            .collect();
+
+        let objective = json!({
+            "name": self.objective_name(),
+            "reg_loss_param": { "scale_pos_weight": "1" }
+        });
+
+        let feature_types: Vec<String> = self.feature_types.iter().map(|t| t.to_string()).collect();
+
+        json!({
+            "learner": {
+                "attributes": {},
+                "feature_names": self.feature_names.as_ref(),
+                "feature_types": feature_types,
+                "gradient_booster": {
+                    "model": {
+                        "gbtree_model_param": {
+                            "num_parallel_tree": "1",
+                            "num_trees": self.trees.len().to_string()
+                        },
+                        "iteration_indptr": (0..=self.trees.len()).collect::<Vec<_>>(),
+                        "tree_info": vec![0; self.trees.len()],
+                        "trees": trees
+                    },
+                    "name": "gbtree"
+                },
+                "learner_model_param": {
+                    "base_score": format!("{:E}", self.base_score),
+                    "boost_from_average": "1",
+                    "num_class": "0",
+                    "num_feature": num_features,
+                    "num_target": "1"
+                },
+                "objective": objective
+            },
+            "version": [2, 0, 0]
+        })
+    }
+}
+
+/// Writes one tree with its nodes renumbered in breadth-first order, root first.
+fn tree_to_json(
+    tree: &VecTreeNodes,
+    tree_id: usize,
+    original_indices: &[usize],
+    num_features: &str,
+) -> Value {
+    let mut order = Vec::with_capacity(tree.len());
+    let mut parents = Vec::with_capacity(tree.len());
+    let mut queue = VecDeque::new();
+    if !tree.is_empty() {
+        queue.push_back((tree.get_root_index(), NO_PARENT));
+    }
+    while let Some((idx, parent)) = queue.pop_front() {
+        let new_idx = order.len() as i64;
+        order.push(idx);
+        parents.push(parent);
+        let node = &tree.nodes[idx];
+        if !node.is_leaf() {
+            queue.push_back((node.left(), new_idx));
+            queue.push_back((node.right(), new_idx));
+        }
+    }
+
+    let num_nodes = order.len().max(1);
+    let mut split_indices = Vec::with_capacity(num_nodes);
+    let mut split_conditions = Vec::with_capacity(num_nodes);
+    let mut left_children = Vec::with_capacity(num_nodes);
+    let mut right_children = Vec::with_capacity(num_nodes);
+    let mut base_weights = Vec::with_capacity(num_nodes);
+    let mut default_left = Vec::with_capacity(num_nodes);
+    let mut loss_changes = Vec::with_capacity(num_nodes);
+    let mut sum_hessian = Vec::with_capacity(num_nodes);
+
+    // Children are pushed in pairs, so node `i`'s children land at consecutive positions
+    // after every child enqueued before them.
+    let mut next_child = 1_i64;
+    for &idx in &order {
+        let node = &tree.nodes[idx];
+        let stats = tree.get_stats(idx).copied().unwrap_or_default();
+        loss_changes.push(stats.gain);
+        sum_hessian.push(stats.cover);
+        if node.is_leaf() {
+            split_indices.push(0);
+            split_conditions.push(node.weight());
+            left_children.push(-1);
+            right_children.push(-1);
+            base_weights.push(node.weight());
+            default_left.push(0);
+        } else {
+            split_indices.push(original_indices[node.feature_index() as usize]);
+            split_conditions.push(node.split_value());
+            left_children.push(next_child);
+            right_children.push(next_child + 1);
+            next_child += 2;
+            base_weights.push(0.0);
+            default_left.push(node.default_left() as u8);
+        }
+    }
+
+    if order.is_empty() {
+        // An empty tree contributes nothing; XGBoost needs at least a root leaf.
+        split_indices.push(0);
+        split_conditions.push(0.0);
+        left_children.push(-1);
+        right_children.push(-1);
+        base_weights.push(0.0);
+        default_left.push(0);
+        loss_changes.push(0.0);
+        sum_hessian.push(0.0);
+        parents.push(NO_PARENT);
+    }
+
+    json!({
+        "base_weights": base_weights,
+        "categories": [],
+        "categories_nodes": [],
+        "categories_segments": [],
+        "categories_sizes": [],
+        "default_left": default_left,
+        "id": tree_id,
+        "left_children": left_children,
+        "loss_changes": loss_changes,
+        "parents": parents,
+        "right_children": right_children,
+        "split_conditions": split_conditions,
+        "split_indices": split_indices,
+        "split_type": vec![0; num_nodes],
+        "sum_hessian": sum_hessian,
+        "tree_param": {
+            "num_deleted": "0",
+            "num_feature": num_features,
+            "num_nodes": num_nodes.to_string(),
+            "size_leaf_vector": "1"
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModelLoader;
+    use crate::objective::Objective;
+
+    #[test]
+    fn test_to_xgboost_json_renumbers_nodes() {
+        // Builder order puts the right leaf before the left subtree's split.
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, 1, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 2.0, 0.0, 0.0])
+            .children(
+                vec![2, u32::MAX, 3, u32::MAX, u32::MAX],
+                vec![1, u32::MAX, 4, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, 3.0, 0.0, 1.0, 2.0])
+            .default_left(vec![true, false, false, false, false])
+            .build()
+            .unwrap();
+
+        let json = tree_to_json(&tree, 0, &[0, 1], "2");
+        assert_eq!(json["left_children"], json!([1, 3, -1, -1, -1]));
+        assert_eq!(json["right_children"], json!([2, 4, -1, -1, -1]));
+        assert_eq!(json["parents"], json!([NO_PARENT, 0, 0, 1, 1]));
+        assert_eq!(json["split_indices"], json!([0, 1, 0, 0, 0]));
+        assert_eq!(json["base_weights"], json!([0.0, 0.0, 3.0, 1.0, 2.0]));
+        assert_eq!(json["default_left"], json!([1, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_to_xgboost_json_reloads() {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 1.0])
+            .default_left(vec![false, false, false])
+            .build()
+            .unwrap();
+        let json = json!({
+            "learner": {
+                "feature_names": ["a", "b"],
+                "feature_types": ["float", "int"],
+                "learner_model_param": { "base_score": "4E-1" },
+                "objective": { "name": "binary:logistic" },
+                "gradient_booster": { "model": { "trees": [] } }
+            }
+        });
+        let mut model = GradientBoostedDecisionTrees::json_loads(&json).unwrap();
+        model.trees = vec![tree];
+        model.required_features = [1].into_iter().collect();
+
+        let exported = model.to_xgboost_json();
+        assert_eq!(
+            exported["learner"]["learner_model_param"]["base_score"],
+            "4E-1"
+        );
+        assert_eq!(exported["learner"]["objective"]["name"], "binary:logistic");
+
+        let reloaded = GradientBoostedDecisionTrees::json_loads(&exported).unwrap();
+        assert_eq!(reloaded.feature_names, model.feature_names);
+        assert_eq!(reloaded.base_score, 0.4);
+        assert_eq!(reloaded.original_feature_indices(), vec![1]);
+        assert_eq!(reloaded.to_xgboost_json(), exported);
+
+        assert_eq!(model.objective, Objective::Logistic);
+        model.objective = Objective::SquaredError;
+        let exported = model.to_xgboost_json();
+        assert_eq!(exported["learner"]["objective"]["name"], "reg:squarederror");
+    }
+}
@@ -0,0 +1,282 @@
+//! Arrow Flight scoring service, enabled by the `flight` cargo feature.
+//!
+//! Each loaded model is a flight whose descriptor path is the model name. `DoExchange` reads the
+//! model from the descriptor of the first message, scores every incoming batch with
+//! [`predict_batches`](GradientBoostedDecisionTrees::predict_batches) and streams back one batch
+//! with a `prediction` column per input batch. Input columns are matched to the model's
+//! [`input_schema`](GradientBoostedDecisionTrees::input_schema) by name; features the trees do
+//! not use may be left out. `ListFlights`, `GetFlightInfo` and `GetSchema` report the input
+//! schemas.
+
+use crate::tree::{GradientBoostedDecisionTrees, PREDICTION_COLUMN};
+use arrow::array::{new_null_array, ArrayRef};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status, Streaming};
+
+struct FlightModel {
+    model: Arc<GradientBoostedDecisionTrees>,
+    schema: SchemaRef,
+}
+
+/// Flight service scoring the models it was created with.
+#[derive(Clone)]
+pub struct TrustyFlightService {
+    models: Arc<BTreeMap<String, FlightModel>>,
+}
+
+impl TrustyFlightService {
+    /// Serves `models`, keyed by the name used in descriptor paths. Models may be passed as
+    /// `Arc`s to share them with other services. Panics if two models have the same name.
+    pub fn new<M: Into<Arc<GradientBoostedDecisionTrees>>>(
+        models: impl IntoIterator<Item = (String, M)>,
+    ) -> Self {
+        let mut models_by_name = BTreeMap::new();
+        for (name, model) in models {
+            let model: Arc<GradientBoostedDecisionTrees> = model.into();
+            let schema = Arc::new(model.input_schema());
+            if models_by_name
+                .insert(name.clone(), FlightModel { model, schema })
+                .is_some()
+            {
+                panic!("Duplicate model name: {}", name);
+            }
+        }
+        Self {
+            models: Arc::new(models_by_name),
+        }
+    }
+
+    /// Wraps the service for a `tonic` server.
+    pub fn into_server(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    fn find_model(&self, descriptor: &FlightDescriptor) -> Result<&FlightModel, Status> {
+        let [name] = descriptor.path.as_slice() else {
+            return Err(Status::invalid_argument(
+                "Expected a descriptor path holding the model name",
+            ));
+        };
+        self.models
+            .get(name)
+            .ok_or_else(|| Status::not_found(format!("Unknown model: {}", name)))
+    }
+
+    fn flight_info(name: &str, model: &FlightModel) -> Result<FlightInfo, Status> {
+        let info = FlightInfo::new()
+            .try_with_schema(&model.schema)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(info.with_descriptor(FlightDescriptor::new_path(vec![name.to_string()])))
+    }
+}
+
+/// Serves `service` on `listener` until the process is stopped.
+pub async fn serve(
+    listener: TcpListener,
+    service: TrustyFlightService,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(service.into_server())
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
+
+/// Reorders and casts the columns of `batch` to `schema`; unused features that are missing are
+/// filled with nulls.
+fn conform(
+    model: &GradientBoostedDecisionTrees,
+    schema: &SchemaRef,
+    batch: &RecordBatch,
+) -> Result<RecordBatch, ArrowError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match batch.column_by_name(field.name()) {
+            Some(column) => cast(column, field.data_type()),
+            None if !model.required_features.contains(&index) => {
+                Ok(new_null_array(field.data_type(), batch.num_rows()))
+            }
+            None => Err(ArrowError::SchemaError(format!(
+                "Missing feature column: {}",
+                field.name()
+            ))),
+        })
+        .collect::<Result<Vec<ArrayRef>, _>>()?;
+    let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+    RecordBatch::try_new_with_options(schema.clone(), columns, &options)
+}
+
+fn score(
+    model: &GradientBoostedDecisionTrees,
+    input_schema: &SchemaRef,
+    output_schema: &SchemaRef,
+    batch: &RecordBatch,
+) -> Result<RecordBatch, ArrowError> {
+    let batch = conform(model, input_schema, batch)?;
+    let predictions = model.predict_batches(&[batch])?;
+    RecordBatch::try_new(output_schema.clone(), vec![Arc::new(predictions)])
+}
+
+fn unsupported<T>(method: &str) -> Result<T, Status> {
+    Err(Status::unimplemented(format!(
+        "{} is not supported, use DoExchange to score batches",
+        method
+    )))
+}
+
+#[tonic::async_trait]
+impl FlightService for TrustyFlightService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        unsupported("Handshake")
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let infos = self
+            .models
+            .iter()
+            .map(|(name, model)| Self::flight_info(name, model))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Response::new(
+            stream::iter(infos.into_iter().map(Ok)).boxed(),
+        ))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        let model = self.find_model(&descriptor)?;
+        Ok(Response::new(Self::flight_info(
+            &descriptor.path[0],
+            model,
+        )?))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        unsupported("PollFlightInfo")
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        let model = self.find_model(request.get_ref())?;
+        let options = IpcWriteOptions::default();
+        let schema: SchemaResult = SchemaAsIpc::new(&model.schema, &options)
+            .try_into()
+            .map_err(|e: ArrowError| Status::internal(e.to_string()))?;
+        Ok(Response::new(schema))
+    }
+
+    async fn do_get(
+        &self,
+        _request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        unsupported("DoGet")
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        unsupported("DoPut")
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        unsupported("DoAction")
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        let mut input = request.into_inner();
+        let first = input
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("Empty exchange"))?;
+        let descriptor = first.flight_descriptor.as_ref().ok_or_else(|| {
+            Status::invalid_argument("The first message must carry the model's descriptor")
+        })?;
+        let entry = self.find_model(descriptor)?;
+        let model = entry.model.clone();
+        let input_schema = entry.schema.clone();
+        let output_schema = Arc::new(Schema::new(vec![Field::new(
+            PREDICTION_COLUMN,
+            DataType::Float32,
+            false,
+        )]));
+
+        let input = stream::once(async { Ok(first) })
+            .chain(input)
+            .map_err(FlightError::from);
+        let schema = output_schema.clone();
+        let predictions =
+            FlightRecordBatchStream::new_from_flight_data(input).and_then(move |batch| {
+                let model = model.clone();
+                let input_schema = input_schema.clone();
+                let output_schema = output_schema.clone();
+                async move {
+                    // Scoring is CPU bound; keep it off the async workers.
+                    tokio::task::spawn_blocking(move || {
+                        score(&model, &input_schema, &output_schema, &batch)
+                    })
+                    .await
+                    .map_err(|e| FlightError::ExternalError(Box::new(e)))?
+                    .map_err(FlightError::from)
+                }
+            });
+        let output = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(predictions)
+            .map_err(|e| Status::invalid_argument(e.to_string()));
+        Ok(Response::new(output.boxed()))
+    }
+}
@@ -1,17 +1,35 @@
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
 pub mod arch;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+pub mod export;
+#[cfg(feature = "flight")]
+pub mod flight;
 pub mod loader;
 pub mod objective;
+#[cfg(feature = "polars")]
+mod polars_plugin;
 pub mod predicates;
+#[cfg(feature = "python")]
 mod python;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod tree;
 
 pub use loader::ModelLoader;
 pub use objective::Objective;
 pub use predicates::{Condition, Predicate};
-pub use tree::{FeatureTreeBuilder, GradientBoostedDecisionTrees, VecTreeNodes};
+pub use tree::{
+    Accumulation, FeatureTreeBuilder, GradientBoostedDecisionTrees, ImportanceType,
+    MultiOutputTrees, OutputColumns, Parallelism, PredictionEngine, PredictionStream,
+    PredictorConfig, PredictorConfigError, VecTreeNodes,
+};
 
+#[cfg(feature = "python")]
 #[pymodule]
 fn trusty(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     let internal = py.import_bound("trusty._internal")?;
@@ -19,10 +37,13 @@ fn trusty(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     Ok(())
 }
 
+#[cfg(feature = "python")]
 #[pymodule]
 fn _internal(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(python::json_load))?;
+    m.add_wrapped(wrap_pyfunction!(python::load))?;
     m.add_class::<python::PyGradientBoostedDecisionTrees>()?;
     m.add_class::<python::Feature>()?;
+    m.add("HAS_POLARS_PLUGIN", cfg!(feature = "polars"))?;
     Ok(())
 }
@@ -18,6 +18,9 @@ pub enum ModelError {
 
     #[error("Tree construction error: {0}")]
     TreeConstruction(#[from] FeatureTreeError),
+
+    #[error("Invalid model snapshot: {0}")]
+    InvalidSnapshot(String),
 }
 
 pub trait ModelLoader: Sized {
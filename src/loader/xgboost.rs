@@ -61,7 +61,15 @@ impl XGBoostParser {
         let default_left =
             Self::extract_array::<bool>(tree_json, "default_left", |v| v.as_i64().map(|x| x != 0))?;
 
-        let sum_hessian = Self::extract_array::<f64>(tree_json, "sum_hessian", |v| v.as_f64())?;
+        let sum_hessian =
+            Self::extract_array::<f32>(tree_json, "sum_hessian", |v| v.as_f64().map(|x| x as f32))?;
+
+        // Older exports omit `loss_changes`; gain based importance then reports zero.
+        let loss_changes = if tree_json.get("loss_changes").is_some() {
+            Self::extract_array::<f32>(tree_json, "loss_changes", |v| v.as_f64().map(|x| x as f32))?
+        } else {
+            Vec::new()
+        };
 
         Ok(TreeArrays {
             split_indices,
@@ -70,6 +78,7 @@ impl XGBoostParser {
             right_children,
             base_weights,
             default_left,
+            loss_changes,
             sum_hessian,
         })
     }
@@ -102,23 +111,61 @@ impl XGBoostParser {
     }
 
     pub fn parse_objective(json: &Value) -> Result<Objective, ModelError> {
-        let objective_name = json["learner"]["objective"]["name"]
+        let num_outputs = Self::parse_num_outputs(json)?;
+        if num_outputs > 1 {
+            return Err(ModelError::InvalidFieldType(format!(
+                "Model predicts {} values per row; load it as a MultiOutputTrees",
+                num_outputs
+            )));
+        }
+        let objective_name = Self::parse_objective_name(json)?;
+        Self::objective_from_name(objective_name).ok_or_else(|| {
+            ModelError::InvalidFieldType(format!("Unsupported objective: {}", objective_name))
+        })
+    }
+
+    pub fn parse_objective_name(json: &Value) -> Result<&str, ModelError> {
+        json["learner"]["objective"]["name"]
             .as_str()
-            .ok_or_else(|| ModelError::MissingField("objective.name".into()))?;
+            .ok_or_else(|| ModelError::MissingField("objective.name".into()))
+    }
+
+    /// Values predicted per row: the number of classes of multiclass models, of targets of
+    /// multi-target ones, and 1 otherwise. Older models have no `num_target`.
+    pub fn parse_num_outputs(json: &Value) -> Result<usize, ModelError> {
+        let mut num_outputs = 1;
+        for field in ["num_class", "num_target"] {
+            let value = &json["learner"]["learner_model_param"][field];
+            if value.is_null() {
+                continue;
+            }
+            let outputs = value
+                .as_str()
+                .and_then(|s| s.parse::<usize>().ok())
+                .ok_or_else(|| ModelError::InvalidFieldType(field.to_string()))?;
+            num_outputs = num_outputs.max(outputs);
+        }
+        Ok(num_outputs)
+    }
+
+    /// The output each tree adds to, in tree order.
+    pub fn parse_tree_info(json: &Value) -> Result<Vec<usize>, ModelError> {
+        Self::extract_array(
+            &json["learner"]["gradient_booster"]["model"],
+            "tree_info",
+            |v| v.as_u64().map(|x| x as usize),
+        )
+    }
 
+    pub fn objective_from_name(objective_name: &str) -> Option<Objective> {
         match objective_name {
-            "reg:squarederror" => Ok(Objective::SquaredError),
-            "reg:logistic" => Ok(Objective::Logistic),
-            "binary:logistic" => Ok(Objective::Logistic),
-            _ => Err(ModelError::InvalidFieldType(format!(
-                "Unsupported objective: {}",
-                objective_name
-            ))),
+            "reg:squarederror" => Some(Objective::SquaredError),
+            "reg:logistic" | "binary:logistic" => Some(Objective::Logistic),
+            _ => None,
         }
     }
 }
 
-#[allow(dead_code)]
 pub(crate) struct TreeArrays {
     pub split_indices: Vec<i32>,
     pub split_conditions: Vec<f32>,
@@ -126,5 +173,6 @@ pub(crate) struct TreeArrays {
     pub right_children: Vec<u32>,
     pub base_weights: Vec<f32>,
     pub default_left: Vec<bool>,
-    pub sum_hessian: Vec<f64>, // sum_hessian is nmot being used anywhere
+    pub loss_changes: Vec<f32>,
+    pub sum_hessian: Vec<f32>,
 }
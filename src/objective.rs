@@ -1,10 +1,21 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Objective {
     SquaredError,
     Logistic,
 }
 
 impl Objective {
+    /// The objective's name in XGBoost models. `Logistic` also loads from `binary:logistic`,
+    /// which models keep in [`objective_name`](crate::GradientBoostedDecisionTrees::objective_name).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Objective::SquaredError => "reg:squarederror",
+            Objective::Logistic => "reg:logistic",
+        }
+    }
+
     #[inline(always)]
     pub fn compute_score(&self, leaf_weight: f32) -> f32 {
         match self {
@@ -0,0 +1,141 @@
+//! Polars expression plugin behind `pl.col(...).quickgrove.predict(model)`, enabled by the
+//! `polars` cargo feature. The Python side is `python/quickgrove/polars.py`.
+
+use crate::tree::{GradientBoostedDecisionTrees, PREDICTION_COLUMN};
+use arrow::array::{make_array, ArrayRef};
+use arrow::error::ArrowError;
+use arrow::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use polars::prelude::{
+    CompatLevel, DataType, Float32Chunked, IntoSeries, PolarsError, PolarsResult, Series,
+};
+use polars_arrow::ffi::{export_array_to_c, export_field_to_c};
+use pyo3_polars::derive::polars_expr;
+use serde::de::{Deserializer, Visitor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Parsed models kept between calls; the plugin is called once per chunk of a frame.
+const MAX_CACHED_MODELS: usize = 16;
+
+#[derive(Deserialize)]
+struct PredictKwargs {
+    /// Digest of `model`, computed once when the expression is built. Cached models are looked
+    /// up by it, so chunks after the first neither hash nor parse the snapshot.
+    digest: String,
+    /// Snapshot of the model, as returned by `to_bytes`. Unlike the XGBoost JSON it keeps the
+    /// configuration and node layout, so the plugin scores exactly like the model.
+    #[serde(deserialize_with = "deserialize_bytes")]
+    model: Vec<u8>,
+}
+
+/// Reads a Python `bytes` object, which the kwargs pickle holds as a byte string rather than a
+/// sequence.
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a model snapshot")
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(v)
+        }
+    }
+
+    deserializer.deserialize_byte_buf(BytesVisitor)
+}
+
+fn compute_error(e: impl fmt::Display) -> PolarsError {
+    PolarsError::ComputeError(e.to_string().into())
+}
+
+/// The model loaded from `snapshot`, whose SHA-256 digest is `digest`.
+fn cached_model(digest: &str, snapshot: &[u8]) -> PolarsResult<Arc<GradientBoostedDecisionTrees>> {
+    static MODELS: OnceLock<Mutex<HashMap<String, Arc<GradientBoostedDecisionTrees>>>> =
+        OnceLock::new();
+
+    let mut models = MODELS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(model) = models.get(digest) {
+        return Ok(Arc::clone(model));
+    }
+    let model =
+        Arc::new(GradientBoostedDecisionTrees::from_bytes(snapshot).map_err(compute_error)?);
+    if models.len() >= MAX_CACHED_MODELS {
+        models.clear();
+    }
+    models.insert(digest.to_string(), Arc::clone(&model));
+    Ok(model)
+}
+
+/// Hands the buffers of `series` to arrow-rs through the Arrow C Data Interface, without
+/// copying them.
+fn to_arrow_rs(series: &Series) -> Result<(arrow::datatypes::Field, ArrayRef), ArrowError> {
+    let series = series.rechunk();
+    let field = series.field().to_arrow(CompatLevel::newest());
+    let array = series.to_arrow(0, CompatLevel::newest());
+    // SAFETY: both structs are the C Data Interface's `ArrowArray` and `ArrowSchema`.
+    let data = unsafe {
+        let ffi_array: FFI_ArrowArray = std::mem::transmute(export_array_to_c(array));
+        let ffi_schema: FFI_ArrowSchema = std::mem::transmute(export_field_to_c(&field));
+        from_ffi(ffi_array, &ffi_schema)?
+    };
+    let field =
+        arrow::datatypes::Field::new(series.name().to_string(), data.data_type().clone(), true);
+    Ok((field, make_array(data)))
+}
+
+/// Scores the rows of the input columns, or of the fields of a single struct column, which are
+/// matched to the model's features by name.
+#[polars_expr(output_type = Float32)]
+fn predict(inputs: &[Series], kwargs: PredictKwargs) -> PolarsResult<Series> {
+    let model = cached_model(&kwargs.digest, &kwargs.model)?;
+    let columns = match inputs {
+        [series] if matches!(series.dtype(), DataType::Struct(_)) => {
+            series.struct_()?.fields_as_series()
+        }
+        _ => inputs.to_vec(),
+    };
+    let num_rows = inputs.first().map_or(0, |series| series.len());
+
+    let (fields, arrays): (Vec<_>, Vec<_>) = columns
+        .iter()
+        .map(to_arrow_rs)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(compute_error)?
+        .into_iter()
+        .unzip();
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    let batch = RecordBatch::try_new_with_options(
+        Arc::new(arrow::datatypes::Schema::new(fields)),
+        arrays,
+        &options,
+    )
+    .map_err(compute_error)?;
+
+    let predictions = if model.required_features.is_empty() {
+        model.predict_dense(&[], num_rows, 0)
+    } else {
+        model
+            .feature_arrays_by_name(&batch)
+            .and_then(|features| model.predict_arrays(&features))
+    }
+    .map_err(compute_error)?;
+
+    let name = inputs
+        .first()
+        .map_or_else(|| PREDICTION_COLUMN.into(), |series| series.name().clone());
+    Ok(Float32Chunked::from_vec(name, predictions.values().to_vec()).into_series())
+}
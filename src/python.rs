@@ -1,20 +1,30 @@
+use crate::export::GraphOptions;
 use crate::loader::ModelLoader;
-use crate::tree::{GradientBoostedDecisionTrees, PredictorConfig, VecTreeNodes};
+use crate::tree::{
+    Accumulation, DenseValue, GradientBoostedDecisionTrees, ImportanceType, Parallelism,
+    PredictionEngine, PredictorConfig, PredictorConfigError, TuningOptions, VecTreeNodes,
+};
 use crate::Condition;
 use crate::Predicate;
 use arrow::array::Array;
 use arrow::array::ArrayRef;
+use arrow::array::Float32Array;
 use arrow::compute::cast;
 use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
 use arrow::pyarrow::PyArrowType;
 use arrow::record_batch::RecordBatch;
+use pyo3::buffer::{Element, PyBuffer};
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::types::PyDict;
 use pyo3::types::PyList;
 use pyo3::types::PyType;
 use pyo3_arrow::error::PyArrowResult;
 use pyo3_arrow::PyArray;
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 #[pyclass]
 #[derive(Clone)]
@@ -43,6 +53,7 @@ impl Feature {
 #[pyclass]
 pub struct PyFeatureTree {
     tree: VecTreeNodes,
+    feature_names: Vec<String>,
 }
 
 #[pymethods]
@@ -50,9 +61,21 @@ impl PyFeatureTree {
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!("{}", self.tree))
     }
+
+    #[pyo3(signature = (*, show_stats=false))]
+    fn to_dot(&self, show_stats: bool) -> String {
+        self.tree
+            .to_dot_with_options(&self.feature_names, &GraphOptions { show_stats })
+    }
+
+    #[pyo3(signature = (*, show_stats=false))]
+    fn to_mermaid(&self, show_stats: bool) -> String {
+        self.tree
+            .to_mermaid_with_options(&self.feature_names, &GraphOptions { show_stats })
+    }
 }
 
-#[pyclass]
+#[pyclass(module = "quickgrove._internal")]
 pub struct PyGradientBoostedDecisionTrees {
     model: Arc<GradientBoostedDecisionTrees>,
 }
@@ -82,69 +105,151 @@ impl PyGradientBoostedDecisionTrees {
         })
     }
 
-    #[pyo3(signature = (py_record_batches, *, row_chunk_size=64, tree_chunk_size=8))]
+    /// Loads a model written by `save`.
+    #[classmethod]
+    fn load(_cls: Py<PyType>, path: PathBuf) -> PyResult<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        model_from_bytes(&bytes)
+    }
+
+    /// Writes the model in the compact binary format used for pickling, which keeps pruning,
+    /// node layout and configuration. Thread pools are not saved.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        std::fs::write(path, self.model.to_bytes())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    #[classmethod]
+    fn from_bytes(_cls: Py<PyType>, data: &[u8]) -> PyResult<Self> {
+        model_from_bytes(data)
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.model.to_bytes())
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        self.to_bytes(py)
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = model_from_bytes(state)?;
+        Ok(())
+    }
+
+    /// `__new__` needs a model, so pickles are rebuilt through `from_bytes`.
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let from_bytes = slf.get_type().getattr("from_bytes")?;
+        Ok((from_bytes, (slf.borrow().to_bytes(slf.py()),)))
+    }
+
+    /// Models are immutable, so copies share the trees.
+    fn __copy__(&self) -> Self {
+        Self {
+            model: Arc::clone(&self.model),
+        }
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.__copy__()
+    }
+
+    /// Predicts a list of pyarrow record batches, whose columns are in `feature_names` order, or
+    /// any input described in `extract_input`.
+    ///
+    /// Chunk sizes and engine default to the model's configuration, which `autotune` or a
+    /// tuning result cached in the model file may have set. `accumulation="f64"` makes the
+    /// results independent of the chunk sizes, engine and thread count.
+    #[pyo3(signature = (py_record_batches, *, row_chunk_size=None, tree_chunk_size=None, engine=None, accumulation=None, n_threads=None))]
+    #[allow(clippy::too_many_arguments)]
     fn predict_batches(
         &self,
         py: Python,
-        py_record_batches: &Bound<'_, PyList>,
-        row_chunk_size: usize,
-        tree_chunk_size: usize,
+        py_record_batches: &Bound<'_, PyAny>,
+        row_chunk_size: Option<usize>,
+        tree_chunk_size: Option<usize>,
+        engine: Option<&str>,
+        accumulation: Option<&str>,
+        n_threads: Option<usize>,
     ) -> PyArrowResult<PyObject> {
-        let mut batches = Vec::with_capacity(py_record_batches.len());
-        // Need this clone to make config work. perhaps, another way to avoid it?
-        let model = Arc::new({
-            let mut m = (*self.model).clone();
-            m.set_config(PredictorConfig {
-                row_chunk_size,
-                tree_chunk_size,
-            });
-            m
-        });
-
-        for py_batch in py_record_batches.iter() {
-            let py_arrow_type = py_batch.extract::<PyArrowType<RecordBatch>>()?;
-            let record_batch = py_arrow_type.0;
-            let arrays: Vec<ArrayRef> = record_batch
-                .columns()
-                .iter()
-                .map(|col| {
-                    if col.data_type() == &DataType::Float64 {
-                        cast(col, &DataType::Float32).unwrap()
-                    } else {
-                        Arc::clone(col)
-                    }
-                })
-                .collect();
-            let new_schema = Schema::new(
-                record_batch
-                    .schema()
-                    .fields()
-                    .iter()
-                    .map(|field| {
-                        if field.data_type() == &DataType::Float64 {
-                            Arc::new(Field::new(
-                                field.name(),
-                                DataType::Float32,
-                                field.is_nullable(),
-                            ))
-                        } else {
-                            field.clone()
-                        }
-                    })
-                    .collect::<Vec<Arc<Field>>>(),
-            );
-            let float32_batch = RecordBatch::try_new(Arc::new(new_schema), arrays).unwrap();
-            batches.push(float32_batch);
+        let mut config = self.model.config.clone();
+        if let Some(engine) = engine {
+            let engine = engine
+                .parse::<PredictionEngine>()
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            config = config.with_engine(engine);
+        }
+        if let Some(accumulation) = accumulation {
+            let accumulation = accumulation
+                .parse::<Accumulation>()
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            config = config.with_accumulation(accumulation);
+        }
+        if n_threads.is_some() {
+            config = config.with_parallelism(parallelism(n_threads)?);
         }
+        if let Some(row_chunk_size) = row_chunk_size {
+            config = config
+                .with_row_chunk_size(row_chunk_size)
+                .map_err(config_error)?;
+        }
+        if let Some(tree_chunk_size) = tree_chunk_size {
+            config = config
+                .with_tree_chunk_size(tree_chunk_size)
+                .map_err(config_error)?;
+        }
+        let model = &self.model;
+        let batches = match py_record_batches.downcast::<PyList>() {
+            Ok(py_record_batches) => extract_batches(py_record_batches)?,
+            Err(_) => {
+                let input = extract_input(model, py_record_batches)?;
+                return predict_input(py, model, &config, input);
+            }
+        };
 
-        let predictions_array = model
-            .predict_batches(&batches)
+        let predictions_array = py
+            .allow_threads(|| model.predict_batches_with_config(&batches, &config))
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
 
         let field = Field::new("predictions", DataType::Float32, false);
         Ok(PyArray::new(Arc::new(predictions_array), Arc::new(field)).to_pyarrow(py)?)
     }
 
+    /// Benchmarks chunk sizes, engines and node layouts on a sample batch and returns the model
+    /// with the fastest combination. With `cache_path`, the result is also stored in that
+    /// XGBoost JSON model file and applied whenever it is loaded.
+    #[pyo3(signature = (py_batch, *, cache_path=None))]
+    fn autotune(
+        &self,
+        py: Python,
+        py_batch: &Bound<'_, PyAny>,
+        cache_path: Option<PathBuf>,
+    ) -> PyResult<Self> {
+        let batch = float32_batch(py_batch.extract::<PyArrowType<RecordBatch>>()?.0);
+        let model = Arc::clone(&self.model);
+        let tuning = py
+            .allow_threads(|| model.autotune(&batch, &TuningOptions::default()))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+
+        if let Some(path) = cache_path {
+            let to_py_err = |e: &dyn std::fmt::Display| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())
+            };
+            let contents = std::fs::read_to_string(&path).map_err(|e| to_py_err(&e))?;
+            let mut model_json: serde_json::Value =
+                serde_json::from_str(&contents).map_err(|e| to_py_err(&e))?;
+            tuning.write_to_model_json(&mut model_json);
+            std::fs::write(&path, model_json.to_string()).map_err(|e| to_py_err(&e))?;
+        }
+
+        Ok(Self {
+            model: Arc::new(tuning.apply(&self.model)),
+        })
+    }
+
     fn prune(&self, predicates: &Bound<'_, PyList>) -> PyResult<Self> {
         let mut predicate = Predicate::new();
         for pred in predicates.iter() {
@@ -185,7 +290,38 @@ impl PyGradientBoostedDecisionTrees {
         Ok(format!("{}", self.model))
     }
 
-    fn predict_arrays(&self, py: Python, py_arrays: &Bound<'_, PyList>) -> PyArrowResult<PyObject> {
+    /// Feature importance keyed by feature name, as in XGBoost's `get_score`
+    #[pyo3(signature = (importance_type="weight"))]
+    fn feature_importance(&self, importance_type: &str) -> PyResult<HashMap<String, f64>> {
+        let kind = importance_type
+            .parse::<ImportanceType>()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        Ok(self.model.feature_importance(kind))
+    }
+
+    /// The model as an XGBoost JSON string, e.g. to save a pruned model
+    fn to_xgboost_json(&self) -> String {
+        self.model.to_xgboost_json().to_string()
+    }
+
+    /// Predicts a list of pyarrow arrays, one per required feature in the order of
+    /// `required_features`, or any input described in `extract_input`.
+    #[pyo3(signature = (py_arrays, *, n_threads=None))]
+    fn predict_arrays(
+        &self,
+        py: Python,
+        py_arrays: &Bound<'_, PyAny>,
+        n_threads: Option<usize>,
+    ) -> PyArrowResult<PyObject> {
+        let model = &self.model;
+        let mut config = model.config.clone();
+        if n_threads.is_some() {
+            config = config.with_parallelism(parallelism(n_threads)?);
+        }
+        let py_arrays = match py_arrays.downcast::<PyList>() {
+            Ok(py_arrays) => py_arrays,
+            Err(_) => return predict_input(py, model, &config, extract_input(model, py_arrays)?),
+        };
         let mut arrays = Vec::with_capacity(py_arrays.len());
 
         for py_array in py_arrays.iter() {
@@ -201,9 +337,8 @@ impl PyGradientBoostedDecisionTrees {
             arrays.push(processed_array);
         }
 
-        let predictions_array = self
-            .model
-            .predict_arrays(&arrays)
+        let predictions_array = py
+            .allow_threads(|| model.predict_arrays_with_config(&arrays, &config))
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
 
         let predictions_ref: ArrayRef = Arc::new(predictions_array);
@@ -217,6 +352,7 @@ impl PyGradientBoostedDecisionTrees {
         match tree_index {
             Some(idx) if idx < self.model.trees.len() => Ok(PyFeatureTree {
                 tree: self.model.trees[idx].clone(),
+                feature_names: self.model.tree_feature_names(),
             }),
             Some(idx) => Err(PyErr::new::<pyo3::exceptions::PyIndexError, _>(format!(
                 "Tree index {} out of range",
@@ -229,6 +365,254 @@ impl PyGradientBoostedDecisionTrees {
     }
 }
 
+fn extract_batches(py_record_batches: &Bound<'_, PyList>) -> PyResult<Vec<RecordBatch>> {
+    py_record_batches
+        .iter()
+        .map(|py_batch| {
+            let py_arrow_type = py_batch.extract::<PyArrowType<RecordBatch>>()?;
+            Ok(float32_batch(py_arrow_type.0))
+        })
+        .collect()
+}
+
+/// Inputs of the predict methods other than lists of pyarrow objects.
+enum Input {
+    /// A 2-D buffer with one column per feature, in `feature_names` order.
+    Matrix(Matrix),
+    /// The required features, looked up by name.
+    Columns(RecordBatch),
+}
+
+enum Matrix {
+    Float32(PyBuffer<f32>),
+    Float64(PyBuffer<f64>),
+}
+
+/// Besides lists of pyarrow objects, which each method reads in its own way, the predict
+/// methods accept:
+///
+/// - 2-D float32 or float64 NumPy arrays, or other objects exporting such a buffer, in C or
+///   Fortran order. They are read in place, with NaN as missing, and predictions are returned
+///   as a NumPy array.
+/// - pandas DataFrames and dicts of arrays, whose columns are matched to the features by name
+///   and may be in any order. Columns the trees do not use may be left out.
+fn extract_input(model: &GradientBoostedDecisionTrees, obj: &Bound<'_, PyAny>) -> PyResult<Input> {
+    if obj.is_instance_of::<PyDict>() || is_dataframe(obj)? {
+        return extract_columns(model, obj).map(Input::Columns);
+    }
+    if let Ok(buffer) = PyBuffer::<f32>::get_bound(obj) {
+        return Ok(Input::Matrix(Matrix::Float32(check_matrix(buffer)?)));
+    }
+    if let Ok(buffer) = PyBuffer::<f64>::get_bound(obj) {
+        return Ok(Input::Matrix(Matrix::Float64(check_matrix(buffer)?)));
+    }
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+        "Expected a list of pyarrow objects, a dict or pandas DataFrame of columns, or a 2-D \
+         float32 or float64 array, got {}",
+        obj.get_type().name()?
+    )))
+}
+
+/// Whether `obj` is a pandas DataFrame, without importing pandas when it is not loaded.
+fn is_dataframe(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let modules = obj.py().import_bound("sys")?.getattr("modules")?;
+    match modules.downcast::<PyDict>()?.get_item("pandas")? {
+        Some(pandas) => obj.is_instance(&pandas.getattr("DataFrame")?),
+        None => Ok(false),
+    }
+}
+
+/// Collects the required features of a dict or DataFrame into a batch. Values that are not
+/// Arrow arrays, such as NumPy arrays, lists or pandas Series, go through `pyarrow.array`.
+fn extract_columns(
+    model: &GradientBoostedDecisionTrees,
+    obj: &Bound<'_, PyAny>,
+) -> PyResult<RecordBatch> {
+    let pyarrow = obj.py().import_bound("pyarrow")?;
+    let mut columns = Vec::with_capacity(model.required_features.len());
+    for index in model.original_feature_indices() {
+        let name = &model.feature_names[index];
+        if !obj.contains(name)? {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Missing feature column: {}",
+                name
+            )));
+        }
+        let value = obj.get_item(name)?;
+        let array = match value.extract::<PyArray>() {
+            Ok(array) => array,
+            Err(_) => pyarrow.call_method1("array", (value,))?.extract()?,
+        };
+        columns.push((name.clone(), array.array()));
+    }
+    RecordBatch::try_from_iter(columns)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+fn check_matrix<T: Element>(buffer: PyBuffer<T>) -> PyResult<PyBuffer<T>> {
+    if buffer.dimensions() != 2 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Expected a 2-D array, got {} dimensions",
+            buffer.dimensions()
+        )));
+    }
+    Ok(buffer)
+}
+
+/// Scores the matrix in place, through its strides. Writable arrays are scored with the GIL
+/// held, so that Python code cannot write to them meanwhile; read-only ones without it.
+/// Arrays with negative or unaligned strides, such as reversed views, are copied first.
+fn predict_matrix<T: Element + DenseValue>(
+    py: Python,
+    model: &GradientBoostedDecisionTrees,
+    config: &PredictorConfig,
+    buffer: &PyBuffer<T>,
+) -> PyResult<Float32Array> {
+    let to_py_err = |e: ArrowError| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string());
+    let (n_rows, n_cols) = (buffer.shape()[0], buffer.shape()[1]);
+    let item_size = buffer.item_size() as isize;
+    if buffer
+        .strides()
+        .iter()
+        .any(|&stride| stride < 0 || stride % item_size != 0)
+    {
+        let values = buffer.to_vec(py)?;
+        return py
+            .allow_threads(|| {
+                model.predict_strided_with_config(&values, n_rows, n_cols, n_cols, 1, config)
+            })
+            .map_err(to_py_err);
+    }
+    let row_stride = (buffer.strides()[0] / item_size) as usize;
+    let col_stride = (buffer.strides()[1] / item_size) as usize;
+    let len = if n_rows == 0 || n_cols == 0 {
+        0
+    } else {
+        (n_rows - 1) * row_stride + (n_cols - 1) * col_stride + 1
+    };
+    let predict = || {
+        // SAFETY: the strides are non-negative multiples of the item size, so every element
+        // lies within the first `len` items of the buffer, which `PyBuffer` checked to be
+        // aligned for `T` and keeps alive. Nothing writes to it while it is read: either the
+        // exporter marked it read-only or the caller holds the GIL.
+        let values = unsafe { std::slice::from_raw_parts(buffer.buf_ptr() as *const T, len) };
+        model.predict_strided_with_config(values, n_rows, n_cols, row_stride, col_stride, config)
+    };
+    let predictions = if buffer.readonly() {
+        py.allow_threads(predict)
+    } else {
+        predict()
+    };
+    predictions.map_err(to_py_err)
+}
+
+/// Predicts a matrix or named columns, returning NumPy for a matrix and pyarrow otherwise.
+fn predict_input(
+    py: Python,
+    model: &GradientBoostedDecisionTrees,
+    config: &PredictorConfig,
+    input: Input,
+) -> PyArrowResult<PyObject> {
+    let to_py_err = |e: ArrowError| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string());
+    let predictions = match &input {
+        Input::Matrix(Matrix::Float32(buffer)) => predict_matrix(py, model, config, buffer)?,
+        Input::Matrix(Matrix::Float64(buffer)) => predict_matrix(py, model, config, buffer)?,
+        Input::Columns(batch) => py
+            .allow_threads(|| {
+                let features = model.feature_arrays_by_name(batch)?;
+                model.predict_arrays_with_config(&features, config)
+            })
+            .map_err(to_py_err)?,
+    };
+
+    let field = Field::new("predictions", DataType::Float32, false);
+    let predictions = PyArray::new(Arc::new(predictions), Arc::new(field)).to_pyarrow(py)?;
+    match input {
+        Input::Matrix(_) => {
+            // A zero-copy view of the Arrow buffer would be read-only; callers expect to be able
+            // to modify NumPy results in place.
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("zero_copy_only", false)?;
+            kwargs.set_item("writable", true)?;
+            Ok(predictions.call_method_bound(py, "to_numpy", (), Some(&kwargs))?)
+        }
+        Input::Columns(_) => Ok(predictions),
+    }
+}
+
+/// Casts Float64 columns, which pandas produces by default, to Float32.
+fn float32_batch(record_batch: RecordBatch) -> RecordBatch {
+    let arrays: Vec<ArrayRef> = record_batch
+        .columns()
+        .iter()
+        .map(|col| {
+            if col.data_type() == &DataType::Float64 {
+                cast(col, &DataType::Float32).unwrap()
+            } else {
+                Arc::clone(col)
+            }
+        })
+        .collect();
+    let new_schema = Schema::new(
+        record_batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                if field.data_type() == &DataType::Float64 {
+                    Arc::new(Field::new(
+                        field.name(),
+                        DataType::Float32,
+                        field.is_nullable(),
+                    ))
+                } else {
+                    field.clone()
+                }
+            })
+            .collect::<Vec<Arc<Field>>>(),
+    );
+    RecordBatch::try_new(Arc::new(new_schema), arrays).unwrap()
+}
+
+fn config_error(e: PredictorConfigError) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+}
+
+/// Maps the `n_threads` argument to a `Parallelism`, reusing one pool per thread count.
+fn parallelism(n_threads: Option<usize>) -> PyResult<Parallelism> {
+    static POOLS: OnceLock<Mutex<HashMap<usize, Parallelism>>> = OnceLock::new();
+    let Some(n_threads) = n_threads else {
+        return Ok(Parallelism::Global);
+    };
+    let mut pools = POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(parallelism) = pools.get(&n_threads) {
+        return Ok(parallelism.clone());
+    }
+    let parallelism = Parallelism::threads(n_threads)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+    pools.insert(n_threads, parallelism.clone());
+    Ok(parallelism)
+}
+
+fn model_from_bytes(bytes: &[u8]) -> PyResult<PyGradientBoostedDecisionTrees> {
+    let model = GradientBoostedDecisionTrees::from_bytes(bytes)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    Ok(PyGradientBoostedDecisionTrees {
+        model: Arc::new(model),
+    })
+}
+
+/// Loads a model written by `PyGradientBoostedDecisionTrees.save`.
+#[pyfunction]
+pub fn load(path: PathBuf) -> PyResult<PyGradientBoostedDecisionTrees> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+    model_from_bytes(&bytes)
+}
+
 #[pyfunction]
 pub fn json_load(path: PathBuf) -> PyResult<PyGradientBoostedDecisionTrees> {
     let str_path = path
@@ -0,0 +1,226 @@
+use crate::tree::GradientBoostedDecisionTrees;
+use arrow::array::{Array, ArrayRef, Float32Array};
+use arrow::compute::concat;
+use arrow::error::ArrowError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout_at, Instant};
+
+/// How requests to one model are merged into a single `predict_arrays` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Requests stop being merged once the batch has this many rows.
+    pub max_batch_rows: usize,
+    /// Time to wait for more requests after the first one of a batch arrives.
+    pub max_delay: Duration,
+    /// Requests waiting for a batch before new ones are rejected.
+    pub queue_size: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_batch_rows: 65536,
+            max_delay: Duration::from_millis(2),
+            queue_size: 1024,
+        }
+    }
+}
+
+/// Counters of the requests scored for one model.
+#[derive(Debug, Default)]
+pub(crate) struct BatchStats {
+    pub requests: AtomicUsize,
+    pub batches: AtomicUsize,
+    pub rows: AtomicUsize,
+}
+
+struct PredictRequest {
+    /// Feature arrays as returned by `feature_arrays_by_name`.
+    features: Vec<ArrayRef>,
+    num_rows: usize,
+    reply: oneshot::Sender<Result<Float32Array, String>>,
+}
+
+/// Handle to the task that scores the requests of one model.
+#[derive(Clone)]
+pub(crate) struct Batcher {
+    sender: mpsc::Sender<PredictRequest>,
+    pub stats: Arc<BatchStats>,
+}
+
+impl Batcher {
+    /// Spawns the batching task on the current tokio runtime.
+    pub fn spawn(model: Arc<GradientBoostedDecisionTrees>, options: BatchOptions) -> Self {
+        let (sender, receiver) = mpsc::channel(options.queue_size.max(1));
+        let stats = Arc::new(BatchStats::default());
+        tokio::spawn(run(model, receiver, options, stats.clone()));
+        Self { sender, stats }
+    }
+
+    /// Scores `num_rows` rows, possibly together with other pending requests.
+    pub async fn predict(
+        &self,
+        features: Vec<ArrayRef>,
+        num_rows: usize,
+    ) -> Result<Float32Array, String> {
+        let (reply, response) = oneshot::channel();
+        let request = PredictRequest {
+            features,
+            num_rows,
+            reply,
+        };
+        self.sender
+            .try_send(request)
+            .map_err(|_| "Too many pending requests".to_string())?;
+        response
+            .await
+            .map_err(|_| "The model's batching task stopped".to_string())?
+    }
+}
+
+async fn run(
+    model: Arc<GradientBoostedDecisionTrees>,
+    mut receiver: mpsc::Receiver<PredictRequest>,
+    options: BatchOptions,
+    stats: Arc<BatchStats>,
+) {
+    while let Some(first) = receiver.recv().await {
+        let deadline = Instant::now() + options.max_delay;
+        let mut num_rows = first.num_rows;
+        let mut requests = vec![first];
+        while num_rows < options.max_batch_rows {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(request)) => {
+                    num_rows += request.num_rows;
+                    requests.push(request);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        stats.requests.fetch_add(requests.len(), Ordering::Relaxed);
+        stats.batches.fetch_add(1, Ordering::Relaxed);
+        stats.rows.fetch_add(num_rows, Ordering::Relaxed);
+
+        // Scoring is CPU bound; run it off the async workers. Requests that arrive meanwhile
+        // queue up and form the next batch.
+        let model = model.clone();
+        let scored = tokio::task::spawn_blocking(move || {
+            let result = score(&model, &requests, num_rows).map_err(|e| e.to_string());
+            (requests, result)
+        })
+        .await;
+        let Ok((requests, result)) = scored else {
+            // The requests were dropped with the panicking task, which fails their replies.
+            continue;
+        };
+
+        let mut offset = 0;
+        for request in requests {
+            let reply = match &result {
+                Ok(predictions) => Ok(predictions.slice(offset, request.num_rows)),
+                Err(e) => Err(e.clone()),
+            };
+            offset += request.num_rows;
+            // The client may have disconnected.
+            let _ = request.reply.send(reply);
+        }
+    }
+}
+
+fn score(
+    model: &GradientBoostedDecisionTrees,
+    requests: &[PredictRequest],
+    num_rows: usize,
+) -> Result<Float32Array, ArrowError> {
+    if model.required_features.is_empty() {
+        return model.predict_dense(&[], num_rows, 0);
+    }
+    if let [request] = requests {
+        return model.predict_arrays(&request.features);
+    }
+    let features = (0..model.required_features.len())
+        .map(|feature| {
+            let arrays: Vec<&dyn Array> = requests
+                .iter()
+                .map(|request| request.features[feature].as_ref())
+                .collect();
+            concat(&arrays)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    model.predict_arrays(&features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{FeatureType, VecTreeNodes};
+
+    fn model() -> Arc<GradientBoostedDecisionTrees> {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 1.0])
+            .default_left(vec![true, false, false])
+            .build()
+            .unwrap();
+        Arc::new(GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["a".into()]),
+            feature_types: Arc::new(vec![FeatureType::Float]),
+            required_features: [0].into_iter().collect(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_batches() {
+        let options = BatchOptions {
+            max_delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let batcher = Batcher::spawn(model(), options);
+        let request = |values: Vec<f32>| {
+            let batcher = batcher.clone();
+            async move {
+                let num_rows = values.len();
+                let features: Vec<ArrayRef> = vec![Arc::new(Float32Array::from(values))];
+                batcher.predict(features, num_rows).await.unwrap()
+            }
+        };
+
+        let (first, second, third) = tokio::join!(
+            request(vec![0.0, 1.0]),
+            request(vec![1.0]),
+            request(vec![0.0, 0.0, 1.0])
+        );
+        assert_eq!(first.values(), &[-1.0, 1.0]);
+        assert_eq!(second.values(), &[1.0]);
+        assert_eq!(third.values(), &[-1.0, -1.0, 1.0]);
+        assert_eq!(batcher.stats.requests.load(Ordering::Relaxed), 3);
+        assert_eq!(batcher.stats.batches.load(Ordering::Relaxed), 1);
+        assert_eq!(batcher.stats.rows.load(Ordering::Relaxed), 6);
+    }
+
+    #[tokio::test]
+    async fn test_batch_row_limit() {
+        let options = BatchOptions {
+            max_batch_rows: 2,
+            max_delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let batcher = Batcher::spawn(model(), options);
+        let features = || -> Vec<ArrayRef> { vec![Arc::new(Float32Array::from(vec![0.0, 1.0]))] };
+        let (first, second) = tokio::join!(
+            batcher.predict(features(), 2),
+            batcher.predict(features(), 2)
+        );
+        assert_eq!(first.unwrap().values(), &[-1.0, 1.0]);
+        assert_eq!(second.unwrap().values(), &[-1.0, 1.0]);
+        assert_eq!(batcher.stats.batches.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,297 @@
+//! HTTP inference server, enabled by the `server` cargo feature.
+//!
+//! | Method | Path | |
+//! |---|---|---|
+//! | `GET` | `/health` | Liveness and the number of loaded models |
+//! | `GET` | `/models` | Metadata of every model |
+//! | `GET` | `/models/{name}` | Metadata of one model |
+//! | `POST` | `/models/{name}/predict` | Scores JSON rows or an Arrow IPC stream |
+//!
+//! JSON requests carry `{"rows": [{"feature": value, ...}, ...]}` and get
+//! `{"predictions": [...]}` back; missing features and `null` are treated as missing values.
+//! Requests with the `application/vnd.apache.arrow.stream` content type carry an IPC stream
+//! whose columns are matched to features by name, and get a stream with a `prediction` column.
+//!
+//! Concurrent requests to a model are merged into one `predict_arrays` call, see
+//! [`BatchOptions`].
+
+mod batcher;
+
+pub use batcher::BatchOptions;
+
+use crate::tree::{GradientBoostedDecisionTrees, PREDICTION_COLUMN};
+use arrow::array::{ArrayRef, Float32Array};
+use arrow::compute::concat_batches;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use batcher::Batcher;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Content type of Arrow IPC streams.
+pub const ARROW_STREAM: &str = "application/vnd.apache.arrow.stream";
+
+struct ModelEntry {
+    model: Arc<GradientBoostedDecisionTrees>,
+    batcher: Batcher,
+}
+
+type Models = Arc<BTreeMap<String, ModelEntry>>;
+
+/// Builds the server's routes for `models`, keyed by the name used in request paths. Models
+/// may be passed as `Arc`s to share them with other services.
+///
+/// Must be called within a tokio runtime, which runs one batching task per model. Panics if two
+/// models have the same name.
+pub fn router<M: Into<Arc<GradientBoostedDecisionTrees>>>(
+    models: impl IntoIterator<Item = (String, M)>,
+    options: BatchOptions,
+) -> Router {
+    let mut models_by_name = BTreeMap::new();
+    for (name, model) in models {
+        let model: Arc<GradientBoostedDecisionTrees> = model.into();
+        let batcher = Batcher::spawn(model.clone(), options);
+        if models_by_name
+            .insert(name.clone(), ModelEntry { model, batcher })
+            .is_some()
+        {
+            panic!("Duplicate model name: {}", name);
+        }
+    }
+
+    Router::new()
+        .route("/health", get(health))
+        .route("/models", get(list_models))
+        .route("/models/:name", get(model_metadata))
+        .route("/models/:name/predict", post(predict))
+        .with_state(Arc::new(models_by_name))
+}
+
+/// Serves `router` on `listener` until the process is stopped.
+pub async fn serve(listener: TcpListener, router: Router) -> std::io::Result<()> {
+    axum::serve(listener, router).await
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<ArrowError> for ApiError {
+    fn from(e: ArrowError) -> Self {
+        Self::bad_request(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+async fn health(State(models): State<Models>) -> Json<Value> {
+    Json(json!({ "status": "ok", "models": models.len() }))
+}
+
+async fn list_models(State(models): State<Models>) -> Json<Value> {
+    let metadata: Vec<Value> = models
+        .iter()
+        .map(|(name, entry)| metadata(name, entry))
+        .collect();
+    Json(json!({ "models": metadata }))
+}
+
+async fn model_metadata(
+    State(models): State<Models>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let entry = find_model(&models, &name)?;
+    Ok(Json(metadata(&name, entry)))
+}
+
+fn find_model<'a>(models: &'a Models, name: &str) -> Result<&'a ModelEntry, ApiError> {
+    models.get(name).ok_or_else(|| ApiError {
+        status: StatusCode::NOT_FOUND,
+        message: format!("Unknown model: {}", name),
+    })
+}
+
+fn metadata(name: &str, entry: &ModelEntry) -> Value {
+    let model = &entry.model;
+    let feature_types: Vec<String> = model.feature_types.iter().map(|t| t.to_string()).collect();
+    let stats = &entry.batcher.stats;
+    json!({
+        "name": name,
+        "feature_names": model.feature_names.as_ref(),
+        "feature_types": feature_types,
+        "required_features": model.tree_feature_names(),
+        "objective": format!("{:?}", model.objective),
+        "num_trees": model.num_trees(),
+        "stats": {
+            "requests": stats.requests.load(Ordering::Relaxed),
+            "batches": stats.batches.load(Ordering::Relaxed),
+            "rows": stats.rows.load(Ordering::Relaxed),
+        },
+    })
+}
+
+#[derive(Deserialize)]
+struct PredictRows {
+    rows: Vec<Map<String, Value>>,
+}
+
+async fn predict(
+    State(models): State<Models>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let entry = find_model(&models, &name)?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/json");
+
+    if content_type.starts_with(ARROW_STREAM) {
+        let reader = StreamReader::try_new(Cursor::new(body), None)?;
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        let batch = concat_batches(&schema, &batches)?;
+        let predictions = score(entry, &batch).await?;
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            PREDICTION_COLUMN,
+            DataType::Float32,
+            false,
+        )]));
+        let output = RecordBatch::try_new(schema.clone(), vec![Arc::new(predictions)])?;
+        let mut writer = StreamWriter::try_new(Vec::new(), &schema)?;
+        writer.write(&output)?;
+        writer.finish()?;
+        let bytes = writer.into_inner()?;
+        Ok(([(header::CONTENT_TYPE, ARROW_STREAM)], bytes).into_response())
+    } else if content_type.starts_with("application/json") {
+        let request: PredictRows = serde_json::from_slice(&body).map_err(ApiError::bad_request)?;
+        let batch = rows_to_batch(&entry.model, &request.rows)?;
+        let predictions = score(entry, &batch).await?;
+        Ok(Json(json!({ "predictions": predictions.values().as_ref() })).into_response())
+    } else {
+        Err(ApiError {
+            status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            message: format!(
+                "Unsupported content type {}, expected application/json or {}",
+                content_type, ARROW_STREAM
+            ),
+        })
+    }
+}
+
+async fn score(entry: &ModelEntry, batch: &RecordBatch) -> Result<Float32Array, ApiError> {
+    // Checked here so that a malformed request cannot fail the batch it would be merged into.
+    let features = entry.model.feature_arrays_by_name(batch)?;
+    entry
+        .batcher
+        .predict(features, batch.num_rows())
+        .await
+        .map_err(|message| ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message,
+        })
+}
+
+/// One `Float32` column per feature used by the trees, whatever its type: the engines read
+/// every value as `f32`, and `feature_arrays_by_name` passes `Float32` columns through, so
+/// fractional values of integer features are not truncated.
+fn rows_to_batch(
+    model: &GradientBoostedDecisionTrees,
+    rows: &[Map<String, Value>],
+) -> Result<RecordBatch, ApiError> {
+    let names = model.tree_feature_names();
+    let mut fields = Vec::with_capacity(names.len());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(names.len());
+    for name in names {
+        let values = rows
+            .iter()
+            .map(|row| match row.get(&name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Bool(value)) => Ok(Some(if *value { 1.0 } else { 0.0 })),
+                // Integers are rounded to `f32` once, as the engines do with `Int64` columns.
+                Some(Value::Number(value)) => Ok(value
+                    .as_i64()
+                    .map(|value| value as f32)
+                    .or_else(|| value.as_f64().map(|value| value as f32))),
+                Some(other) => Err(ApiError::bad_request(format!(
+                    "Feature {} must be a number, a boolean or null, got {}",
+                    name, other
+                ))),
+            })
+            .collect::<Result<Float32Array, _>>()?;
+        fields.push(Field::new(name, DataType::Float32, true));
+        columns.push(Arc::new(values));
+    }
+    let options = arrow::record_batch::RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        columns,
+        &options,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{FeatureType, VecTreeNodes};
+
+    #[test]
+    fn test_rows_keep_fractions_of_int_features() {
+        // n < 59.5 -> -1, else 1.
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![59.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 1.0])
+            .default_left(vec![true, false, false])
+            .build()
+            .unwrap();
+        let model = GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["n".into()]),
+            feature_types: Arc::new(vec![FeatureType::Int]),
+            required_features: [0].into_iter().collect(),
+            ..Default::default()
+        };
+        let rows: Vec<Map<String, Value>> =
+            serde_json::from_str(r#"[{"n": 59}, {"n": 59.7}, {"n": 16777217}, {"n": null}]"#)
+                .unwrap();
+
+        let batch = rows_to_batch(&model, &rows).ok().unwrap();
+        let features = model.feature_arrays_by_name(&batch).unwrap();
+        let values = features[0].as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(values.value(2), 16777217_i64 as f32);
+        let predictions = model.predict_arrays(&features).unwrap();
+        assert_eq!(predictions.values(), &[-1.0, 1.0, 1.0, -1.0]);
+    }
+}
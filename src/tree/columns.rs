@@ -0,0 +1,230 @@
+use arrow::array::{Array, ArrayRef, BooleanArray, Float32Array, Int64Array};
+use arrow::buffer::{BooleanBuffer, NullBuffer};
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+
+enum ColumnValues<'a> {
+    Float32(&'a [f32]),
+    Int64(&'a [i64]),
+    Boolean(&'a BooleanBuffer),
+}
+
+/// Borrowed view of one input column. Values are converted to `f32` on read and nulls are
+/// checked against the validity bitmap only when the column has one.
+pub(crate) struct FeatureColumn<'a> {
+    values: ColumnValues<'a>,
+    nulls: Option<&'a NullBuffer>,
+}
+
+impl<'a> FeatureColumn<'a> {
+    fn try_new(array: &'a ArrayRef) -> Result<Self, ArrowError> {
+        let values = match array.data_type() {
+            DataType::Float32 => {
+                let array = array
+                    .as_any()
+                    .downcast_ref::<Float32Array>()
+                    .ok_or_else(|| {
+                        ArrowError::InvalidArgumentError("Expected Float32Array".into())
+                    })?;
+                ColumnValues::Float32(array.values())
+            }
+            DataType::Int64 => {
+                let array = array.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
+                    ArrowError::InvalidArgumentError("Expected Int64Array".into())
+                })?;
+                ColumnValues::Int64(array.values())
+            }
+            DataType::Boolean => {
+                let array = array
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .ok_or_else(|| {
+                        ArrowError::InvalidArgumentError("Expected BooleanArray".into())
+                    })?;
+                ColumnValues::Boolean(array.values())
+            }
+            actual_type => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Unsupported data type: {:?}",
+                    actual_type
+                )));
+            }
+        };
+        Ok(Self {
+            values,
+            nulls: array.nulls().filter(|nulls| nulls.null_count() > 0),
+        })
+    }
+
+    /// Value of `row` as `f32`, with nulls read as NaN.
+    #[inline]
+    pub(crate) fn value(&self, row: usize) -> f32 {
+        if self.nulls.is_some_and(|nulls| nulls.is_null(row)) {
+            return f32::NAN;
+        }
+        match self.values {
+            ColumnValues::Float32(values) => values[row],
+            ColumnValues::Int64(values) => values[row] as f32,
+            ColumnValues::Boolean(values) => {
+                if values.value(row) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Input rows readable by the prediction engines, with features in compacted order.
+pub(crate) trait FeatureSource: Sync {
+    fn num_rows(&self) -> usize;
+
+    fn num_features(&self) -> usize;
+
+    /// Copies `row_indices` into `rows`, row-major with `row_len` values per row and missing
+    /// values as NaN.
+    fn gather_rows(&self, row_indices: &[usize], row_len: usize, rows: &mut [f32]);
+}
+
+/// Borrowed view of the feature columns of a batch, in compacted feature order.
+///
+/// Null-free `Float32` buffers are read in place; nothing is copied until rows are gathered,
+/// so peak memory grows with the row chunk size rather than with the batch.
+pub(crate) struct FeatureColumns<'a> {
+    columns: Vec<FeatureColumn<'a>>,
+    num_rows: usize,
+}
+
+impl<'a> FeatureColumns<'a> {
+    pub(crate) fn try_new(
+        feature_arrays: &'a [ArrayRef],
+        num_rows: usize,
+    ) -> Result<Self, ArrowError> {
+        if let Some((index, array)) = feature_arrays
+            .iter()
+            .enumerate()
+            .find(|(_, array)| array.len() != num_rows)
+        {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Feature array {} has {} rows, expected {}",
+                index,
+                array.len(),
+                num_rows
+            )));
+        }
+        Ok(Self {
+            columns: feature_arrays
+                .iter()
+                .map(FeatureColumn::try_new)
+                .collect::<Result<_, _>>()?,
+            num_rows,
+        })
+    }
+
+    pub(crate) fn columns(&self) -> &[FeatureColumn<'a>] {
+        &self.columns
+    }
+}
+
+impl FeatureSource for FeatureColumns<'_> {
+    fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    fn num_features(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Columns are walked one at a time so the type dispatch happens once per column and chunk.
+    fn gather_rows(&self, row_indices: &[usize], row_len: usize, rows: &mut [f32]) {
+        debug_assert!(row_len >= self.columns.len());
+        debug_assert!(rows.len() >= row_indices.len() * row_len);
+        for (feature, column) in self.columns.iter().enumerate() {
+            let targets = rows[feature..].iter_mut().step_by(row_len);
+            match column.values {
+                ColumnValues::Float32(values) => {
+                    for (target, &row) in targets.zip(row_indices) {
+                        *target = values[row];
+                    }
+                }
+                ColumnValues::Int64(values) => {
+                    for (target, &row) in targets.zip(row_indices) {
+                        *target = values[row] as f32;
+                    }
+                }
+                ColumnValues::Boolean(values) => {
+                    for (target, &row) in targets.zip(row_indices) {
+                        *target = if values.value(row) { 1.0 } else { 0.0 };
+                    }
+                }
+            }
+            if let Some(nulls) = column.nulls {
+                let targets = rows[feature..].iter_mut().step_by(row_len);
+                for (target, &row) in targets.zip(row_indices) {
+                    if nulls.is_null(row) {
+                        *target = f32::NAN;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_gather_rows_reads_nulls_lazily() {
+        let float = Float32Array::from(vec![Some(0.5), None, Some(-1.0), Some(2.0)]);
+        let arrays: Vec<ArrayRef> = vec![
+            // Sliced arrays keep their offset into the shared buffers.
+            Arc::new(float.slice(1, 3)),
+            Arc::new(Int64Array::from(vec![Some(3), Some(-4), None])),
+            Arc::new(BooleanArray::from(vec![None, Some(true), Some(false)])),
+            Arc::new(Float32Array::from(vec![1.0, 2.0, 3.0])),
+        ];
+        let columns = FeatureColumns::try_new(&arrays, 3).unwrap();
+        assert_eq!(columns.num_rows(), 3);
+        assert_eq!(columns.num_features(), 4);
+
+        let mut rows = vec![0.0; 3 * 5];
+        columns.gather_rows(&[2, 0, 1], 5, &mut rows);
+        let expected = [
+            [2.0, f32::NAN, 0.0, 3.0],
+            [f32::NAN, 3.0, f32::NAN, 1.0],
+            [-1.0, -4.0, 1.0, 2.0],
+        ];
+        for (i, row) in expected.iter().enumerate() {
+            for (feature, &value) in row.iter().enumerate() {
+                let actual = rows[i * 5 + feature];
+                assert_eq!(actual.to_bits(), value.to_bits(), "row {}", i);
+            }
+        }
+
+        let lazy: Vec<f32> = (0..3).map(|row| columns.columns()[1].value(row)).collect();
+        assert_eq!(lazy[..2], [3.0, -4.0]);
+        assert!(lazy[2].is_nan());
+    }
+
+    #[test]
+    fn test_unsupported_column_type() {
+        let arrays: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec!["a"]))];
+        assert!(FeatureColumns::try_new(&arrays, 1).is_err());
+    }
+
+    #[test]
+    fn test_unequal_column_lengths() {
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Float32Array::from(vec![1.0, 2.0])),
+            Arc::new(Float32Array::from(vec![1.0])),
+        ];
+        let err = FeatureColumns::try_new(&arrays, 2).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Feature array 1 has 1 rows, expected 2"));
+    }
+}
@@ -0,0 +1,383 @@
+use super::columns::FeatureSource;
+use super::trees::{GradientBoostedDecisionTrees, PredictorConfig};
+use arrow::array::Float32Array;
+use arrow::error::ArrowError;
+
+/// Element type of the dense matrices read by
+/// [`predict_strided`](GradientBoostedDecisionTrees::predict_strided).
+pub trait DenseValue: Copy + Sync {
+    fn to_f32(self) -> f32;
+}
+
+impl DenseValue for f32 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl DenseValue for f64 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+/// Strided matrix in the original feature order, read through the compacted feature indices.
+struct DenseRows<'a, T> {
+    values: &'a [T],
+    num_rows: usize,
+    row_stride: usize,
+    col_stride: usize,
+    /// Original column of each compacted feature.
+    columns: Vec<usize>,
+}
+
+impl<T: DenseValue> FeatureSource for DenseRows<'_, T> {
+    fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    fn num_features(&self) -> usize {
+        self.columns.len()
+    }
+
+    fn gather_rows(&self, row_indices: &[usize], row_len: usize, rows: &mut [f32]) {
+        for (&row, out) in row_indices.iter().zip(rows.chunks_mut(row_len)) {
+            let start = row * self.row_stride;
+            for (target, &column) in out.iter_mut().zip(&self.columns) {
+                *target = self.values[start + column * self.col_stride].to_f32();
+            }
+        }
+    }
+}
+
+/// Compressed sparse rows in the original feature order. Absent entries are missing values.
+struct CsrRows<'a> {
+    indptr: &'a [usize],
+    indices: &'a [usize],
+    values: &'a [f32],
+    /// Compacted feature of each original column, or `usize::MAX` if the trees don't use it.
+    compact: Vec<usize>,
+    num_features: usize,
+}
+
+impl FeatureSource for CsrRows<'_> {
+    fn num_rows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    fn num_features(&self) -> usize {
+        self.num_features
+    }
+
+    fn gather_rows(&self, row_indices: &[usize], row_len: usize, rows: &mut [f32]) {
+        for (&row, out) in row_indices.iter().zip(rows.chunks_mut(row_len)) {
+            out.fill(f32::NAN);
+            let entries = self.indptr[row]..self.indptr[row + 1];
+            for (&column, &value) in self.indices[entries.clone()]
+                .iter()
+                .zip(&self.values[entries])
+            {
+                let feature = self.compact[column];
+                if feature != usize::MAX {
+                    out[feature] = value;
+                }
+            }
+        }
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Predicts a single row given in the original feature order. NaN marks a missing value.
+    ///
+    /// The row is scored on the calling thread with the traversal engine, which every engine
+    /// matches exactly.
+    pub fn predict_row(&self, row: &[f32]) -> Result<f32, ArrowError> {
+        let columns = self.engine_cache.feature_columns(&self.required_features);
+        self.check_num_cols(columns, row.len())?;
+
+        let mut stack = [0.0; 64];
+        let mut heap = Vec::new();
+        let features = if columns.len() <= stack.len() {
+            &mut stack[..columns.len()]
+        } else {
+            heap.resize(columns.len(), 0.0);
+            &mut heap[..]
+        };
+        for (feature, &column) in features.iter_mut().zip(columns) {
+            *feature = row[column];
+        }
+
+        let accumulation = self.config.accumulation();
+        let score = self.trees.chunks(self.config.tree_chunk_size()).fold(
+            self.base_score as f64,
+            |score, tree_chunk| {
+                accumulation.add_chunk(score, tree_chunk.iter().map(|tree| tree.predict(features)))
+            },
+        );
+        Ok(self.objective.compute_score(score as f32))
+    }
+
+    /// Predicts `n_rows` rows stored row-major in `values`, each with `n_cols` features in the
+    /// original feature order. NaN marks a missing value.
+    pub fn predict_dense(
+        &self,
+        values: &[f32],
+        n_rows: usize,
+        n_cols: usize,
+    ) -> Result<Float32Array, ArrowError> {
+        if n_rows.checked_mul(n_cols) != Some(values.len()) {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Expected {} x {} values, got {}",
+                n_rows,
+                n_cols,
+                values.len()
+            )));
+        }
+        self.predict_strided(values, n_rows, n_cols, n_cols, 1)
+    }
+
+    /// Predicts an `n_rows` x `n_cols` matrix whose element `(i, j)` is
+    /// `values[i * row_stride + j * col_stride]`, so row-major and column-major layouts are both
+    /// read in place. Columns are in the original feature order and NaN marks a missing value.
+    pub fn predict_strided<T: DenseValue>(
+        &self,
+        values: &[T],
+        n_rows: usize,
+        n_cols: usize,
+        row_stride: usize,
+        col_stride: usize,
+    ) -> Result<Float32Array, ArrowError> {
+        self.predict_strided_with_config(
+            values,
+            n_rows,
+            n_cols,
+            row_stride,
+            col_stride,
+            &self.config,
+        )
+    }
+
+    pub(crate) fn predict_strided_with_config<T: DenseValue>(
+        &self,
+        values: &[T],
+        n_rows: usize,
+        n_cols: usize,
+        row_stride: usize,
+        col_stride: usize,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        if n_rows > 0 && n_cols > 0 {
+            let last = (n_rows - 1)
+                .checked_mul(row_stride)
+                .zip((n_cols - 1).checked_mul(col_stride))
+                .and_then(|(row, col)| row.checked_add(col));
+            if !matches!(last, Some(last) if last < values.len()) {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "A {} x {} matrix with strides ({}, {}) does not fit in {} values",
+                    n_rows,
+                    n_cols,
+                    row_stride,
+                    col_stride,
+                    values.len()
+                )));
+            }
+        }
+        let columns = self.original_feature_indices();
+        self.check_num_cols(&columns, n_cols)?;
+        self.predict_with_config(
+            &DenseRows {
+                values,
+                num_rows: n_rows,
+                row_stride,
+                col_stride,
+                columns,
+            },
+            config,
+        )
+    }
+
+    /// Predicts rows stored in CSR format: the entries of row `i` are
+    /// `indptr[i]..indptr[i + 1]` of `indices` (original feature positions, below `n_cols`) and
+    /// `values`. Features without an entry are treated as missing.
+    pub fn predict_csr(
+        &self,
+        indptr: &[usize],
+        indices: &[usize],
+        values: &[f32],
+        n_cols: usize,
+    ) -> Result<Float32Array, ArrowError> {
+        if indices.len() != values.len() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "CSR indices and values differ in length: {} vs {}",
+                indices.len(),
+                values.len()
+            )));
+        }
+        if indptr.first() != Some(&0)
+            || indptr.last() != Some(&indices.len())
+            || indptr.windows(2).any(|w| w[0] > w[1])
+        {
+            return Err(ArrowError::InvalidArgumentError(
+                "CSR indptr must start at 0, be non-decreasing and end at the number of entries"
+                    .into(),
+            ));
+        }
+        if let Some(&column) = indices.iter().find(|&&column| column >= n_cols) {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "CSR column index {} out of bounds for {} columns",
+                column, n_cols
+            )));
+        }
+
+        let columns = self.original_feature_indices();
+        self.check_num_cols(&columns, n_cols)?;
+        let mut compact = vec![usize::MAX; n_cols];
+        for (feature, &column) in columns.iter().enumerate() {
+            compact[column] = feature;
+        }
+        self.predict_internal(&CsrRows {
+            indptr,
+            indices,
+            values,
+            compact,
+            num_features: columns.len(),
+        })
+    }
+
+    fn check_num_cols(&self, columns: &[usize], n_cols: usize) -> Result<(), ArrowError> {
+        match columns.last() {
+            Some(&last) if last >= n_cols => Err(ArrowError::InvalidArgumentError(format!(
+                "Model uses feature {} but rows have {} columns",
+                last, n_cols
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModelLoader;
+    use crate::tree::{Accumulation, PredictorConfig, VecTreeNodes};
+    use arrow::array::ArrayRef;
+    use std::sync::Arc;
+
+    // Uses original features 1 and 3 of 4; feature 1 goes left when missing.
+    fn sparse_model() -> GradientBoostedDecisionTrees {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, 1, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 2.0, 0.0, 0.0])
+            .children(
+                vec![1, u32::MAX, 3, u32::MAX, u32::MAX],
+                vec![2, u32::MAX, 4, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, -1.0, 0.0, 2.0, 5.0])
+            .default_left(vec![true, false, false, false, false])
+            .build()
+            .unwrap();
+        GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["a".into(), "b".into(), "c".into(), "d".into()]),
+            required_features: [1, 3].into_iter().collect(),
+            base_score: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dense_and_csr_match_arrays() {
+        let model = sparse_model();
+        let nan = f32::NAN;
+        let dense = [
+            9.0, 0.0, 9.0, 9.0, //
+            9.0, 1.0, 9.0, 1.0, //
+            9.0, 1.0, 9.0, 3.0, //
+            9.0, nan, 9.0, 3.0, //
+            9.0, 1.0, 9.0, nan,
+        ];
+        let expected = [-0.5, 2.5, 5.5, -0.5, 5.5];
+
+        let arrays: Vec<ArrayRef> = [1, 3]
+            .iter()
+            .map(|&column| {
+                let values: Vec<f32> = dense.iter().skip(column).step_by(4).copied().collect();
+                Arc::new(Float32Array::from(values)) as ArrayRef
+            })
+            .collect();
+        assert_eq!(model.predict_arrays(&arrays).unwrap().values(), &expected);
+        assert_eq!(
+            model.predict_dense(&dense, 5, 4).unwrap().values(),
+            &expected
+        );
+        let column_major: Vec<f64> = (0..4)
+            .flat_map(|column| dense.iter().skip(column).step_by(4).map(|&v| v as f64))
+            .collect();
+        assert_eq!(
+            model
+                .predict_strided(&column_major, 5, 4, 1, 5)
+                .unwrap()
+                .values(),
+            &expected
+        );
+        for (row, &value) in dense.chunks(4).zip(&expected) {
+            assert_eq!(model.predict_row(row).unwrap(), value);
+        }
+
+        // The same rows with NaN entries and unused columns left out.
+        let indptr = [0, 2, 4, 6, 7, 8];
+        let indices = [1, 3, 1, 3, 3, 1, 3, 1];
+        let values = [0.0, 9.0, 1.0, 1.0, 3.0, 1.0, 3.0, 1.0];
+        let csr = model.predict_csr(&indptr, &indices, &values, 4).unwrap();
+        assert_eq!(csr.values(), &expected);
+    }
+
+    #[test]
+    fn test_predict_row_matches_batches() {
+        let model = GradientBoostedDecisionTrees::json_load(
+            "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json",
+        )
+        .unwrap();
+        let n_cols = model.feature_names.len();
+        let rows: Vec<f32> = (0..50 * n_cols)
+            .map(|i| {
+                if i % 17 == 0 {
+                    f32::NAN
+                } else {
+                    (i % 13) as f32 * 0.4
+                }
+            })
+            .collect();
+
+        for tree_chunk_size in [1, 7, 64] {
+            for accumulation in [Accumulation::Chunked, Accumulation::F64] {
+                let mut model = model.clone();
+                model.set_config(
+                    PredictorConfig::new(8, tree_chunk_size)
+                        .unwrap()
+                        .with_accumulation(accumulation),
+                );
+                let expected = model.predict_dense(&rows, 50, n_cols).unwrap();
+                for (row, &expected) in rows.chunks(n_cols).zip(expected.values()) {
+                    assert_eq!(model.predict_row(row).unwrap(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_dense_and_csr_inputs() {
+        let model = sparse_model();
+        assert!(model.predict_row(&[0.0, 1.0, 2.0]).is_err());
+        assert!(model.predict_dense(&[0.0; 7], 2, 4).is_err());
+        assert!(model.predict_strided(&[0.0; 8], 2, 4, 1, 3).is_err());
+        assert!(model
+            .predict_strided(&[0.0; 8], 2, 4, 4, usize::MAX)
+            .is_err());
+        assert!(model.predict_csr(&[0, 1], &[4], &[1.0], 4).is_err());
+        assert!(model.predict_csr(&[0, 2], &[1], &[1.0], 4).is_err());
+        assert!(model.predict_csr(&[0, 1], &[1, 3], &[1.0], 4).is_err());
+    }
+}
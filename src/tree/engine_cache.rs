@@ -0,0 +1,166 @@
+use super::quickscorer::QuickScorer;
+use super::simd::LockstepPredictor;
+use super::trees::VecTreeNodes;
+use crate::arch::SimdLevel;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+/// Prediction engines and lookup tables built from a model's trees on first use and reused by
+/// later calls.
+///
+/// The engines depend on the trees only, not on the [`PredictorConfig`](super::PredictorConfig).
+/// Cloning gives an empty cache, so a clone whose `trees` are then edited never scores with the
+/// engines of the original.
+#[derive(Default)]
+pub(crate) struct EngineCache {
+    quickscorer: OnceLock<Arc<QuickScorer>>,
+    lockstep: OnceLock<Arc<LockstepPredictor>>,
+    feature_columns: OnceLock<Vec<usize>>,
+}
+
+impl Clone for EngineCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl EngineCache {
+    /// `required_features` in compacted feature order, see
+    /// [`original_feature_indices`](super::GradientBoostedDecisionTrees::original_feature_indices).
+    pub(crate) fn feature_columns(&self, required_features: &HashSet<usize>) -> &[usize] {
+        self.feature_columns.get_or_init(|| {
+            let mut columns: Vec<usize> = required_features.iter().copied().collect();
+            columns.sort_unstable();
+            columns
+        })
+    }
+
+    pub(crate) fn quickscorer(&self, trees: &[VecTreeNodes]) -> Arc<QuickScorer> {
+        Arc::clone(
+            self.quickscorer
+                .get_or_init(|| Arc::new(QuickScorer::new(trees))),
+        )
+    }
+
+    /// The lockstep predictor for `level`. Only one level is cached, as the detected level does
+    /// not change while the process runs; a predictor for any other level is built per call.
+    pub(crate) fn lockstep(
+        &self,
+        trees: &[VecTreeNodes],
+        level: SimdLevel,
+    ) -> Arc<LockstepPredictor> {
+        let cached = self
+            .lockstep
+            .get_or_init(|| Arc::new(LockstepPredictor::new(trees, level)));
+        if cached.level() == level {
+            Arc::clone(cached)
+        } else {
+            Arc::new(LockstepPredictor::new(trees, level))
+        }
+    }
+}
+
+impl fmt::Debug for EngineCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EngineCache")
+            .field("quickscorer", &self.quickscorer.get().is_some())
+            .field(
+                "lockstep",
+                &self.lockstep.get().map(|predictor| predictor.level()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModelLoader;
+    use crate::tree::{
+        GradientBoostedDecisionTrees, NodeLayout, PredictionEngine, PredictorConfig,
+    };
+    use crate::{Condition, Predicate};
+
+    fn load_model() -> GradientBoostedDecisionTrees {
+        GradientBoostedDecisionTrees::json_load(
+            "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quickscorer_built_once() {
+        let model = load_model();
+        let first = model.engine_cache.quickscorer(&model.trees);
+        let second = model.engine_cache.quickscorer(&model.trees);
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_lockstep_keyed_by_level() {
+        let model = load_model();
+        let portable = model
+            .engine_cache
+            .lockstep(&model.trees, SimdLevel::Portable);
+        let again = model
+            .engine_cache
+            .lockstep(&model.trees, SimdLevel::Portable);
+        assert!(Arc::ptr_eq(&portable, &again));
+
+        // Building a predictor runs no vector instructions, so any level works here.
+        let other = model.engine_cache.lockstep(&model.trees, SimdLevel::Avx2);
+        assert_eq!(other.level(), SimdLevel::Avx2);
+        assert!(!Arc::ptr_eq(&portable, &other));
+    }
+
+    #[test]
+    fn test_cache_kept_across_config_changes() {
+        let mut model = load_model();
+        let scorer = model.engine_cache.quickscorer(&model.trees);
+
+        model.set_config(PredictorConfig::default().with_engine(PredictionEngine::QuickScorer));
+        assert!(Arc::ptr_eq(
+            &scorer,
+            &model.engine_cache.quickscorer(&model.trees)
+        ));
+    }
+
+    #[test]
+    fn test_cache_reset_when_trees_change() {
+        let mut model = load_model();
+        model.engine_cache.quickscorer(&model.trees);
+        model
+            .engine_cache
+            .lockstep(&model.trees, SimdLevel::Portable);
+        let built = |model: &GradientBoostedDecisionTrees| {
+            let engines = &model.engine_cache;
+            engines.quickscorer.get().is_some()
+                || engines.lockstep.get().is_some()
+                || engines.feature_columns.get().is_some()
+        };
+        assert!(built(&model));
+
+        let mut predicate = Predicate::new();
+        predicate.add_condition("carat".to_string(), Condition::LessThan(0.5));
+        assert!(!built(&model.prune(&predicate)));
+        assert!(!built(&model.with_layout(NodeLayout {
+            bfs_levels: 2,
+            block_size: 4,
+            use_cover: true,
+        })));
+
+        let scorer = model.engine_cache.quickscorer(&model.trees);
+        let mut copy = model.clone();
+        assert!(!built(&copy));
+        copy.trees.truncate(1);
+        assert!(!Arc::ptr_eq(
+            &scorer,
+            &copy.engine_cache.quickscorer(&copy.trees)
+        ));
+
+        model.trees.truncate(1);
+        model.reset_engine_cache();
+        assert!(!built(&model));
+    }
+}
@@ -14,7 +14,7 @@ pub enum FeatureTreeError {
     LengthMismatch,
     #[error("Feature index {0} out of bounds")]
     InvalidFeatureIndex(usize),
-    #[error("Invalid node structure")]
+    #[error("Invalid node structure: {0}")]
     InvalidStructure(String),
     #[error("Unsupported feature type: {0}. Supported types are: int, float, i (indicator)")]
     UnsupportedType(String),
@@ -0,0 +1,163 @@
+use super::trees::GradientBoostedDecisionTrees;
+use super::vec_tree::Traversable;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Feature importance measures, named as in XGBoost's `Booster.get_score(importance_type=...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportanceType {
+    /// Number of splits on the feature.
+    Weight,
+    /// Average gain of the splits on the feature.
+    Gain,
+    /// Average cover of the splits on the feature.
+    Cover,
+    /// Total gain of the splits on the feature.
+    TotalGain,
+    /// Total cover of the splits on the feature.
+    TotalCover,
+}
+
+impl FromStr for ImportanceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "weight" => Ok(ImportanceType::Weight),
+            "gain" => Ok(ImportanceType::Gain),
+            "cover" => Ok(ImportanceType::Cover),
+            "total_gain" => Ok(ImportanceType::TotalGain),
+            "total_cover" => Ok(ImportanceType::TotalCover),
+            other => Err(format!("Unsupported importance type: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for ImportanceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportanceType::Weight => write!(f, "weight"),
+            ImportanceType::Gain => write!(f, "gain"),
+            ImportanceType::Cover => write!(f, "cover"),
+            ImportanceType::TotalGain => write!(f, "total_gain"),
+            ImportanceType::TotalCover => write!(f, "total_cover"),
+        }
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Computes feature importance keyed by feature name.
+    ///
+    /// Only features with at least one split are present, as in XGBoost. Gain and cover come
+    /// from the `loss_changes` and `sum_hessian` arrays of the loaded model and are zero for
+    /// trees built without them.
+    pub fn feature_importance(&self, kind: ImportanceType) -> HashMap<String, f64> {
+        let feature_names = self.tree_feature_names();
+        let mut totals: HashMap<usize, (usize, f64, f64)> = HashMap::new();
+
+        for tree in &self.trees {
+            for (idx, node) in tree.nodes.iter().enumerate() {
+                if node.is_leaf() {
+                    continue;
+                }
+                let stats = tree.get_stats(idx).copied().unwrap_or_default();
+                let entry = totals
+                    .entry(node.feature_index() as usize)
+                    .or_insert((0, 0.0, 0.0));
+                entry.0 += 1;
+                entry.1 += stats.gain as f64;
+                entry.2 += stats.cover as f64;
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(feature_index, (weight, total_gain, total_cover))| {
+                let score = match kind {
+                    ImportanceType::Weight => weight as f64,
+                    ImportanceType::Gain => total_gain / weight as f64,
+                    ImportanceType::Cover => total_cover / weight as f64,
+                    ImportanceType::TotalGain => total_gain,
+                    ImportanceType::TotalCover => total_cover,
+                };
+                (feature_names[feature_index].clone(), score)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::VecTreeNodes;
+    use std::sync::Arc;
+
+    fn model() -> GradientBoostedDecisionTrees {
+        // Both trees split on compacted features; `b` is unused and must not be reported.
+        let tree = |indices: Vec<i32>, gains: Vec<f32>, covers: Vec<f32>| {
+            VecTreeNodes::builder()
+                .split_indices(indices)
+                .split_conditions(vec![0.5, 1.5, 0.0, 0.0, 0.0])
+                .children(
+                    vec![1, 3, u32::MAX, u32::MAX, u32::MAX],
+                    vec![2, 4, u32::MAX, u32::MAX, u32::MAX],
+                )
+                .base_weights(vec![0.0, 0.0, 1.0, 2.0, 3.0])
+                .default_left(vec![false; 5])
+                .loss_changes(gains)
+                .sum_hessians(covers)
+                .build()
+                .unwrap()
+        };
+        GradientBoostedDecisionTrees {
+            trees: vec![
+                tree(
+                    vec![0, 1, -1, -1, -1],
+                    vec![4.0, 2.0, 0.0, 0.0, 0.0],
+                    vec![10.0, 6.0, 4.0, 3.0, 3.0],
+                ),
+                tree(
+                    vec![0, 0, -1, -1, -1],
+                    vec![3.0, 1.0, 0.0, 0.0, 0.0],
+                    vec![10.0, 5.0, 5.0, 2.0, 3.0],
+                ),
+            ],
+            feature_names: Arc::new(vec!["a".into(), "b".into(), "c".into()]),
+            required_features: [0, 2].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_feature_importance() {
+        let model = model();
+        let expected = [
+            (ImportanceType::Weight, 3.0, 1.0),
+            (ImportanceType::TotalGain, 8.0, 2.0),
+            (ImportanceType::Gain, 8.0 / 3.0, 2.0),
+            (ImportanceType::TotalCover, 25.0, 6.0),
+            (ImportanceType::Cover, 25.0 / 3.0, 6.0),
+        ];
+        for (kind, a, c) in expected {
+            let scores = model.feature_importance(kind);
+            assert_eq!(scores.len(), 2, "{}", kind);
+            assert_eq!(scores["a"], a, "{}", kind);
+            assert_eq!(scores["c"], c, "{}", kind);
+        }
+    }
+
+    #[test]
+    fn test_importance_type_from_str() {
+        for kind in [
+            ImportanceType::Weight,
+            ImportanceType::Gain,
+            ImportanceType::Cover,
+            ImportanceType::TotalGain,
+            ImportanceType::TotalCover,
+        ] {
+            assert_eq!(kind.to_string().parse::<ImportanceType>(), Ok(kind));
+        }
+        assert!("split".parse::<ImportanceType>().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use super::engine_cache::EngineCache;
+use super::trees::{GradientBoostedDecisionTrees, VecTreeNodes};
+use super::vec_tree::Traversable;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Order in which tree nodes are stored in memory.
+///
+/// The top `bfs_levels` levels are stored breadth-first, so the first few steps of every
+/// traversal stay within a handful of cache lines. Deeper nodes are grouped into blocks of
+/// `block_size` nodes, each holding the nodes most likely to be visited after the block's
+/// first node. With `use_cover`, likelihood follows the training cover (`sum_hessian`) so the
+/// more frequently taken child is stored next to its parent; otherwise blocks are filled
+/// breadth-first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeLayout {
+    pub bfs_levels: usize,
+    pub block_size: usize,
+    pub use_cover: bool,
+}
+
+impl Default for NodeLayout {
+    fn default() -> Self {
+        Self {
+            // 16-byte nodes: the first 3 levels span two cache lines, a block fills one.
+            bfs_levels: 3,
+            block_size: 4,
+            use_cover: true,
+        }
+    }
+}
+
+impl NodeLayout {
+    /// Stores every level breadth-first (Eytzinger order for complete trees).
+    pub fn breadth_first() -> Self {
+        Self {
+            bfs_levels: usize::MAX,
+            block_size: 1,
+            use_cover: false,
+        }
+    }
+}
+
+/// Block candidate, ordered by cover and then by discovery order.
+struct Candidate {
+    cover: f32,
+    seq: usize,
+    idx: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cover
+            .total_cmp(&other.cover)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl VecTreeNodes {
+    /// Returns a copy of the tree with its nodes stored in `layout` order. Predictions are
+    /// unchanged; the root stays at index 0.
+    pub fn with_layout(&self, layout: NodeLayout) -> VecTreeNodes {
+        if self.is_empty() {
+            return self.clone();
+        }
+
+        let order = self.layout_order(layout);
+        let mut new_index = vec![usize::MAX; self.len()];
+        for (new_idx, &old_idx) in order.iter().enumerate() {
+            new_index[old_idx] = new_idx;
+        }
+
+        let mut tree = VecTreeNodes::new();
+        for &old_idx in &order {
+            let mut node = self.nodes[old_idx].clone();
+            if !node.is_leaf() {
+                node.set_left(new_index[node.left()]);
+                node.set_right(new_index[node.right()]);
+            }
+            tree.nodes.push(node);
+            if let Some(stats) = self.get_stats(old_idx) {
+                tree.stats.push(*stats);
+            }
+        }
+        tree
+    }
+
+    fn layout_order(&self, layout: NodeLayout) -> Vec<usize> {
+        let cover = |idx: usize| {
+            if layout.use_cover {
+                self.get_stats(idx).map_or(0.0, |stats| stats.cover)
+            } else {
+                0.0
+            }
+        };
+        let children = |idx: usize| {
+            let node = &self.nodes[idx];
+            if node.is_leaf() {
+                None
+            } else {
+                Some([node.left(), node.right()])
+            }
+        };
+
+        let mut order = Vec::with_capacity(self.len());
+        let mut block_roots = VecDeque::new();
+        let mut level = vec![self.get_root_index()];
+        let mut depth = 0;
+        while !level.is_empty() {
+            if depth >= layout.bfs_levels {
+                block_roots.extend(level);
+                break;
+            }
+            order.extend_from_slice(&level);
+            level = level
+                .iter()
+                .filter_map(|&idx| children(idx))
+                .flatten()
+                .collect();
+            depth += 1;
+        }
+
+        let block_size = layout.block_size.max(1);
+        while let Some(root) = block_roots.pop_front() {
+            // Best-first expansion: take the most covered reachable nodes, ties in BFS order.
+            let mut seq = 0;
+            let mut frontier = BinaryHeap::new();
+            frontier.push(Candidate {
+                cover: cover(root),
+                seq,
+                idx: root,
+            });
+            let mut taken = 0;
+            while taken < block_size {
+                let Some(candidate) = frontier.pop() else {
+                    break;
+                };
+                order.push(candidate.idx);
+                taken += 1;
+                for child in children(candidate.idx).into_iter().flatten() {
+                    seq += 1;
+                    frontier.push(Candidate {
+                        cover: cover(child),
+                        seq,
+                        idx: child,
+                    });
+                }
+            }
+            let mut remaining: Vec<Candidate> = frontier.into_vec();
+            remaining.sort_by_key(|candidate| candidate.seq);
+            block_roots.extend(remaining.into_iter().map(|candidate| candidate.idx));
+        }
+        order
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Returns a copy of the model with every tree stored in `layout` order.
+    pub fn with_layout(&self, layout: NodeLayout) -> Self {
+        Self {
+            trees: self
+                .trees
+                .iter()
+                .map(|tree| tree.with_layout(layout))
+                .collect(),
+            engine_cache: EngineCache::default(),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //            0: f0 < 1
+    //           /          \
+    //      1: f1 < 1      2: f1 < 2
+    //      /      \        /      \
+    //   3: -1   4: f0 < 0  5: 1   6: 2
+    //            /    \
+    //         7: 3   8: 4
+    // Stored in builder order 0, 2, 1, 6, 5, 4, 3, 8, 7 to scramble the layout.
+    fn scrambled_tree() -> VecTreeNodes {
+        VecTreeNodes::builder()
+            .split_indices(vec![0, 1, 1, -1, -1, 0, -1, -1, -1])
+            .split_conditions(vec![1.0, 2.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+            .children(
+                vec![2, 4, 6, u32::MAX, u32::MAX, 8, u32::MAX, u32::MAX, u32::MAX],
+                vec![1, 3, 5, u32::MAX, u32::MAX, 7, u32::MAX, u32::MAX, u32::MAX],
+            )
+            .base_weights(vec![0.0, 0.0, 0.0, 2.0, 1.0, 0.0, -1.0, 4.0, 3.0])
+            .default_left(vec![
+                true, false, true, false, false, false, false, false, false,
+            ])
+            .sum_hessians(vec![100.0, 60.0, 40.0, 10.0, 50.0, 30.0, 10.0, 5.0, 25.0])
+            .build()
+            .unwrap()
+    }
+
+    fn weights(tree: &VecTreeNodes) -> Vec<Option<f32>> {
+        tree.nodes
+            .iter()
+            .map(|node| node.is_leaf().then(|| node.weight()))
+            .collect()
+    }
+
+    fn assert_same_predictions(a: &VecTreeNodes, b: &VecTreeNodes) {
+        let values = [f32::NAN, -1.0, 0.0, 0.5, 1.0, 1.5, 2.0, 3.0];
+        for &x in &values {
+            for &y in &values {
+                let (pa, pb) = (a.predict(&[x, y]), b.predict(&[x, y]));
+                assert_eq!(pa.to_bits(), pb.to_bits(), "row [{}, {}]", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_breadth_first_layout() {
+        let tree = scrambled_tree();
+        let bfs = tree.with_layout(NodeLayout::breadth_first());
+        assert!(bfs.validate_connections());
+        assert_eq!(
+            weights(&bfs),
+            vec![
+                None,
+                None,
+                None,
+                Some(-1.0),
+                None,
+                Some(1.0),
+                Some(2.0),
+                Some(3.0),
+                Some(4.0)
+            ]
+        );
+        assert_eq!(bfs.stats.len(), bfs.len());
+        assert_eq!(bfs.get_stats(8).unwrap().cover, 5.0);
+        assert_same_predictions(&tree, &bfs);
+    }
+
+    #[test]
+    fn test_cover_blocked_layout() {
+        let tree = scrambled_tree();
+        let layout = NodeLayout {
+            bfs_levels: 1,
+            block_size: 3,
+            use_cover: true,
+        };
+        let blocked = tree.with_layout(layout);
+        assert!(blocked.validate_connections());
+        // Root, then a block following the heaviest path 1 -> 4 -> 7 below the left child.
+        assert_eq!(blocked.nodes[0].left(), 1);
+        assert_eq!(blocked.nodes[1].right(), 2);
+        assert_eq!(blocked.nodes[2].left(), 3);
+        assert_eq!(blocked.nodes[3].weight(), 3.0);
+        assert_same_predictions(&tree, &blocked);
+    }
+}
@@ -1,8 +1,31 @@
+mod columns;
+mod dense;
+mod engine_cache;
 mod feature_type;
+mod importance;
+mod layout;
+mod multi_output;
+mod quantized;
+mod quickscorer;
 mod serde_helpers;
+mod simd;
+mod snapshot;
+mod stream;
 mod trees;
+mod tuning;
 mod vec_tree;
+pub use dense::DenseValue;
 pub use feature_type::{FeatureTreeError, FeatureType};
-pub use serde_helpers::{arc_vec_serde, vec_tree_serde};
-pub use trees::{FeatureTreeBuilder, GradientBoostedDecisionTrees, PredictorConfig, VecTreeNodes};
-pub use vec_tree::SplitType;
+pub use importance::ImportanceType;
+pub use layout::NodeLayout;
+pub use multi_output::MultiOutputTrees;
+pub use quantized::QuantizedTrees;
+pub use serde_helpers::{arc_vec_serde, vec_tree_serde, vec_tree_stats_serde};
+pub use stream::{OutputColumns, PredictionStream, PREDICTION_COLUMN};
+pub use trees::{
+    Accumulation, FeatureTreeBuilder, GradientBoostedDecisionTrees, Parallelism, PredictionEngine,
+    PredictorConfig, PredictorConfigError, VecTreeNodes,
+};
+pub use tuning::{TuningOptions, TuningResult, TUNING_ATTRIBUTE};
+pub(crate) use vec_tree::Traversable;
+pub use vec_tree::{NodeStats, SplitType};
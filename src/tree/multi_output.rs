@@ -0,0 +1,331 @@
+use super::trees::GradientBoostedDecisionTrees;
+use crate::loader::{ModelError, ModelLoader, XGBoostParser};
+use crate::objective::Objective;
+use crate::tree::FeatureType;
+use arrow::array::{Array, ArrayRef, FixedSizeListArray, Float32Array};
+use arrow::datatypes::{DataType, Field, FieldRef};
+use arrow::error::ArrowError;
+use serde_json::Value;
+use std::fs;
+use std::sync::Arc;
+
+/// A model predicting several values per row: XGBoost multiclass (`multi:softprob`) and
+/// multi-target models.
+///
+/// Each output is scored by a single-output model holding the trees that add to it, so all the
+/// prediction engines apply. Multiclass outputs are the class probabilities, the softmax of the
+/// outputs' margins; multi-target outputs go through the objective one by one.
+#[derive(Debug, Clone)]
+pub struct MultiOutputTrees {
+    outputs: Vec<GradientBoostedDecisionTrees>,
+    softmax: bool,
+    /// Features used by any output, as positions in `feature_names`, in ascending order.
+    feature_indices: Vec<usize>,
+}
+
+impl MultiOutputTrees {
+    pub fn num_outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// The model scoring each output. Multiclass models score margins, before the softmax.
+    pub fn outputs(&self) -> &[GradientBoostedDecisionTrees] {
+        &self.outputs
+    }
+
+    pub fn feature_names(&self) -> &[String] {
+        &self.outputs[0].feature_names
+    }
+
+    pub fn feature_types(&self) -> &[FeatureType] {
+        &self.outputs[0].feature_types
+    }
+
+    /// Positions in `feature_names` of the features used by any output, in the order expected
+    /// by [`predict_arrays`](Self::predict_arrays).
+    pub fn original_feature_indices(&self) -> Vec<usize> {
+        self.feature_indices.clone()
+    }
+
+    /// Type of the predictions: a fixed-size list of one `Float32` per output.
+    pub fn output_type(&self) -> DataType {
+        DataType::FixedSizeList(item_field(), self.outputs.len() as i32)
+    }
+
+    /// Predicts from one array per feature used by any output, in the order of
+    /// [`original_feature_indices`](Self::original_feature_indices). Row `i` of the result holds
+    /// the outputs for row `i` of the features.
+    pub fn predict_arrays(
+        &self,
+        feature_arrays: &[ArrayRef],
+    ) -> Result<FixedSizeListArray, ArrowError> {
+        let num_rows = feature_arrays
+            .first()
+            .map(|array| array.len())
+            .ok_or_else(|| {
+                ArrowError::InvalidArgumentError(
+                    "Cannot infer the number of rows without feature arrays".into(),
+                )
+            })?;
+        self.predict_rows(feature_arrays, num_rows)
+    }
+
+    /// [`predict_arrays`](Self::predict_arrays) for `num_rows` rows, which also works for
+    /// models using no features.
+    pub(crate) fn predict_rows(
+        &self,
+        feature_arrays: &[ArrayRef],
+        num_rows: usize,
+    ) -> Result<FixedSizeListArray, ArrowError> {
+        if feature_arrays.len() != self.feature_indices.len() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Expected {} feature arrays, got {}",
+                self.feature_indices.len(),
+                feature_arrays.len()
+            )));
+        }
+        let num_outputs = self.outputs.len();
+        let mut values = vec![0.0; num_rows * num_outputs];
+        for (output, model) in self.outputs.iter().enumerate() {
+            let arrays: Vec<ArrayRef> = model
+                .original_feature_indices()
+                .into_iter()
+                .map(|index| {
+                    let position = self.feature_indices.binary_search(&index).unwrap();
+                    Arc::clone(&feature_arrays[position])
+                })
+                .collect();
+            let features = model.feature_columns(&arrays, Some(num_rows))?;
+            let predictions = model.predict_internal(&features)?;
+            for (row, &prediction) in predictions.values().iter().enumerate() {
+                values[row * num_outputs + output] = prediction;
+            }
+        }
+        if self.softmax {
+            values.chunks_mut(num_outputs).for_each(softmax);
+        }
+        FixedSizeListArray::try_new(
+            item_field(),
+            num_outputs as i32,
+            Arc::new(Float32Array::from(values)),
+            None,
+        )
+    }
+}
+
+fn item_field() -> FieldRef {
+    Arc::new(Field::new("item", DataType::Float32, false))
+}
+
+fn softmax(margins: &mut [f32]) {
+    let max = margins.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.0;
+    for margin in margins.iter_mut() {
+        *margin = (*margin - max).exp();
+        sum += *margin;
+    }
+    for probability in margins.iter_mut() {
+        *probability /= sum;
+    }
+}
+
+impl ModelLoader for MultiOutputTrees {
+    fn json_load(path: &str) -> Result<Self, ModelError> {
+        let data = fs::read_to_string(path).map_err(|e| ModelError::IoError(e.to_string()))?;
+        let result: Value =
+            serde_json::from_str(&data).map_err(|e| ModelError::IoError(e.to_string()))?;
+        Self::json_loads(&result)
+    }
+
+    fn json_loads(json: &Value) -> Result<Self, ModelError> {
+        let objective_name = XGBoostParser::parse_objective_name(json)?;
+        let (objective, softmax) = match objective_name {
+            "multi:softprob" => (Objective::SquaredError, true),
+            name => (
+                XGBoostParser::objective_from_name(name).ok_or_else(|| {
+                    ModelError::InvalidFieldType(format!("Unsupported objective: {}", name))
+                })?,
+                false,
+            ),
+        };
+        // Trees of `multi_strategy="multi_output_tree"` models hold one weight per output.
+        let trees = XGBoostParser::parse_trees(json)?;
+        for tree in trees {
+            let leaf_size = tree["tree_param"]["size_leaf_vector"].as_str();
+            if leaf_size.and_then(|s| s.parse::<usize>().ok()) > Some(1) {
+                return Err(ModelError::InvalidFieldType(
+                    "Unsupported trees with vector leaves".into(),
+                ));
+            }
+        }
+
+        let num_outputs = XGBoostParser::parse_num_outputs(json)?;
+        let tree_info = XGBoostParser::parse_tree_info(json)?;
+        if tree_info.len() != trees.len() {
+            return Err(ModelError::InvalidFieldType(format!(
+                "tree_info: {} entries for {} trees",
+                tree_info.len(),
+                trees.len()
+            )));
+        }
+        if let Some(&output) = tree_info.iter().find(|&&output| output >= num_outputs) {
+            return Err(ModelError::InvalidFieldType(format!(
+                "tree_info: output {} of a model with {} outputs",
+                output, num_outputs
+            )));
+        }
+        let outputs = (0..num_outputs)
+            .map(|output| {
+                GradientBoostedDecisionTrees::from_xgboost_trees(json, objective.clone(), |tree| {
+                    tree_info.get(tree) == Some(&output)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut feature_indices: Vec<usize> = outputs
+            .iter()
+            .flat_map(|model| model.required_features.iter().copied())
+            .collect();
+        feature_indices.sort_unstable();
+        feature_indices.dedup();
+        Ok(Self {
+            outputs,
+            softmax,
+            feature_indices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::AsArray;
+    use arrow::datatypes::Float32Type;
+
+    fn diamonds_json() -> Value {
+        let path = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// The diamonds model with its trees spread round-robin over `num_outputs` outputs.
+    fn multi_output_json(objective: &str, field: &str, num_outputs: usize) -> Value {
+        let mut json = diamonds_json();
+        json["learner"]["objective"]["name"] = objective.into();
+        json["learner"]["learner_model_param"][field] = num_outputs.to_string().into();
+        let model = &mut json["learner"]["gradient_booster"]["model"];
+        let num_trees = model["trees"].as_array().unwrap().len();
+        model["tree_info"] = (0..num_trees).map(|tree| tree % num_outputs).collect();
+        json
+    }
+
+    /// The single-output model of the trees adding to `output`.
+    fn output_model(
+        json: &Value,
+        output: usize,
+        num_outputs: usize,
+    ) -> GradientBoostedDecisionTrees {
+        let mut json = json.clone();
+        json["learner"]["objective"]["name"] = "reg:squarederror".into();
+        json["learner"]["learner_model_param"]["num_class"] = "0".into();
+        json["learner"]["learner_model_param"]["num_target"] = "1".into();
+        let trees = &mut json["learner"]["gradient_booster"]["model"]["trees"];
+        let kept: Vec<Value> = trees
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(tree, _)| tree % num_outputs == output)
+            .map(|(_, tree)| tree.clone())
+            .collect();
+        *trees = kept.into();
+        GradientBoostedDecisionTrees::json_loads(&json).unwrap()
+    }
+
+    fn features(model: &MultiOutputTrees) -> Vec<ArrayRef> {
+        model
+            .original_feature_indices()
+            .into_iter()
+            .enumerate()
+            .map(|(column, _)| {
+                let values: Vec<Option<f32>> = (0..50)
+                    .map(|row| (row % 7 != column % 5).then_some((row * (column + 1)) as f32 / 9.0))
+                    .collect();
+                Arc::new(Float32Array::from(values)) as ArrayRef
+            })
+            .collect()
+    }
+
+    fn predict_output(
+        multi: &MultiOutputTrees,
+        model: &GradientBoostedDecisionTrees,
+        arrays: &[ArrayRef],
+    ) -> Vec<f32> {
+        let arrays: Vec<ArrayRef> = multi
+            .original_feature_indices()
+            .into_iter()
+            .zip(arrays)
+            .filter(|(index, _)| model.required_features.contains(index))
+            .map(|(_, array)| Arc::clone(array))
+            .collect();
+        model.predict_arrays(&arrays).unwrap().values().to_vec()
+    }
+
+    #[test]
+    fn test_multi_target_outputs_match_single_output_models() {
+        let json = multi_output_json("reg:squarederror", "num_target", 2);
+        assert!(GradientBoostedDecisionTrees::json_loads(&json).is_err());
+        let model = MultiOutputTrees::json_loads(&json).unwrap();
+        assert_eq!(model.num_outputs(), 2);
+        assert_eq!(
+            model.output_type(),
+            DataType::FixedSizeList(item_field(), 2)
+        );
+
+        let arrays = features(&model);
+        let predictions = model.predict_arrays(&arrays).unwrap();
+        assert_eq!(predictions.len(), 50);
+        let values = predictions.values().as_primitive::<Float32Type>().values();
+        for output in 0..2 {
+            let expected = predict_output(&model, &output_model(&json, output, 2), &arrays);
+            let actual: Vec<f32> = values.iter().skip(output).step_by(2).copied().collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_multiclass_outputs_are_probabilities() {
+        let json = multi_output_json("multi:softprob", "num_class", 3);
+        let model = MultiOutputTrees::json_loads(&json).unwrap();
+        let arrays = features(&model);
+        let predictions = model.predict_arrays(&arrays).unwrap();
+        let margins: Vec<Vec<f32>> = (0..3)
+            .map(|output| predict_output(&model, &output_model(&json, output, 3), &arrays))
+            .collect();
+        for row in 0..50 {
+            let row_values = predictions.value(row);
+            let probabilities: &[f32] = row_values.as_primitive::<Float32Type>().values();
+            let mut expected: Vec<f32> = margins.iter().map(|margins| margins[row]).collect();
+            softmax(&mut expected);
+            assert_eq!(probabilities, expected);
+            assert!((probabilities.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_models() {
+        let mut json = multi_output_json("multi:softprob", "num_class", 3);
+        json["learner"]["gradient_booster"]["model"]["tree_info"][0] = 3.into();
+        assert!(MultiOutputTrees::json_loads(&json).is_err());
+
+        let mut json = multi_output_json("multi:softmax", "num_class", 3);
+        assert!(MultiOutputTrees::json_loads(&json).is_err());
+
+        json = multi_output_json("reg:squarederror", "num_target", 2);
+        json["learner"]["gradient_booster"]["model"]["trees"][0]["tree_param"]
+            ["size_leaf_vector"] = "2".into();
+        assert!(MultiOutputTrees::json_loads(&json).is_err());
+
+        let model = MultiOutputTrees::json_loads(&diamonds_json()).unwrap();
+        assert!(model.predict_arrays(&[]).is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use super::columns::{FeatureColumn, FeatureColumns, FeatureSource};
+use super::feature_type::FeatureTreeError;
+use super::trees::{GradientBoostedDecisionTrees, PredictorConfig, VecTreeNodes};
+use super::vec_tree::Traversable;
+use crate::objective::Objective;
+use arrow::array::{ArrayRef, Float32Array, Float32Builder};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use rayon::prelude::*;
+
+/// Tree node comparing bin indices instead of raw feature values. Half the size of `TreeNode`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+struct QuantizedNode {
+    /// Feature index with `DEFAULT_LEFT` as the high bit, or `LEAF`.
+    feature: u16,
+    /// Rows whose bin is at least `split_bin` go right.
+    split_bin: u16,
+    /// Left child, or the index into `leaf_values` for leaves.
+    left: u16,
+    right: u16,
+}
+
+impl QuantizedNode {
+    const LEAF: u16 = u16::MAX;
+    const DEFAULT_LEFT: u16 = 0x8000;
+    const MAX_FEATURES: usize = 0x7FFF;
+
+    #[inline(always)]
+    fn is_leaf(&self) -> bool {
+        self.feature == Self::LEAF
+    }
+
+    #[inline(always)]
+    fn feature_index(&self) -> usize {
+        (self.feature & !Self::DEFAULT_LEFT) as usize
+    }
+
+    #[inline(always)]
+    fn default_left(&self) -> bool {
+        self.feature & Self::DEFAULT_LEFT != 0
+    }
+}
+
+struct QuantizedTree {
+    nodes: Vec<QuantizedNode>,
+    leaf_values: Vec<f32>,
+}
+
+impl QuantizedTree {
+    #[inline(always)]
+    fn predict<B: Bin>(&self, bins: &[B]) -> f32 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        let mut node = &self.nodes[0];
+        while !node.is_leaf() {
+            let bin = bins[node.feature_index()];
+            let go_right = if bin == B::MISSING {
+                !node.default_left()
+            } else {
+                bin.index() >= node.split_bin
+            };
+            node = &self.nodes[if go_right { node.right } else { node.left } as usize];
+        }
+        self.leaf_values[node.left as usize]
+    }
+}
+
+/// Storage type of a bin index. `MISSING` marks null and NaN values.
+trait Bin: Copy + PartialEq + Send + Sync {
+    const MISSING: Self;
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> u16;
+}
+
+impl Bin for u8 {
+    const MISSING: Self = u8::MAX;
+
+    fn from_index(index: usize) -> Self {
+        index as u8
+    }
+
+    fn index(self) -> u16 {
+        self as u16
+    }
+}
+
+impl Bin for u16 {
+    const MISSING: Self = u16::MAX;
+
+    fn from_index(index: usize) -> Self {
+        index as u16
+    }
+
+    fn index(self) -> u16 {
+        self
+    }
+}
+
+/// A model whose splits compare pre-binned features.
+///
+/// Every feature is binned against the sorted distinct thresholds the trees split it on: the bin
+/// of `x` is the number of thresholds `t` with `t <= x`, so `x >= t_k` exactly when
+/// `bin >= k + 1`. Routing, including the default direction of missing values, is therefore
+/// identical to the float comparisons, and predictions are bit-identical to the original model.
+/// Bins are stored as `u8` when every feature has fewer than 255 thresholds, and `u16` otherwise.
+pub struct QuantizedTrees {
+    trees: Vec<QuantizedTree>,
+    /// Sorted distinct thresholds per compacted feature.
+    thresholds: Vec<Vec<f32>>,
+    model: GradientBoostedDecisionTrees,
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Builds the quantized form of this model, see [`QuantizedTrees`].
+    pub fn quantize(&self) -> Result<QuantizedTrees, FeatureTreeError> {
+        QuantizedTrees::new(self)
+    }
+}
+
+impl QuantizedTrees {
+    fn new(model: &GradientBoostedDecisionTrees) -> Result<Self, FeatureTreeError> {
+        let num_features = model.required_features.len();
+        if num_features > QuantizedNode::MAX_FEATURES {
+            return Err(FeatureTreeError::InvalidStructure(format!(
+                "Cannot quantize a model using {} features",
+                num_features
+            )));
+        }
+
+        let mut thresholds = vec![Vec::new(); num_features];
+        for tree in &model.trees {
+            for node in tree.nodes.iter().filter(|node| !node.is_leaf()) {
+                let feature = node.feature_index() as usize;
+                thresholds
+                    .get_mut(feature)
+                    .ok_or(FeatureTreeError::InvalidFeatureIndex(feature))?
+                    .push(node.split_value());
+            }
+        }
+        for feature_thresholds in &mut thresholds {
+            feature_thresholds.sort_by(f32::total_cmp);
+            // -0.0 and 0.0 compare equal and must share a bin.
+            feature_thresholds.dedup_by(|a, b| a == b);
+            if feature_thresholds.len() >= u16::MAX as usize {
+                return Err(FeatureTreeError::InvalidStructure(format!(
+                    "Cannot quantize a feature with {} distinct thresholds",
+                    feature_thresholds.len()
+                )));
+            }
+        }
+
+        let trees = model
+            .trees
+            .iter()
+            .map(|tree| quantize_tree(tree, &thresholds))
+            .collect();
+
+        Ok(Self {
+            trees,
+            thresholds,
+            model: model.clone(),
+        })
+    }
+
+    pub fn config(&self) -> &PredictorConfig {
+        &self.model.config
+    }
+
+    pub fn set_config(&mut self, config: PredictorConfig) {
+        self.model.config = config;
+    }
+
+    pub fn predict_batches(&self, batches: &[RecordBatch]) -> Result<Float32Array, ArrowError> {
+        let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        let mut builder = Float32Builder::with_capacity(total_rows);
+        for batch in batches {
+            let columns = self.model.required_columns(batch);
+            let features = self
+                .model
+                .feature_columns(&columns, Some(batch.num_rows()))?;
+            builder.append_slice(&self.predict_features(&features));
+        }
+        Ok(builder.finish())
+    }
+
+    pub fn predict_arrays(&self, feature_arrays: &[ArrayRef]) -> Result<Float32Array, ArrowError> {
+        let features = self.model.feature_columns(feature_arrays, None)?;
+        Ok(Float32Array::from(self.predict_features(&features)))
+    }
+
+    fn predict_features(&self, features: &FeatureColumns) -> Vec<f32> {
+        let num_rows = features.num_rows();
+        if self.thresholds.iter().all(|t| t.len() < u8::MAX as usize) {
+            self.predict_binned(&self.bin_columns::<u8>(features), num_rows)
+        } else {
+            self.predict_binned(&self.bin_columns::<u16>(features), num_rows)
+        }
+    }
+
+    fn bin_columns<B: Bin>(&self, features: &FeatureColumns) -> Vec<Vec<B>> {
+        let bin_column = |(column, thresholds): (&FeatureColumn, &Vec<f32>)| {
+            (0..features.num_rows())
+                .map(|row| {
+                    let x = column.value(row);
+                    if x.is_nan() {
+                        B::MISSING
+                    } else {
+                        B::from_index(thresholds.partition_point(|&t| t <= x))
+                    }
+                })
+                .collect()
+        };
+        let config = &self.model.config;
+        if config.is_sequential(features.num_rows()) {
+            return features
+                .columns()
+                .iter()
+                .zip(&self.thresholds)
+                .map(bin_column)
+                .collect();
+        }
+        config.install(|| {
+            features
+                .columns()
+                .par_iter()
+                .zip(&self.thresholds)
+                .map(bin_column)
+                .collect()
+        })
+    }
+
+    /// Mirrors the chunking of the float traversal so scores are summed in the same order.
+    fn predict_binned<B: Bin>(&self, columns: &[Vec<B>], num_rows: usize) -> Vec<f32> {
+        let config = &self.model.config;
+        let num_features = columns.len();
+        let base_score = self.model.base_score;
+        let objective: &Objective = &self.model.objective;
+
+        config.map_row_chunks(num_rows, |row_indices, chunk_results| {
+            let mut row_bins = vec![B::MISSING; num_features];
+            let mut chunk_scores = vec![base_score as f64; row_indices.len()];
+
+            for tree_chunk in self.trees.chunks(config.tree_chunk_size()) {
+                for (chunk_idx, &row_idx) in row_indices.iter().enumerate() {
+                    for (bin, column) in row_bins.iter_mut().zip(columns) {
+                        *bin = column[row_idx];
+                    }
+                    chunk_scores[chunk_idx] = config.accumulation().add_chunk(
+                        chunk_scores[chunk_idx],
+                        tree_chunk.iter().map(|tree| tree.predict(&row_bins)),
+                    );
+                }
+            }
+
+            chunk_results.extend(
+                chunk_scores
+                    .into_iter()
+                    .map(|score| objective.compute_score(score as f32)),
+            );
+        })
+    }
+}
+
+fn quantize_tree(tree: &VecTreeNodes, thresholds: &[Vec<f32>]) -> QuantizedTree {
+    let mut leaf_values = Vec::new();
+    let nodes = tree
+        .nodes
+        .iter()
+        .map(|node| {
+            if node.is_leaf() {
+                leaf_values.push(node.weight());
+                return QuantizedNode {
+                    feature: QuantizedNode::LEAF,
+                    split_bin: 0,
+                    left: (leaf_values.len() - 1) as u16,
+                    right: 0,
+                };
+            }
+            let feature = node.feature_index() as usize;
+            let threshold = node.split_value();
+            let split_bin = thresholds[feature].partition_point(|&t| t < threshold) + 1;
+            let mut feature_bits = feature as u16;
+            if node.default_left() {
+                feature_bits |= QuantizedNode::DEFAULT_LEFT;
+            }
+            QuantizedNode {
+                feature: feature_bits,
+                split_bin: split_bin as u16,
+                left: node.left() as u16,
+                right: node.right() as u16,
+            }
+        })
+        .collect();
+    QuantizedTree { nodes, leaf_values }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_quantized_node_size() {
+        assert_eq!(std::mem::size_of::<QuantizedNode>(), 8);
+    }
+
+    fn random_model(
+        rng: &mut StdRng,
+        num_trees: usize,
+        num_thresholds: usize,
+    ) -> GradientBoostedDecisionTrees {
+        let candidates: Vec<f32> = (0..num_thresholds)
+            .map(|i| i as f32 - (num_thresholds / 2) as f32)
+            .chain([-0.0, 0.0, f32::INFINITY, f32::NEG_INFINITY])
+            .collect();
+        let trees = (0..num_trees)
+            .map(|_| {
+                // Complete tree of depth 4.
+                let (num_nodes, num_internal) = (31usize, 15usize);
+                let is_split = |i: usize| i < num_internal;
+                VecTreeNodes::builder()
+                    .split_indices(
+                        (0..num_nodes)
+                            .map(|i| if is_split(i) { rng.gen_range(0..3) } else { -1 })
+                            .collect(),
+                    )
+                    .split_conditions(
+                        (0..num_nodes)
+                            .map(|_| candidates[rng.gen_range(0..candidates.len())])
+                            .collect(),
+                    )
+                    .children(
+                        (0..num_nodes)
+                            .map(|i| {
+                                if is_split(i) {
+                                    2 * i as u32 + 1
+                                } else {
+                                    u32::MAX
+                                }
+                            })
+                            .collect(),
+                        (0..num_nodes)
+                            .map(|i| {
+                                if is_split(i) {
+                                    2 * i as u32 + 2
+                                } else {
+                                    u32::MAX
+                                }
+                            })
+                            .collect(),
+                    )
+                    .base_weights((0..num_nodes).map(|_| rng.gen_range(-1.0..1.0)).collect())
+                    .default_left((0..num_nodes).map(|_| rng.gen_bool(0.5)).collect())
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        GradientBoostedDecisionTrees {
+            trees,
+            feature_names: Arc::new(vec!["a".into(), "b".into(), "c".into()]),
+            required_features: [0, 1, 2].into_iter().collect(),
+            base_score: 0.25,
+            objective: Objective::Logistic,
+            objective_name: None,
+            ..Default::default()
+        }
+    }
+
+    fn assert_quantized_matches(model: &GradientBoostedDecisionTrees, rng: &mut StdRng) {
+        let columns: Vec<ArrayRef> = (0..3)
+            .map(|_| {
+                let values: Vec<Option<f32>> = (0..500)
+                    .map(|_| match rng.gen_range(0..10) {
+                        0 => None,
+                        1 => Some(f32::NAN),
+                        2 => Some(-0.0),
+                        3 => Some(rng.gen_range(-400..400) as f32),
+                        _ => Some(rng.gen_range(-400.0..400.0)),
+                    })
+                    .collect();
+                Arc::new(Float32Array::from(values)) as ArrayRef
+            })
+            .collect();
+
+        let quantized = model.quantize().unwrap();
+        assert_eq!(
+            quantized.predict_arrays(&columns).unwrap(),
+            model.predict_arrays(&columns).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_quantized_matches_float_routing() {
+        let mut rng = StdRng::seed_from_u64(3);
+        // u8 bins.
+        let model = random_model(&mut rng, 20, 100);
+        assert!(model
+            .quantize()
+            .unwrap()
+            .thresholds
+            .iter()
+            .all(|t| t.len() < 255));
+        assert_quantized_matches(&model, &mut rng);
+        // u16 bins.
+        let model = random_model(&mut rng, 200, 600);
+        assert!(model
+            .quantize()
+            .unwrap()
+            .thresholds
+            .iter()
+            .any(|t| t.len() >= 255));
+        assert_quantized_matches(&model, &mut rng);
+    }
+}
@@ -0,0 +1,264 @@
+use super::trees::{PredictorConfig, VecTreeNodes};
+use super::vec_tree::Traversable;
+
+/// QuickScorer (Lucchese et al., SIGIR 2015) evaluation of a tree ensemble.
+///
+/// Leaves of every tree are numbered left to right and tracked in a per-tree bitvector. Split
+/// nodes are grouped by feature and sorted by threshold, so a row is scored by scanning each
+/// feature's thresholds up to the row's value and clearing the left-subtree leaves of every
+/// node that sends the row right. The exit leaf of a tree is then its lowest set bit.
+pub(crate) struct QuickScorer {
+    num_trees: usize,
+    words_per_tree: usize,
+    /// Nodes splitting on feature `f` are `feature_offsets[f]..feature_offsets[f + 1]`.
+    feature_offsets: Vec<usize>,
+    thresholds: Vec<f32>,
+    default_left: Vec<bool>,
+    tree_ids: Vec<usize>,
+    /// `words_per_tree` words per node, with the leaves of its left subtree cleared.
+    masks: Vec<u64>,
+    leaf_offsets: Vec<usize>,
+    leaf_values: Vec<f32>,
+}
+
+struct SplitNode {
+    feature_index: usize,
+    threshold: f32,
+    default_left: bool,
+    tree_id: usize,
+    left_leaves: std::ops::Range<usize>,
+}
+
+impl QuickScorer {
+    pub(crate) fn new(trees: &[VecTreeNodes]) -> Self {
+        fn collect(
+            tree: &VecTreeNodes,
+            idx: usize,
+            tree_id: usize,
+            splits: &mut Vec<SplitNode>,
+            leaves: &mut Vec<f32>,
+        ) {
+            let node = &tree.nodes[idx];
+            if node.is_leaf() {
+                leaves.push(node.weight());
+                return;
+            }
+            let first_leaf = leaves.len();
+            collect(tree, node.left(), tree_id, splits, leaves);
+            splits.push(SplitNode {
+                feature_index: node.feature_index() as usize,
+                threshold: node.split_value(),
+                default_left: node.default_left(),
+                tree_id,
+                left_leaves: first_leaf..leaves.len(),
+            });
+            collect(tree, node.right(), tree_id, splits, leaves);
+        }
+
+        let mut splits = Vec::new();
+        let mut leaf_offsets = Vec::with_capacity(trees.len() + 1);
+        let mut leaf_values = Vec::new();
+        let mut max_leaves = 1;
+        for (tree_id, tree) in trees.iter().enumerate() {
+            let offset = leaf_values.len();
+            leaf_offsets.push(offset);
+            let mut leaves = Vec::new();
+            if tree.is_empty() {
+                leaves.push(0.0);
+            } else {
+                collect(
+                    tree,
+                    tree.get_root_index(),
+                    tree_id,
+                    &mut splits,
+                    &mut leaves,
+                );
+            }
+            max_leaves = max_leaves.max(leaves.len());
+            leaf_values.extend(leaves);
+        }
+        leaf_offsets.push(leaf_values.len());
+
+        splits.sort_by(|a, b| {
+            a.feature_index
+                .cmp(&b.feature_index)
+                .then(a.threshold.total_cmp(&b.threshold))
+        });
+
+        let num_features = splits.last().map_or(0, |s| s.feature_index + 1);
+        let mut feature_offsets = vec![0; num_features + 1];
+        for split in &splits {
+            feature_offsets[split.feature_index + 1] += 1;
+        }
+        for f in 0..num_features {
+            feature_offsets[f + 1] += feature_offsets[f];
+        }
+
+        let words_per_tree = max_leaves.div_ceil(64);
+        let mut masks = vec![u64::MAX; splits.len() * words_per_tree];
+        for (node, split) in splits.iter().enumerate() {
+            let mask = &mut masks[node * words_per_tree..(node + 1) * words_per_tree];
+            for leaf in split.left_leaves.clone() {
+                mask[leaf / 64] &= !(1u64 << (leaf % 64));
+            }
+        }
+
+        Self {
+            num_trees: trees.len(),
+            words_per_tree,
+            feature_offsets,
+            thresholds: splits.iter().map(|s| s.threshold).collect(),
+            default_left: splits.iter().map(|s| s.default_left).collect(),
+            tree_ids: splits.iter().map(|s| s.tree_id).collect(),
+            masks,
+            leaf_offsets,
+            leaf_values,
+        }
+    }
+
+    /// Number of `u64` words of scratch space `predict_row` needs.
+    pub(crate) fn bitvector_len(&self) -> usize {
+        self.num_trees * self.words_per_tree
+    }
+
+    /// Scores one row, summing trees in chunks of `config.tree_chunk_size()` in the same order
+    /// as the traversal predictor so both produce bit-identical results.
+    pub(crate) fn predict_row(
+        &self,
+        row: &[f32],
+        base_score: f32,
+        config: &PredictorConfig,
+        bitvectors: &mut [u64],
+    ) -> f32 {
+        bitvectors.fill(u64::MAX);
+
+        for (feature, &value) in row.iter().enumerate().take(self.feature_offsets.len() - 1) {
+            let nodes = self.feature_offsets[feature]..self.feature_offsets[feature + 1];
+            if value.is_nan() {
+                for node in nodes.filter(|&node| !self.default_left[node]) {
+                    self.clear_left_leaves(node, bitvectors);
+                }
+            } else {
+                for node in nodes {
+                    if self.thresholds[node] > value {
+                        break;
+                    }
+                    self.clear_left_leaves(node, bitvectors);
+                }
+            }
+        }
+
+        let tree_chunk_size = config.tree_chunk_size();
+        let mut score = base_score as f64;
+        for chunk_start in (0..self.num_trees).step_by(tree_chunk_size) {
+            let chunk_end = (chunk_start + tree_chunk_size).min(self.num_trees);
+            score = config.accumulation().add_chunk(
+                score,
+                (chunk_start..chunk_end).map(|tree| self.exit_leaf_value(tree, bitvectors)),
+            );
+        }
+        score as f32
+    }
+
+    #[inline(always)]
+    fn clear_left_leaves(&self, node: usize, bitvectors: &mut [u64]) {
+        let w = self.words_per_tree;
+        let tree = self.tree_ids[node];
+        let mask = &self.masks[node * w..(node + 1) * w];
+        for (word, &m) in bitvectors[tree * w..(tree + 1) * w].iter_mut().zip(mask) {
+            *word &= m;
+        }
+    }
+
+    #[inline(always)]
+    fn exit_leaf_value(&self, tree: usize, bitvectors: &[u64]) -> f32 {
+        let w = self.words_per_tree;
+        let words = &bitvectors[tree * w..(tree + 1) * w];
+        // The true exit leaf is never cleared, so some word is always non-zero.
+        let (word_idx, word) = words
+            .iter()
+            .enumerate()
+            .find(|(_, &word)| word != 0)
+            .expect("exit leaf is never cleared");
+        self.leaf_values[self.leaf_offsets[tree] + word_idx * 64 + word.trailing_zeros() as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Builds a complete tree of the given depth with random splits over `num_features`.
+    fn random_tree(rng: &mut StdRng, depth: u32, num_features: i32) -> VecTreeNodes {
+        let num_nodes = (1usize << (depth + 1)) - 1;
+        let num_internal = (1usize << depth) - 1;
+        let mut split_indices = Vec::with_capacity(num_nodes);
+        let mut split_conditions = Vec::with_capacity(num_nodes);
+        let mut left = Vec::with_capacity(num_nodes);
+        let mut right = Vec::with_capacity(num_nodes);
+        let mut weights = Vec::with_capacity(num_nodes);
+        let mut default_left = Vec::with_capacity(num_nodes);
+        for i in 0..num_nodes {
+            if i < num_internal {
+                split_indices.push(rng.gen_range(0..num_features));
+                split_conditions.push(rng.gen_range(0..8) as f32 * 0.5);
+                left.push((2 * i + 1) as u32);
+                right.push((2 * i + 2) as u32);
+                weights.push(0.0);
+            } else {
+                split_indices.push(-1);
+                split_conditions.push(0.0);
+                left.push(u32::MAX);
+                right.push(u32::MAX);
+                weights.push(rng.gen_range(-1.0..1.0));
+            }
+            default_left.push(rng.gen_bool(0.5));
+        }
+        VecTreeNodes::builder()
+            .split_indices(split_indices)
+            .split_conditions(split_conditions)
+            .children(left, right)
+            .base_weights(weights)
+            .default_left(default_left)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_quickscorer_matches_traversal() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // Depth 7 trees have 128 leaves, exercising multi-word bitvectors.
+        let trees: Vec<VecTreeNodes> = (0..20u32)
+            .map(|i| random_tree(&mut rng, 2 + i % 6, 4))
+            .chain(std::iter::once(VecTreeNodes::new()))
+            .collect();
+        let scorer = QuickScorer::new(&trees);
+        let mut bitvectors = vec![0; scorer.bitvector_len()];
+
+        for _ in 0..1000 {
+            let row: Vec<f32> = (0..4)
+                .map(|_| {
+                    if rng.gen_bool(0.2) {
+                        f32::NAN
+                    } else {
+                        rng.gen_range(0..9) as f32 * 0.5 - 0.25 * rng.gen_range(0..2) as f32
+                    }
+                })
+                .collect();
+            for tree_chunk_size in [1, 3, 64] {
+                let mut expected = 0.5;
+                for chunk in trees.chunks(tree_chunk_size) {
+                    let chunk_score: f32 = chunk.iter().map(|tree| tree.predict(&row)).sum();
+                    expected += chunk_score;
+                }
+                let config = PredictorConfig::default()
+                    .with_tree_chunk_size(tree_chunk_size)
+                    .unwrap();
+                let actual = scorer.predict_row(&row, 0.5, &config, &mut bitvectors);
+                assert_eq!(actual.to_bits(), expected.to_bits(), "row {:?}", row);
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use crate::tree::vec_tree::{TreeNode, VecTree};
+use crate::tree::vec_tree::{NodeStats, TreeNode, VecTree};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::sync::Arc;
 
 type VecTreeWithTreeNode = VecTree<TreeNode>;
 
+/// Serializes the nodes of a tree; split statistics are left out and empty after loading. Use
+/// [`vec_tree_stats_serde`] to keep them.
 pub mod vec_tree_serde {
     use super::*;
 
@@ -19,7 +21,30 @@ pub mod vec_tree_serde {
         D: Deserializer<'de>,
     {
         let nodes = Vec::deserialize(deserializer)?;
-        Ok(VecTreeWithTreeNode { nodes })
+        Ok(VecTreeWithTreeNode {
+            nodes,
+            stats: Vec::new(),
+        })
+    }
+}
+
+/// Serializes the nodes of a tree together with their split statistics.
+pub mod vec_tree_stats_serde {
+    use super::*;
+
+    pub fn serialize<S>(tree: &VecTreeWithTreeNode, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct("VecTreeWithTreeNodeStats", &(&tree.nodes, &tree.stats))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<VecTreeWithTreeNode, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (nodes, stats) = <(Vec<TreeNode>, Vec<NodeStats>)>::deserialize(deserializer)?;
+        Ok(VecTreeWithTreeNode { nodes, stats })
     }
 }
 
@@ -43,3 +68,38 @@ pub mod arc_vec_serde {
         Ok(Arc::new(vec))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModelLoader;
+    use crate::tree::GradientBoostedDecisionTrees;
+
+    #[derive(Serialize, Deserialize)]
+    struct Nodes(#[serde(with = "vec_tree_serde")] VecTreeWithTreeNode);
+
+    #[derive(Serialize, Deserialize)]
+    struct NodesWithStats(#[serde(with = "vec_tree_stats_serde")] VecTreeWithTreeNode);
+
+    #[test]
+    fn test_vec_tree_formats() {
+        let model = GradientBoostedDecisionTrees::json_load(
+            "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json",
+        )
+        .unwrap();
+        let tree = model.trees[0].clone();
+        assert!(!tree.stats.is_empty());
+
+        // `vec_tree_serde` writes only the nodes, so data written before statistics existed
+        // still loads.
+        let json = serde_json::to_value(Nodes(tree.clone())).unwrap();
+        assert_eq!(json, serde_json::to_value(&tree.nodes).unwrap());
+        let Nodes(loaded) = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.nodes, tree.nodes);
+        assert!(loaded.stats.is_empty());
+
+        let json = serde_json::to_value(NodesWithStats(tree.clone())).unwrap();
+        let NodesWithStats(loaded) = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, tree);
+    }
+}
@@ -0,0 +1,368 @@
+use super::columns::FeatureSource;
+use super::trees::{PredictorConfig, VecTreeNodes};
+use super::vec_tree::Traversable;
+use crate::arch::SimdLevel;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Structure-of-arrays copy of a tree for lockstep traversal of several rows.
+///
+/// Leaves point to themselves and store their weight in `value`, so every lane can take
+/// exactly `depth` steps from `root` without branching and then read its leaf weight.
+struct LockstepTree {
+    root: i32,
+    feature: Vec<i32>,
+    value: Vec<f32>,
+    left: Vec<i32>,
+    right: Vec<i32>,
+    /// -1 where a missing value goes right, 0 where it goes left.
+    nan_right: Vec<i32>,
+    depth: usize,
+}
+
+impl LockstepTree {
+    fn new(tree: &VecTreeNodes) -> Self {
+        if tree.is_empty() {
+            return Self {
+                root: 0,
+                feature: vec![0],
+                value: vec![0.0],
+                left: vec![0],
+                right: vec![0],
+                nan_right: vec![0],
+                depth: 0,
+            };
+        }
+
+        let len = tree.len();
+        let mut lockstep = Self {
+            root: tree.get_root_index() as i32,
+            feature: Vec::with_capacity(len),
+            value: Vec::with_capacity(len),
+            left: Vec::with_capacity(len),
+            right: Vec::with_capacity(len),
+            nan_right: Vec::with_capacity(len),
+            depth: tree.depth(),
+        };
+        for (idx, node) in tree.nodes.iter().enumerate() {
+            if node.is_leaf() {
+                lockstep.feature.push(0);
+                lockstep.value.push(node.weight());
+                lockstep.left.push(idx as i32);
+                lockstep.right.push(idx as i32);
+                lockstep.nan_right.push(0);
+            } else {
+                lockstep.feature.push(node.feature_index());
+                lockstep.value.push(node.split_value());
+                lockstep.left.push(node.left() as i32);
+                lockstep.right.push(node.right() as i32);
+                lockstep
+                    .nan_right
+                    .push(if node.default_left() { 0 } else { -1 });
+            }
+        }
+        lockstep
+    }
+
+    /// Leaf weights for `out.len()` rows stored row-major in `rows`, `num_features` apart.
+    fn leaf_values_portable(&self, rows: &[f32], num_features: usize, out: &mut [f32]) {
+        let mut current = [self.root as usize; 16];
+        let current = &mut current[..out.len()];
+        for _ in 0..self.depth {
+            for (lane, idx) in current.iter_mut().enumerate() {
+                let x = rows[lane * num_features + self.feature[*idx] as usize];
+                let go_right = if x.is_nan() {
+                    self.nan_right[*idx] != 0
+                } else {
+                    x >= self.value[*idx]
+                };
+                *idx = if go_right {
+                    self.right[*idx]
+                } else {
+                    self.left[*idx]
+                } as usize;
+            }
+        }
+        for (value, &idx) in out.iter_mut().zip(current.iter()) {
+            *value = self.value[idx];
+        }
+    }
+
+    /// # Safety
+    /// Requires AVX2, `out.len() == 8` and `rows.len() >= 8 * num_features`.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn leaf_values_avx2(&self, rows: &[f32], num_features: usize, out: &mut [f32]) {
+        let row_offsets = _mm256_mullo_epi32(
+            _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7),
+            _mm256_set1_epi32(num_features as i32),
+        );
+        let mut current = _mm256_set1_epi32(self.root);
+        for _ in 0..self.depth {
+            let feature = _mm256_i32gather_epi32::<4>(self.feature.as_ptr(), current);
+            let threshold = _mm256_i32gather_ps::<4>(self.value.as_ptr(), current);
+            let left = _mm256_i32gather_epi32::<4>(self.left.as_ptr(), current);
+            let right = _mm256_i32gather_epi32::<4>(self.right.as_ptr(), current);
+            let nan_right = _mm256_i32gather_epi32::<4>(self.nan_right.as_ptr(), current);
+
+            let x = _mm256_i32gather_ps::<4>(rows.as_ptr(), _mm256_add_epi32(row_offsets, feature));
+            let ge = _mm256_cmp_ps::<_CMP_GE_OQ>(x, threshold);
+            let nan = _mm256_cmp_ps::<_CMP_UNORD_Q>(x, x);
+            let go_right = _mm256_or_ps(
+                _mm256_andnot_ps(nan, ge),
+                _mm256_and_ps(nan, _mm256_castsi256_ps(nan_right)),
+            );
+            current = _mm256_castps_si256(_mm256_blendv_ps(
+                _mm256_castsi256_ps(left),
+                _mm256_castsi256_ps(right),
+                go_right,
+            ));
+        }
+        let values = _mm256_i32gather_ps::<4>(self.value.as_ptr(), current);
+        _mm256_storeu_ps(out.as_mut_ptr(), values);
+    }
+
+    /// # Safety
+    /// Requires AVX-512F, `out.len() == 16` and `rows.len() >= 16 * num_features`.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn leaf_values_avx512(&self, rows: &[f32], num_features: usize, out: &mut [f32]) {
+        let row_offsets = _mm512_mullo_epi32(
+            _mm512_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15),
+            _mm512_set1_epi32(num_features as i32),
+        );
+        let mut current = _mm512_set1_epi32(self.root);
+        // The pointee type of the AVX-512 gathers changed from `u8` to the element type over
+        // toolchain versions; `cast` infers whichever the toolchain expects.
+        for _ in 0..self.depth {
+            let feature = _mm512_i32gather_epi32::<4>(current, self.feature.as_ptr().cast());
+            let threshold = _mm512_i32gather_ps::<4>(current, self.value.as_ptr().cast());
+            let left = _mm512_i32gather_epi32::<4>(current, self.left.as_ptr().cast());
+            let right = _mm512_i32gather_epi32::<4>(current, self.right.as_ptr().cast());
+            let nan_right = _mm512_i32gather_epi32::<4>(current, self.nan_right.as_ptr().cast());
+
+            let x = _mm512_i32gather_ps::<4>(
+                _mm512_add_epi32(row_offsets, feature),
+                rows.as_ptr().cast(),
+            );
+            let ge = _mm512_cmp_ps_mask::<_CMP_GE_OQ>(x, threshold);
+            let nan = _mm512_cmp_ps_mask::<_CMP_UNORD_Q>(x, x);
+            let go_right = (ge & !nan) | (nan & _mm512_test_epi32_mask(nan_right, nan_right));
+            current = _mm512_mask_blend_epi32(go_right, left, right);
+        }
+        let values = _mm512_i32gather_ps::<4>(current, self.value.as_ptr().cast());
+        _mm512_storeu_ps(out.as_mut_ptr(), values);
+    }
+}
+
+/// Evaluates an ensemble by moving a group of rows through each tree in lockstep.
+pub(crate) struct LockstepPredictor {
+    trees: Vec<LockstepTree>,
+    level: SimdLevel,
+    max_feature: Option<usize>,
+}
+
+impl LockstepPredictor {
+    /// `level` must be supported by the running CPU, as reported by `CpuFeatures`.
+    pub(crate) fn new(trees: &[VecTreeNodes], level: SimdLevel) -> Self {
+        let trees: Vec<LockstepTree> = trees.iter().map(LockstepTree::new).collect();
+        let max_feature = trees
+            .iter()
+            .flat_map(|tree| tree.feature.iter())
+            .map(|&feature| feature as usize)
+            .max();
+        Self {
+            trees,
+            level,
+            max_feature,
+        }
+    }
+
+    pub(crate) fn level(&self) -> SimdLevel {
+        self.level
+    }
+
+    /// Appends raw scores for `row_indices` of `features` to `out`, summing trees in chunks of
+    /// `config.tree_chunk_size()` in the same order as the scalar traversal.
+    pub(crate) fn predict_rows<F: FeatureSource>(
+        &self,
+        features: &F,
+        row_indices: &[usize],
+        base_score: f32,
+        config: &PredictorConfig,
+        out: &mut Vec<f32>,
+    ) {
+        let tree_chunk_size = config.tree_chunk_size();
+        let num_features = features.num_features();
+        // Gathers are unchecked, so every feature index must be inside a row.
+        assert!(self.max_feature.is_none_or(|max| max < num_features.max(1)));
+
+        let lanes = self.level.lanes();
+        let row_len = num_features.max(1);
+        let mut rows = vec![0.0; lanes * row_len];
+        let mut leaf_values = vec![0.0; tree_chunk_size.min(self.trees.len()) * lanes];
+
+        for group in row_indices.chunks(lanes) {
+            // Pad short groups by repeating the last row; their results are dropped.
+            let mut lane_rows = [0; 16];
+            for (lane, row_idx) in lane_rows.iter_mut().enumerate().take(lanes) {
+                *row_idx = group[lane.min(group.len() - 1)];
+            }
+            features.gather_rows(&lane_rows[..lanes], row_len, &mut rows);
+
+            let mut scores = [base_score as f64; 16];
+            for tree_chunk in self.trees.chunks(tree_chunk_size) {
+                for (tree, values) in tree_chunk.iter().zip(leaf_values.chunks_mut(lanes)) {
+                    self.leaf_values(tree, &rows, row_len, values);
+                }
+                for (lane, score) in scores.iter_mut().enumerate().take(group.len()) {
+                    *score = config.accumulation().add_chunk(
+                        *score,
+                        (0..tree_chunk.len()).map(|tree| leaf_values[tree * lanes + lane]),
+                    );
+                }
+            }
+            out.extend(scores[..group.len()].iter().map(|&score| score as f32));
+        }
+    }
+
+    #[inline(always)]
+    fn leaf_values(&self, tree: &LockstepTree, rows: &[f32], row_len: usize, out: &mut [f32]) {
+        match self.level {
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx512 => unsafe { tree.leaf_values_avx512(rows, row_len, out) },
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => unsafe { tree.leaf_values_avx2(rows, row_len, out) },
+            _ => tree.leaf_values_portable(rows, row_len, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::CpuFeatures;
+    use crate::tree::columns::FeatureColumns;
+    use arrow::array::{ArrayRef, Float32Array};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+
+    /// Builds a random, unbalanced tree over `num_features` features.
+    fn random_tree(rng: &mut StdRng, num_features: i32) -> VecTreeNodes {
+        let mut split_indices = vec![];
+        let mut split_conditions = vec![];
+        let mut left = vec![];
+        let mut right = vec![];
+        let mut weights = vec![];
+        let mut default_left = vec![];
+        let mut pending = vec![(0usize, 0u32)];
+        split_indices.push(0);
+        split_conditions.push(0.0);
+        left.push(u32::MAX);
+        right.push(u32::MAX);
+        weights.push(0.0);
+        default_left.push(false);
+
+        while let Some((idx, depth)) = pending.pop() {
+            if depth >= 8 || (depth > 0 && rng.gen_bool(0.3)) {
+                split_indices[idx] = -1;
+                weights[idx] = rng.gen_range(-1.0..1.0);
+                continue;
+            }
+            split_indices[idx] = rng.gen_range(0..num_features);
+            split_conditions[idx] = rng.gen_range(0..8) as f32 * 0.5;
+            default_left[idx] = rng.gen_bool(0.5);
+            for child in 0..2 {
+                let child_idx = split_indices.len();
+                split_indices.push(0);
+                split_conditions.push(0.0);
+                left.push(u32::MAX);
+                right.push(u32::MAX);
+                weights.push(0.0);
+                default_left.push(false);
+                if child == 0 {
+                    left[idx] = child_idx as u32;
+                } else {
+                    right[idx] = child_idx as u32;
+                }
+                pending.push((child_idx, depth + 1));
+            }
+        }
+
+        VecTreeNodes::builder()
+            .split_indices(split_indices)
+            .split_conditions(split_conditions)
+            .children(left, right)
+            .base_weights(weights)
+            .default_left(default_left)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lockstep_matches_scalar_traversal() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let num_features = 5;
+        let trees: Vec<VecTreeNodes> = (0..30)
+            .map(|_| random_tree(&mut rng, num_features))
+            .chain(std::iter::once(VecTreeNodes::new()))
+            .collect();
+        let num_rows = 203;
+        let features: Vec<Vec<f32>> = (0..num_features)
+            .map(|_| {
+                (0..num_rows)
+                    .map(|_| {
+                        if rng.gen_bool(0.15) {
+                            f32::NAN
+                        } else {
+                            rng.gen_range(0..9) as f32 * 0.5 - 0.25 * rng.gen_range(0..2) as f32
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let arrays: Vec<ArrayRef> = features
+            .iter()
+            .map(|column| Arc::new(Float32Array::from(column.clone())) as ArrayRef)
+            .collect();
+        let columns = FeatureColumns::try_new(&arrays, num_rows).unwrap();
+        let row_indices: Vec<usize> = (0..num_rows).collect();
+
+        let mut levels = vec![SimdLevel::Portable];
+        match CpuFeatures::new().simd_level() {
+            SimdLevel::Avx512 => levels.extend([SimdLevel::Avx2, SimdLevel::Avx512]),
+            SimdLevel::Avx2 => levels.push(SimdLevel::Avx2),
+            SimdLevel::Portable => {}
+        }
+
+        for tree_chunk_size in [1, 7, 64] {
+            let expected: Vec<f32> = row_indices
+                .iter()
+                .map(|&row_idx| {
+                    let row: Vec<f32> = features.iter().map(|column| column[row_idx]).collect();
+                    let mut score = 0.5;
+                    for chunk in trees.chunks(tree_chunk_size) {
+                        let chunk_score: f32 = chunk.iter().map(|tree| tree.predict(&row)).sum();
+                        score += chunk_score;
+                    }
+                    score
+                })
+                .collect();
+
+            let config = PredictorConfig::default()
+                .with_tree_chunk_size(tree_chunk_size)
+                .unwrap();
+            for &level in &levels {
+                let predictor = LockstepPredictor::new(&trees, level);
+                let mut actual = Vec::new();
+                predictor.predict_rows(&columns, &row_indices, 0.5, &config, &mut actual);
+                assert_eq!(actual.len(), expected.len());
+                for (row, (a, e)) in actual.iter().zip(&expected).enumerate() {
+                    assert_eq!(a.to_bits(), e.to_bits(), "{:?} row {}", level, row);
+                }
+            }
+        }
+    }
+}
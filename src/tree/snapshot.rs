@@ -0,0 +1,269 @@
+use super::engine_cache::EngineCache;
+use super::serde_helpers::vec_tree_stats_serde;
+use super::trees::{
+    Accumulation, GradientBoostedDecisionTrees, Parallelism, PredictionEngine, PredictorConfig,
+    VecTreeNodes,
+};
+use super::vec_tree::Traversable;
+use super::FeatureType;
+use crate::loader::ModelError;
+use crate::objective::Objective;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Leading bytes of every snapshot, followed by [`SNAPSHOT_VERSION`].
+const SNAPSHOT_MAGIC: &[u8; 4] = b"TRST";
+const SNAPSHOT_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    trees: Vec<SnapshotTree>,
+    feature_names: Vec<String>,
+    feature_types: Vec<FeatureType>,
+    base_score: f32,
+    objective: Objective,
+    objective_name: Option<String>,
+    required_features: Vec<usize>,
+    config: SnapshotConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotTree(#[serde(with = "vec_tree_stats_serde")] VecTreeNodes);
+
+/// The configuration, with a dedicated thread pool stored as its number of threads since the
+/// pool itself belongs to the process.
+#[derive(Serialize, Deserialize)]
+struct SnapshotConfig {
+    row_chunk_size: usize,
+    tree_chunk_size: usize,
+    engine: String,
+    accumulation: String,
+    /// `None` for rayon's global pool and 1 for sequential prediction.
+    threads: Option<usize>,
+    sequential_threshold: usize,
+}
+
+fn invalid(message: impl Into<String>) -> ModelError {
+    ModelError::InvalidSnapshot(message.into())
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Serializes the model into a compact binary snapshot, read back by
+    /// [`from_bytes`](Self::from_bytes).
+    ///
+    /// Unlike [`to_xgboost_json`](Self::to_xgboost_json), the snapshot keeps everything that
+    /// affects predictions: node order and statistics, the compacted feature indices of pruned
+    /// models and the configuration. A dedicated thread pool is stored as its number of threads
+    /// and loaded models build a new pool of that size.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut required_features: Vec<usize> = self.required_features.iter().copied().collect();
+        required_features.sort_unstable();
+        let snapshot = Snapshot {
+            trees: self.trees.iter().cloned().map(SnapshotTree).collect(),
+            feature_names: self.feature_names.to_vec(),
+            feature_types: self.feature_types.to_vec(),
+            base_score: self.base_score,
+            objective: self.objective.clone(),
+            objective_name: self.objective_name.clone(),
+            required_features,
+            config: SnapshotConfig {
+                row_chunk_size: self.config.row_chunk_size(),
+                tree_chunk_size: self.config.tree_chunk_size(),
+                engine: self.config.engine().to_string(),
+                accumulation: self.config.accumulation().to_string(),
+                threads: match self.config.parallelism() {
+                    Parallelism::Global => None,
+                    Parallelism::Pool(pool) => Some(pool.current_num_threads()),
+                    Parallelism::Sequential => Some(1),
+                },
+                sequential_threshold: self.config.sequential_threshold(),
+            },
+        };
+
+        let mut bytes = SNAPSHOT_MAGIC.to_vec();
+        bytes.push(SNAPSHOT_VERSION);
+        bincode::serialize_into(&mut bytes, &snapshot).expect("model snapshots serialize");
+        bytes
+    }
+
+    /// Loads a model written by [`to_bytes`](Self::to_bytes), checking that its trees only
+    /// reference nodes and features that exist.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        let payload = bytes
+            .strip_prefix(SNAPSHOT_MAGIC)
+            .ok_or_else(|| invalid("not a trusty model snapshot"))?;
+        let payload = match payload.split_first() {
+            Some((&SNAPSHOT_VERSION, payload)) => payload,
+            Some((version, _)) => {
+                return Err(invalid(format!("unsupported version {}", version)));
+            }
+            None => return Err(invalid("missing version")),
+        };
+        let snapshot: Snapshot =
+            bincode::deserialize(payload).map_err(|e| invalid(e.to_string()))?;
+
+        if snapshot.feature_types.len() != snapshot.feature_names.len() {
+            return Err(invalid("feature names and types differ in length"));
+        }
+        if let Some(&index) = snapshot
+            .required_features
+            .iter()
+            .find(|&&index| index >= snapshot.feature_names.len())
+        {
+            return Err(invalid(format!(
+                "required feature {} out of bounds for {} features",
+                index,
+                snapshot.feature_names.len()
+            )));
+        }
+        for (tree_index, SnapshotTree(tree)) in snapshot.trees.iter().enumerate() {
+            let features_in_bounds = tree.nodes.iter().all(|node| {
+                node.is_leaf()
+                    || (0..snapshot.required_features.len() as i32).contains(&node.feature_index())
+            });
+            if tree.is_empty()
+                || !tree.validate_connections()
+                || !features_in_bounds
+                || !(tree.stats.is_empty() || tree.stats.len() == tree.len())
+            {
+                return Err(invalid(format!("tree {} is malformed", tree_index)));
+            }
+        }
+
+        let config = &snapshot.config;
+        let engine = config
+            .engine
+            .parse::<PredictionEngine>()
+            .map_err(ModelError::InvalidSnapshot)?;
+        let accumulation = config
+            .accumulation
+            .parse::<Accumulation>()
+            .map_err(ModelError::InvalidSnapshot)?;
+        let parallelism = match config.threads {
+            None => Parallelism::Global,
+            Some(threads) => Parallelism::threads(threads).map_err(|e| invalid(e.to_string()))?,
+        };
+        let config = PredictorConfig::new(config.row_chunk_size, config.tree_chunk_size)
+            .map_err(|e| invalid(e.to_string()))?
+            .with_engine(engine)
+            .with_accumulation(accumulation)
+            .with_parallelism(parallelism)
+            .with_sequential_threshold(config.sequential_threshold);
+
+        Ok(GradientBoostedDecisionTrees {
+            trees: snapshot.trees.into_iter().map(|tree| tree.0).collect(),
+            feature_names: Arc::new(snapshot.feature_names),
+            base_score: snapshot.base_score,
+            feature_types: Arc::new(snapshot.feature_types),
+            objective: snapshot.objective,
+            objective_name: snapshot.objective_name,
+            config,
+            required_features: snapshot.required_features.into_iter().collect(),
+            engine_cache: EngineCache::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::ModelLoader;
+    use crate::predicates::{Condition, Predicate};
+    use crate::tree::NodeLayout;
+    use arrow::array::{ArrayRef, Float32Array};
+
+    fn load_model() -> GradientBoostedDecisionTrees {
+        GradientBoostedDecisionTrees::json_load(
+            "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json",
+        )
+        .unwrap()
+    }
+
+    fn sample_arrays(model: &GradientBoostedDecisionTrees) -> Vec<ArrayRef> {
+        (0..model.required_features.len())
+            .map(|feature| {
+                let values: Vec<f32> = (0..64)
+                    .map(|row| ((row * 7 + feature * 3) % 11) as f32 * 0.3)
+                    .collect();
+                Arc::new(Float32Array::from(values)) as ArrayRef
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut predicate = Predicate::new();
+        predicate.add_condition("carat".to_string(), Condition::LessThan(0.5));
+        let mut model = load_model().prune(&predicate).with_layout(NodeLayout {
+            bfs_levels: 2,
+            block_size: 4,
+            use_cover: true,
+        });
+        model.set_config(
+            PredictorConfig::new(16, 4)
+                .unwrap()
+                .with_engine(PredictionEngine::QuickScorer)
+                .with_accumulation(Accumulation::F64)
+                .with_parallelism(Parallelism::threads(3).unwrap())
+                .with_sequential_threshold(10),
+        );
+
+        let bytes = model.to_bytes();
+        assert!(bytes.len() < model.to_xgboost_json().to_string().len() / 2);
+        let loaded = GradientBoostedDecisionTrees::from_bytes(&bytes).unwrap();
+
+        assert_eq!(loaded.trees, model.trees);
+        assert_eq!(loaded.feature_names, model.feature_names);
+        assert_eq!(loaded.required_features, model.required_features);
+        assert_eq!(loaded.base_score, model.base_score);
+        assert_eq!(loaded.config.row_chunk_size(), 16);
+        assert_eq!(loaded.config.tree_chunk_size(), 4);
+        assert_eq!(loaded.config.engine(), PredictionEngine::QuickScorer);
+        assert_eq!(loaded.config.accumulation(), Accumulation::F64);
+        assert_eq!(loaded.config.sequential_threshold(), 10);
+        match loaded.config.parallelism() {
+            Parallelism::Pool(pool) => assert_eq!(pool.current_num_threads(), 3),
+            other => panic!("expected a pool, got {:?}", other),
+        }
+        assert!(loaded.trees.iter().any(|tree| !tree.stats.is_empty()));
+
+        let arrays = sample_arrays(&model);
+        assert_eq!(
+            loaded.predict_arrays(&arrays).unwrap(),
+            model.predict_arrays(&arrays).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_parallelism() {
+        let mut model = load_model();
+        let loaded = GradientBoostedDecisionTrees::from_bytes(&model.to_bytes()).unwrap();
+        assert!(matches!(loaded.config.parallelism(), Parallelism::Global));
+
+        model.set_config(PredictorConfig::default().with_parallelism(Parallelism::Sequential));
+        let loaded = GradientBoostedDecisionTrees::from_bytes(&model.to_bytes()).unwrap();
+        assert!(matches!(
+            loaded.config.parallelism(),
+            Parallelism::Sequential
+        ));
+    }
+
+    #[test]
+    fn test_invalid_snapshots() {
+        let model = load_model();
+        let bytes = model.to_bytes();
+
+        let err = GradientBoostedDecisionTrees::from_bytes(b"{\"learner\": {}}").unwrap_err();
+        assert!(err.to_string().contains("not a trusty model snapshot"));
+        let mut future = bytes.clone();
+        future[SNAPSHOT_MAGIC.len()] = SNAPSHOT_VERSION + 1;
+        let err = GradientBoostedDecisionTrees::from_bytes(&future).unwrap_err();
+        assert!(err.to_string().contains("unsupported version"));
+        assert!(GradientBoostedDecisionTrees::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+
+        let mut broken = model.clone();
+        broken.trees[0].nodes[0].set_feature_index(model.required_features.len() as i32);
+        let err = GradientBoostedDecisionTrees::from_bytes(&broken.to_bytes()).unwrap_err();
+        assert!(err.to_string().contains("tree 0 is malformed"));
+    }
+}
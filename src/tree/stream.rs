@@ -0,0 +1,295 @@
+use super::trees::GradientBoostedDecisionTrees;
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Name of the prediction column unless set with
+/// [`with_column_name`](PredictionStream::with_column_name).
+pub const PREDICTION_COLUMN: &str = "prediction";
+
+/// Columns of the batches produced by a [`PredictionStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputColumns {
+    /// The prediction column alone.
+    #[default]
+    Prediction,
+    /// The input columns followed by the prediction column.
+    Append,
+}
+
+/// Scores the batches of a [`RecordBatchReader`] one at a time.
+///
+/// Each input batch produces one output batch, so memory use is bounded by the batch size of
+/// the reader rather than by the size of the input. Feature columns are looked up by name as in
+/// [`feature_arrays_by_name`](GradientBoostedDecisionTrees::feature_arrays_by_name).
+pub struct PredictionStream<'a, R> {
+    model: &'a GradientBoostedDecisionTrees,
+    reader: R,
+    columns: OutputColumns,
+    schema: SchemaRef,
+}
+
+impl<'a, R: RecordBatchReader> PredictionStream<'a, R> {
+    fn new(model: &'a GradientBoostedDecisionTrees, reader: R, columns: OutputColumns) -> Self {
+        let schema = output_schema(&reader.schema(), columns, PREDICTION_COLUMN);
+        Self {
+            model,
+            reader,
+            columns,
+            schema,
+        }
+    }
+
+    pub fn with_column_name(mut self, name: &str) -> Self {
+        self.schema = output_schema(&self.reader.schema(), self.columns, name);
+        self
+    }
+
+    fn predict(&self, batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+        let features = self.model.feature_arrays_by_name(&batch)?;
+        let predictions: ArrayRef = Arc::new(self.model.predict_arrays(&features)?);
+        let columns = match self.columns {
+            OutputColumns::Prediction => vec![predictions],
+            OutputColumns::Append => {
+                let mut columns = batch.columns().to_vec();
+                columns.push(predictions);
+                columns
+            }
+        };
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+fn output_schema(input: &Schema, columns: OutputColumns, name: &str) -> SchemaRef {
+    let prediction = Arc::new(Field::new(name, DataType::Float32, false));
+    let fields = match columns {
+        OutputColumns::Prediction => vec![prediction],
+        OutputColumns::Append => input
+            .fields()
+            .iter()
+            .cloned()
+            .chain(std::iter::once(prediction))
+            .collect(),
+    };
+    Arc::new(Schema::new(fields))
+}
+
+impl<R: RecordBatchReader> Iterator for PredictionStream<'_, R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.reader.next()?;
+        Some(batch.and_then(|batch| self.predict(batch)))
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for PredictionStream<'_, R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Lazily scores the batches of `reader`. Errors of the reader are passed through.
+    pub fn predict_stream<R: RecordBatchReader>(
+        &self,
+        reader: R,
+        columns: OutputColumns,
+    ) -> PredictionStream<'_, R> {
+        PredictionStream::new(self, reader, columns)
+    }
+
+    /// Scores an Arrow IPC file batch by batch into another IPC file, and returns the number
+    /// of rows written.
+    pub fn predict_ipc_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        columns: OutputColumns,
+    ) -> Result<usize, ArrowError> {
+        let reader = FileReader::try_new(BufReader::new(File::open(input)?), None)?;
+        let stream = self.predict_stream(reader, columns);
+        let mut writer =
+            FileWriter::try_new(BufWriter::new(File::create(output)?), &stream.schema())?;
+        let num_rows = write_stream(stream, |batch| writer.write(batch))?;
+        writer.finish()?;
+        Ok(num_rows)
+    }
+
+    /// Scores a Parquet file into another Parquet file, reading `batch_size` rows at a time,
+    /// and returns the number of rows written.
+    #[cfg(feature = "parquet")]
+    pub fn predict_parquet_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        batch_size: usize,
+        columns: OutputColumns,
+    ) -> Result<usize, ArrowError> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use parquet::arrow::ArrowWriter;
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(input)?)?
+            .with_batch_size(batch_size)
+            .build()?;
+        let stream = self.predict_stream(reader, columns);
+        let mut writer = ArrowWriter::try_new(File::create(output)?, stream.schema(), None)?;
+        let num_rows = write_stream(stream, |batch| writer.write(batch).map_err(Into::into))?;
+        writer.close()?;
+        Ok(num_rows)
+    }
+}
+
+fn write_stream<R: RecordBatchReader>(
+    stream: PredictionStream<'_, R>,
+    mut write: impl FnMut(&RecordBatch) -> Result<(), ArrowError>,
+) -> Result<usize, ArrowError> {
+    let mut num_rows = 0;
+    for batch in stream {
+        let batch = batch?;
+        write(&batch)?;
+        num_rows += batch.num_rows();
+    }
+    Ok(num_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{FeatureType, VecTreeNodes};
+    use arrow::array::{Array, Float32Array, Float64Array, Int64Array};
+    use arrow::record_batch::RecordBatchIterator;
+
+    // Splits on "b" at 0.5.
+    fn model() -> GradientBoostedDecisionTrees {
+        let tree = VecTreeNodes::builder()
+            .split_indices(vec![0, -1, -1])
+            .split_conditions(vec![0.5, 0.0, 0.0])
+            .children(vec![1, u32::MAX, u32::MAX], vec![2, u32::MAX, u32::MAX])
+            .base_weights(vec![0.0, -1.0, 1.0])
+            .default_left(vec![true, false, false])
+            .build()
+            .unwrap();
+        GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["a".into(), "b".into()]),
+            feature_types: Arc::new(vec![FeatureType::Int, FeatureType::Float]),
+            required_features: [1].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    fn batches() -> Vec<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("b", DataType::Float64, true),
+            Field::new("id", DataType::Int64, false),
+        ]));
+        [vec![Some(0.0), Some(1.0)], vec![None]]
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let ids = vec![i as i64; b.len()];
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Float64Array::from(b)),
+                        Arc::new(Int64Array::from(ids)),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn reader(batches: Vec<RecordBatch>) -> impl RecordBatchReader {
+        let schema = batches[0].schema();
+        RecordBatchIterator::new(batches.into_iter().map(Ok), schema)
+    }
+
+    fn predictions(batch: &RecordBatch, name: &str) -> Vec<f32> {
+        let column = batch.column_by_name(name).unwrap();
+        let values = column.as_any().downcast_ref::<Float32Array>().unwrap();
+        values.values().to_vec()
+    }
+
+    #[test]
+    fn test_stream_output_columns() {
+        let model = model();
+        let stream = model.predict_stream(reader(batches()), OutputColumns::Prediction);
+        let schema = stream.schema();
+        let output: Vec<_> = stream.collect::<Result<_, _>>().unwrap();
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(output.len(), 2);
+        assert_eq!(predictions(&output[0], PREDICTION_COLUMN), [-1.0, 1.0]);
+        assert_eq!(predictions(&output[1], PREDICTION_COLUMN), [-1.0]);
+
+        let stream = model
+            .predict_stream(reader(batches()), OutputColumns::Append)
+            .with_column_name("score");
+        let output: Vec<_> = stream.collect::<Result<_, _>>().unwrap();
+        assert_eq!(output[1].schema().field(2).name(), "score");
+        assert_eq!(output[1].column(1).len(), 1);
+        assert_eq!(predictions(&output[1], "score"), [-1.0]);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let model = model();
+        let schema = batches()[0].schema();
+        let failing = RecordBatchIterator::new(
+            vec![
+                Ok(batches().remove(0)),
+                Err(ArrowError::IoError(
+                    "truncated".into(),
+                    std::io::ErrorKind::Other.into(),
+                )),
+            ],
+            schema,
+        );
+        let mut stream = model.predict_stream(failing, OutputColumns::Prediction);
+        assert!(stream.next().unwrap().is_ok());
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+
+        let without_b = batches()[0].project(&[1]).unwrap();
+        let mut stream = model.predict_stream(reader(vec![without_b]), OutputColumns::Prediction);
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("Missing feature column: b"));
+    }
+
+    #[test]
+    fn test_predict_ipc_file() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("trusty-stream-{}-in.arrow", std::process::id()));
+        let output = dir.join(format!("trusty-stream-{}-out.arrow", std::process::id()));
+        let mut writer =
+            FileWriter::try_new(File::create(&input).unwrap(), &batches()[0].schema()).unwrap();
+        for batch in batches() {
+            writer.write(&batch).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let model = model();
+        let num_rows = model
+            .predict_ipc_file(&input, &output, OutputColumns::Append)
+            .unwrap();
+        assert_eq!(num_rows, 3);
+        let written: Vec<_> = FileReader::try_new(File::open(&output).unwrap(), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[0].num_columns(), 3);
+        assert_eq!(predictions(&written[0], PREDICTION_COLUMN), [-1.0, 1.0]);
+
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
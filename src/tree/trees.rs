@@ -1,19 +1,27 @@
-use super::vec_tree::{Traversable, TreeNode, VecTree};
+use super::columns::{FeatureColumns, FeatureSource};
+use super::engine_cache::EngineCache;
+use super::tuning::read_cached_tuning;
+use super::vec_tree::{NodeStats, Traversable, TreeNode, VecTree};
 use crate::arch::CpuFeatures;
 use crate::loader::{ModelError, ModelLoader, XGBoostParser};
 use crate::objective::Objective;
 use crate::predicates::{Condition, Predicate};
 use crate::tree::{FeatureTreeError, FeatureType};
-use arrow::array::{Array, ArrayRef, BooleanArray, Float32Array, Float32Builder, Int64Array};
-use arrow::datatypes::DataType;
+use arrow::array::{ArrayRef, Float32Array, Float32Builder};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema};
 use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::OnceLock;
+use thiserror::Error;
 
 pub type VecTreeNodes = VecTree<TreeNode>;
 
@@ -169,6 +177,9 @@ impl VecTreeNodes {
                 PruneAction::Keep => {
                     let new_idx = new_tree.nodes.len();
                     new_tree.nodes.push(node.clone());
+                    if let Some(stats) = old_tree.get_stats(node_idx) {
+                        new_tree.stats.push(*stats);
+                    }
 
                     if !node.is_leaf() {
                         let left_idx = prune_recursive(
@@ -220,10 +231,10 @@ impl VecTreeNodes {
         FeatureTreeBuilder::new()
     }
 
-    fn from_nodes(nodes: Vec<NodeDefinition>) -> Result<Self, FeatureTreeError> {
-        if nodes.is_empty() {
-            return Err(FeatureTreeError::InvalidStructure("Empty tree".to_string()));
-        }
+    fn from_nodes(
+        nodes: Vec<NodeDefinition>,
+        stats: Vec<NodeStats>,
+    ) -> Result<Self, FeatureTreeError> {
         if nodes.is_empty() {
             return Err(FeatureTreeError::InvalidStructure("Empty tree".to_string()));
         }
@@ -253,8 +264,13 @@ impl VecTreeNodes {
         for (builder_idx, node_def) in nodes.iter().enumerate() {
             if let NodeDefinition::Split { left, right, .. } = node_def {
                 let parent_idx = node_map[&builder_idx];
-                let left_idx = node_map[left];
-                let right_idx = node_map[right];
+                let (Some(&left_idx), Some(&right_idx)) = (node_map.get(left), node_map.get(right))
+                else {
+                    return Err(FeatureTreeError::InvalidStructure(format!(
+                        "Children of node {} are out of bounds",
+                        builder_idx
+                    )));
+                };
 
                 vec_tree.connect_left(parent_idx, left_idx).map_err(|_| {
                     FeatureTreeError::InvalidStructure("Invalid left child connection".to_string())
@@ -271,6 +287,13 @@ impl VecTreeNodes {
             ));
         }
 
+        if !stats.is_empty() {
+            vec_tree.stats = vec![NodeStats::default(); vec_tree.len()];
+            for (builder_idx, node_stats) in stats.into_iter().enumerate() {
+                vec_tree.stats[node_map[&builder_idx]] = node_stats;
+            }
+        }
+
         Ok(vec_tree)
     }
 }
@@ -282,6 +305,8 @@ pub struct FeatureTreeBuilder {
     right_children: Vec<u32>,
     base_weights: Vec<f32>,
     default_left: Vec<bool>,
+    loss_changes: Vec<f32>,
+    sum_hessians: Vec<f32>,
 }
 
 impl FeatureTreeBuilder {
@@ -293,6 +318,8 @@ impl FeatureTreeBuilder {
             right_children: Vec::new(),
             base_weights: Vec::new(),
             default_left: Vec::new(),
+            loss_changes: Vec::new(),
+            sum_hessians: Vec::new(),
         }
     }
 
@@ -332,17 +359,41 @@ impl FeatureTreeBuilder {
         }
     }
 
+    /// Optional per-node split gains, used for feature importance.
+    pub fn loss_changes(self, gains: Vec<f32>) -> Self {
+        Self {
+            loss_changes: gains,
+            ..self
+        }
+    }
+
+    /// Optional per-node hessian sums, used for feature importance.
+    pub fn sum_hessians(self, covers: Vec<f32>) -> Self {
+        Self {
+            sum_hessians: covers,
+            ..self
+        }
+    }
+
     pub fn build(self) -> Result<VecTreeNodes, FeatureTreeError> {
         let node_count = self.split_indices.len();
         if self.split_conditions.len() != node_count
             || self.left_children.len() != node_count
             || self.right_children.len() != node_count
             || self.base_weights.len() != node_count
+            || self.default_left.len() != node_count
         {
             return Err(FeatureTreeError::InvalidStructure(
                 "Inconsistent array lengths in tree definition".to_string(),
             ));
         }
+        if (!self.loss_changes.is_empty() && self.loss_changes.len() != node_count)
+            || (!self.sum_hessians.is_empty() && self.sum_hessians.len() != node_count)
+        {
+            return Err(FeatureTreeError::InvalidStructure(
+                "Inconsistent node statistics lengths in tree definition".to_string(),
+            ));
+        }
 
         let mut nodes = Vec::with_capacity(node_count);
         for i in 0..node_count {
@@ -363,7 +414,18 @@ impl FeatureTreeBuilder {
             nodes.push(node);
         }
 
-        VecTreeNodes::from_nodes(nodes)
+        let stats = if self.loss_changes.is_empty() && self.sum_hessians.is_empty() {
+            Vec::new()
+        } else {
+            (0..node_count)
+                .map(|i| NodeStats {
+                    gain: self.loss_changes.get(i).copied().unwrap_or(0.0),
+                    cover: self.sum_hessians.get(i).copied().unwrap_or(0.0),
+                })
+                .collect()
+        };
+
+        VecTreeNodes::from_nodes(nodes, stats)
     }
 }
 
@@ -373,10 +435,134 @@ impl Default for FeatureTreeBuilder {
     }
 }
 
+/// Algorithm used to evaluate the trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PredictionEngine {
+    /// Walks each tree from the root for every row.
+    #[default]
+    Traversal,
+    /// Evaluates splits feature by feature with per-tree leaf bitvectors. Faster for large
+    /// ensembles of shallow trees.
+    QuickScorer,
+    /// Moves 8 or 16 rows through each tree in lockstep using AVX2 or AVX-512 gathers when
+    /// the CPU supports them, and a portable implementation otherwise.
+    Simd,
+}
+
+impl FromStr for PredictionEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "traversal" => Ok(PredictionEngine::Traversal),
+            "quickscorer" => Ok(PredictionEngine::QuickScorer),
+            "simd" => Ok(PredictionEngine::Simd),
+            other => Err(format!("Unsupported prediction engine: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for PredictionEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PredictionEngine::Traversal => write!(f, "traversal"),
+            PredictionEngine::QuickScorer => write!(f, "quickscorer"),
+            PredictionEngine::Simd => write!(f, "simd"),
+        }
+    }
+}
+
+/// Precision in which the tree outputs of a row are added up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Accumulation {
+    /// Sums each chunk of `tree_chunk_size` trees in `f32` and adds it to an `f32` score.
+    /// Results change in the last bits with the tree chunk size.
+    #[default]
+    Chunked,
+    /// Adds every tree output to an `f64` score in tree order and rounds to `f32` once.
+    /// Results are identical for any chunk sizes, engine and number of threads.
+    F64,
+}
+
+impl Accumulation {
+    /// Adds the outputs of one tree chunk to `score`. In `Chunked` mode the score always holds
+    /// an `f32` value, so widening it between chunks loses nothing.
+    #[inline(always)]
+    pub(crate) fn add_chunk(self, score: f64, outputs: impl Iterator<Item = f32>) -> f64 {
+        match self {
+            Accumulation::Chunked => (score as f32 + outputs.sum::<f32>()) as f64,
+            Accumulation::F64 => outputs.fold(score, |score, output| score + output as f64),
+        }
+    }
+}
+
+impl FromStr for Accumulation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chunked" => Ok(Accumulation::Chunked),
+            "f64" => Ok(Accumulation::F64),
+            other => Err(format!("Unsupported accumulation: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Accumulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Accumulation::Chunked => write!(f, "chunked"),
+            Accumulation::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// Threads that predictions run on.
+#[derive(Debug, Clone, Default)]
+pub enum Parallelism {
+    /// Rayon's global thread pool.
+    #[default]
+    Global,
+    /// A dedicated pool, e.g. to keep predictions from competing with an async runtime or a
+    /// query engine for the global pool.
+    Pool(Arc<ThreadPool>),
+    /// The calling thread only.
+    Sequential,
+}
+
+impl Parallelism {
+    /// A dedicated pool of `num_threads` threads, or `Sequential` for a single thread. As in
+    /// rayon, 0 means one thread per core.
+    pub fn threads(num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        if num_threads == 1 {
+            return Ok(Parallelism::Sequential);
+        }
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("trusty-predict-{}", i))
+            .build()?;
+        Ok(Parallelism::Pool(Arc::new(pool)))
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PredictorConfigError {
+    #[error("row_chunk_size must be at least 1")]
+    ZeroRowChunkSize,
+    #[error("tree_chunk_size must be at least 1")]
+    ZeroTreeChunkSize,
+}
+
+/// Settings for prediction. Chunk sizes are validated on construction, so a config in hand is
+/// always usable.
 #[derive(Debug, Clone)]
 pub struct PredictorConfig {
-    pub row_chunk_size: usize,
-    pub tree_chunk_size: usize,
+    row_chunk_size: usize,
+    tree_chunk_size: usize,
+    engine: PredictionEngine,
+    accumulation: Accumulation,
+    parallelism: Parallelism,
+    sequential_threshold: usize,
 }
 
 impl Default for PredictorConfig {
@@ -384,19 +570,163 @@ impl Default for PredictorConfig {
         Self {
             row_chunk_size: 8,
             tree_chunk_size: 64,
+            engine: PredictionEngine::default(),
+            accumulation: Accumulation::default(),
+            parallelism: Parallelism::default(),
+            sequential_threshold: 64,
         }
     }
 }
 
+impl PredictorConfig {
+    /// Rows are processed in chunks of `row_chunk_size` and each row's tree outputs are summed
+    /// in chunks of `tree_chunk_size` trees. Both must be positive.
+    pub fn new(
+        row_chunk_size: usize,
+        tree_chunk_size: usize,
+    ) -> Result<Self, PredictorConfigError> {
+        PredictorConfig::default()
+            .with_row_chunk_size(row_chunk_size)?
+            .with_tree_chunk_size(tree_chunk_size)
+    }
+
+    pub fn with_row_chunk_size(self, row_chunk_size: usize) -> Result<Self, PredictorConfigError> {
+        if row_chunk_size == 0 {
+            return Err(PredictorConfigError::ZeroRowChunkSize);
+        }
+        Ok(Self {
+            row_chunk_size,
+            ..self
+        })
+    }
+
+    pub fn with_tree_chunk_size(
+        self,
+        tree_chunk_size: usize,
+    ) -> Result<Self, PredictorConfigError> {
+        if tree_chunk_size == 0 {
+            return Err(PredictorConfigError::ZeroTreeChunkSize);
+        }
+        Ok(Self {
+            tree_chunk_size,
+            ..self
+        })
+    }
+
+    pub fn with_engine(self, engine: PredictionEngine) -> Self {
+        Self { engine, ..self }
+    }
+
+    pub fn with_accumulation(self, accumulation: Accumulation) -> Self {
+        Self {
+            accumulation,
+            ..self
+        }
+    }
+
+    pub fn with_parallelism(self, parallelism: Parallelism) -> Self {
+        Self {
+            parallelism,
+            ..self
+        }
+    }
+
+    /// Batches of at most `sequential_threshold` rows run on the calling thread, where
+    /// dispatching to a pool would cost more than it saves.
+    pub fn with_sequential_threshold(self, sequential_threshold: usize) -> Self {
+        Self {
+            sequential_threshold,
+            ..self
+        }
+    }
+
+    pub fn row_chunk_size(&self) -> usize {
+        self.row_chunk_size
+    }
+
+    pub fn tree_chunk_size(&self) -> usize {
+        self.tree_chunk_size
+    }
+
+    pub fn engine(&self) -> PredictionEngine {
+        self.engine
+    }
+
+    pub fn accumulation(&self) -> Accumulation {
+        self.accumulation
+    }
+
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.parallelism
+    }
+
+    pub fn sequential_threshold(&self) -> usize {
+        self.sequential_threshold
+    }
+
+    pub(crate) fn is_sequential(&self, num_rows: usize) -> bool {
+        num_rows <= self.sequential_threshold || matches!(self.parallelism, Parallelism::Sequential)
+    }
+
+    /// Runs `op` so that its rayon calls use the configured pool.
+    pub(crate) fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.parallelism {
+            Parallelism::Pool(pool) => pool.install(op),
+            Parallelism::Global | Parallelism::Sequential => op(),
+        }
+    }
+
+    /// Calls `score_chunk` on consecutive chunks of `row_chunk_size` rows out of `num_rows`,
+    /// in parallel unless the batch is small, and returns the values it appended in row order.
+    pub(crate) fn map_row_chunks<F>(&self, num_rows: usize, score_chunk: F) -> Vec<f32>
+    where
+        F: Fn(&[usize], &mut Vec<f32>) + Sync,
+    {
+        if self.is_sequential(num_rows) {
+            let mut results = Vec::with_capacity(num_rows);
+            let mut row_indices = Vec::with_capacity(self.row_chunk_size);
+            for start in (0..num_rows).step_by(self.row_chunk_size) {
+                row_indices.clear();
+                row_indices.extend(start..(start + self.row_chunk_size).min(num_rows));
+                score_chunk(&row_indices, &mut results);
+            }
+            return results;
+        }
+
+        self.install(|| {
+            (0..num_rows)
+                .into_par_iter()
+                .chunks(self.row_chunk_size)
+                .fold(
+                    || Vec::with_capacity(self.row_chunk_size),
+                    |mut chunk_results, row_indices| {
+                        score_chunk(&row_indices, &mut chunk_results);
+                        chunk_results
+                    },
+                )
+                .reduce(Vec::new, |mut a, mut b| {
+                    a.append(&mut b);
+                    a
+                })
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GradientBoostedDecisionTrees {
+    /// After editing the trees or `required_features` of a model that has already predicted, call
+    /// [`reset_engine_cache`](Self::reset_engine_cache) before predicting again. Clones start
+    /// with an empty cache, so they can be edited freely.
     pub trees: Vec<VecTreeNodes>,
     pub feature_names: Arc<Vec<String>>,
     pub base_score: f32,
     pub feature_types: Arc<Vec<FeatureType>>,
     pub objective: Objective,
+    /// The objective's name in the loaded model file, if any.
+    pub(crate) objective_name: Option<String>,
     pub config: PredictorConfig,
     pub required_features: HashSet<usize>,
+    pub(crate) engine_cache: EngineCache,
 }
 
 //SAFETY: Send + Sync as all fields are Send + Sync
@@ -411,8 +741,10 @@ impl Default for GradientBoostedDecisionTrees {
             feature_types: Arc::new(vec![]),
             base_score: 0.0,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::new(),
+            engine_cache: EngineCache::default(),
         }
     }
 }
@@ -422,14 +754,47 @@ impl GradientBoostedDecisionTrees {
         &self.config
     }
 
+    /// The objective's XGBoost name: the one the model was loaded with, unless `objective` has
+    /// been changed since, so that e.g. `binary:logistic` models keep their name on export.
+    pub fn objective_name(&self) -> &str {
+        self.objective_name
+            .as_deref()
+            .filter(|name| {
+                XGBoostParser::objective_from_name(name).as_ref() == Some(&self.objective)
+            })
+            .unwrap_or(self.objective.name())
+    }
+
     pub fn set_config(&mut self, config: PredictorConfig) {
         self.config = config;
     }
 
+    /// Drops the prediction engines built from the trees, so that they are rebuilt from the
+    /// current `trees` on next use.
+    pub fn reset_engine_cache(&mut self) {
+        self.engine_cache = EngineCache::default();
+    }
+
     pub fn get_required_features(&self) -> &HashSet<usize> {
         &self.required_features
     }
 
+    /// Positions in `feature_names` of the features used by the trees, in the order of the
+    /// compacted feature indices stored in the tree nodes.
+    pub fn original_feature_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.required_features.iter().copied().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Feature names indexed by the compacted feature indices stored in the tree nodes.
+    pub fn tree_feature_names(&self) -> Vec<String> {
+        self.original_feature_indices()
+            .into_iter()
+            .map(|idx| self.feature_names[idx].clone())
+            .collect()
+    }
+
     fn collect_required_features(trees: &[VecTreeNodes]) -> HashSet<usize> {
         let mut required_features = HashSet::new();
 
@@ -455,201 +820,247 @@ impl GradientBoostedDecisionTrees {
     }
 
     pub fn predict_batches(&self, batches: &[RecordBatch]) -> Result<Float32Array, ArrowError> {
+        self.predict_batches_with_config(batches, &self.config)
+    }
+
+    /// [`predict_batches`](Self::predict_batches) with `config` in place of the model's own, e.g.
+    /// to change the thread count of one call without cloning the model.
+    pub fn predict_batches_with_config(
+        &self,
+        batches: &[RecordBatch],
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
         if batches.len() == 1 {
-            let required_columns: Vec<ArrayRef> = batches[0]
-                .columns()
-                .iter()
-                .enumerate()
-                .filter(|(i, _)| self.required_features.contains(i))
-                .map(|(_, col)| col.clone())
-                .collect();
-            return self.predict_arrays(&required_columns);
+            return self.predict_batch(&batches[0], config);
         }
 
         let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
         let mut builder = Float32Builder::with_capacity(total_rows);
 
         for batch in batches {
-            let required_columns: Vec<ArrayRef> = batch
-                .columns()
-                .iter()
-                .enumerate()
-                .filter(|(i, _)| self.required_features.contains(i))
-                .map(|(_, col)| col.clone())
-                .collect();
-
-            let predictions = self.predict_arrays(&required_columns)?;
+            let predictions = self.predict_batch(batch, config)?;
             builder.append_slice(predictions.values());
         }
         Ok(builder.finish())
     }
 
+    fn predict_batch(
+        &self,
+        batch: &RecordBatch,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        let columns = self.required_columns(batch);
+        let features = self.feature_columns(&columns, Some(batch.num_rows()))?;
+        self.predict_with_config(&features, config)
+    }
+
+    /// Columns of `batch` used by the trees, in compacted feature order.
+    pub(crate) fn required_columns(&self, batch: &RecordBatch) -> Vec<ArrayRef> {
+        batch
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.required_features.contains(i))
+            .map(|(_, col)| col.clone())
+            .collect()
+    }
+
+    /// Predicts from one array per feature used by the trees, in the order of
+    /// [`original_feature_indices`](Self::original_feature_indices).
     #[inline]
     pub fn predict_arrays(&self, feature_arrays: &[ArrayRef]) -> Result<Float32Array, ArrowError> {
-        let features = self.extract_features(feature_arrays)?;
-        self.predict_internal(&features)
+        self.predict_arrays_with_config(feature_arrays, &self.config)
     }
 
-    #[inline]
-    fn predict_internal(&self, features: &[Vec<f32>]) -> Result<Float32Array, ArrowError> {
-        let (num_rows, num_features) = (features[0].len(), features.len());
-
-        let predictions: Vec<f32> = (0..num_rows)
-            .into_par_iter()
-            .chunks(self.config.row_chunk_size)
-            .fold(
-                || Vec::with_capacity(self.config.row_chunk_size),
-                |mut chunk_results, row_indices| {
-                    let mut row_features = vec![0.0; num_features];
-                    let mut chunk_scores = vec![self.base_score; row_indices.len()];
-
-                    for tree_chunk in self.trees.chunks(self.config.tree_chunk_size) {
-                        for (chunk_idx, &row_idx) in row_indices.iter().enumerate() {
-                            // Unroll by 8 for better vectorization
-                            let mut j = 0;
-                            while j + 8 <= num_features {
-                                row_features[j] = features[j][row_idx];
-                                row_features[j + 1] = features[j + 1][row_idx];
-                                row_features[j + 2] = features[j + 2][row_idx];
-                                row_features[j + 3] = features[j + 3][row_idx];
-                                row_features[j + 4] = features[j + 4][row_idx];
-                                row_features[j + 5] = features[j + 5][row_idx];
-                                row_features[j + 6] = features[j + 6][row_idx];
-                                row_features[j + 7] = features[j + 7][row_idx];
-                                j += 8;
-                            }
-                            while j < num_features {
-                                row_features[j] = features[j][row_idx];
-                                j += 1;
-                            }
+    /// [`predict_arrays`](Self::predict_arrays) with `config` in place of the model's own.
+    pub fn predict_arrays_with_config(
+        &self,
+        feature_arrays: &[ArrayRef],
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        let features = self.feature_columns(feature_arrays, None)?;
+        self.predict_with_config(&features, config)
+    }
 
-                            let tree_chunk_score: f32 = tree_chunk
-                                .iter()
-                                .map(|tree| tree.predict(&row_features))
-                                .sum();
-                            chunk_scores[chunk_idx] += tree_chunk_score;
-                        }
+    /// Looks up the features used by the trees in `batch` by name, in the order expected by
+    /// [`predict_arrays`](Self::predict_arrays). Other columns are ignored.
+    ///
+    /// Columns the engines read (`Float32`, `Int64` or `Boolean`) are returned as they are,
+    /// whatever the feature's type; others are cast to `Float32`, as the engines read every
+    /// value as `f32`. Casting a `Float64` column to `Int64` for an integer feature would
+    /// truncate fractions and null out values beyond its range. Values that cannot be cast,
+    /// such as unparsable strings, are an error.
+    pub fn feature_arrays_by_name(&self, batch: &RecordBatch) -> Result<Vec<ArrayRef>, ArrowError> {
+        self.original_feature_indices()
+            .into_iter()
+            .map(|index| {
+                let name = &self.feature_names[index];
+                let column = batch.column_by_name(name).ok_or_else(|| {
+                    ArrowError::SchemaError(format!("Missing feature column: {}", name))
+                })?;
+                match column.data_type() {
+                    DataType::Float32 | DataType::Int64 | DataType::Boolean => {
+                        Ok(Arc::clone(column))
                     }
+                    _ => {
+                        let options = CastOptions {
+                            safe: false,
+                            ..Default::default()
+                        };
+                        cast_with_options(column, &DataType::Float32, &options)
+                    }
+                }
+            })
+            .collect()
+    }
 
-                    chunk_results.extend(
-                        chunk_scores
-                            .into_iter()
-                            .map(|score| self.objective.compute_score(score)),
-                    );
-
-                    chunk_results
-                },
-            )
-            .reduce(Vec::new, |mut a, mut b| {
-                a.append(&mut b);
-                a
-            });
+    /// Schema of the batches read by [`predict_batches`](Self::predict_batches): one nullable
+    /// column per feature of the model, named and typed after it.
+    pub fn input_schema(&self) -> Schema {
+        let fields: Vec<Field> = self
+            .feature_names
+            .iter()
+            .zip(self.feature_types.iter())
+            .map(|(name, feature_type)| Field::new(name, arrow_type(feature_type), true))
+            .collect();
+        Schema::new(fields)
+    }
 
-        let mut builder = Float32Builder::with_capacity(predictions.len());
-        builder.append_slice(&predictions);
-        Ok(builder.finish())
+    /// Checks that `feature_arrays` holds one array per required feature, each of `num_rows`
+    /// rows. Without `num_rows`, the length of the first array is used.
+    pub(crate) fn feature_columns<'a>(
+        &self,
+        feature_arrays: &'a [ArrayRef],
+        num_rows: Option<usize>,
+    ) -> Result<FeatureColumns<'a>, ArrowError> {
+        if feature_arrays.len() != self.required_features.len() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Expected {} feature arrays, got {}",
+                self.required_features.len(),
+                feature_arrays.len()
+            )));
+        }
+        let num_rows = num_rows
+            .or_else(|| feature_arrays.first().map(|array| array.len()))
+            .ok_or_else(|| {
+                ArrowError::InvalidArgumentError(
+                    "Cannot infer the number of rows without feature arrays".into(),
+                )
+            })?;
+        FeatureColumns::try_new(feature_arrays, num_rows)
     }
 
     #[inline]
-    fn extract_features(&self, feature_arrays: &[ArrayRef]) -> Result<Vec<Vec<f32>>, ArrowError> {
-        let num_rows = feature_arrays[0].len();
-        let mut feature_values = Vec::with_capacity(feature_arrays.len());
-
-        for array in feature_arrays.iter() {
-            let values = match array.data_type() {
-                DataType::Float32 => {
-                    let array = array
-                        .as_any()
-                        .downcast_ref::<Float32Array>()
-                        .ok_or_else(|| {
-                            ArrowError::InvalidArgumentError("Expected Float32Array".into())
-                        })?;
-
-                    if array.nulls().is_none() {
-                        array.values().to_vec()
-                    } else {
-                        let values_slice = array.values();
-                        let null_bitmap = array.nulls().unwrap();
-
-                        values_slice
-                            .iter()
-                            .enumerate()
-                            .map(|(i, &val)| {
-                                if null_bitmap.is_null(i) {
-                                    f32::NAN
-                                } else {
-                                    val
-                                }
-                            })
-                            .collect()
-                    }
+    pub(crate) fn predict_internal<F: FeatureSource>(
+        &self,
+        features: &F,
+    ) -> Result<Float32Array, ArrowError> {
+        self.predict_with_config(features, &self.config)
+    }
+
+    pub(crate) fn predict_with_config<F: FeatureSource>(
+        &self,
+        features: &F,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        match config.engine {
+            PredictionEngine::Traversal => self.predict_traversal(features, config),
+            PredictionEngine::QuickScorer => self.predict_quickscorer(features, config),
+            PredictionEngine::Simd => self.predict_simd(features, config),
+        }
+    }
+
+    fn predict_simd<F: FeatureSource>(
+        &self,
+        features: &F,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        static CPU_FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+        let level = CPU_FEATURES.get_or_init(CpuFeatures::new).simd_level();
+        let predictor = self.engine_cache.lockstep(&self.trees, level);
+
+        let predictions =
+            config.map_row_chunks(features.num_rows(), |row_indices, chunk_results| {
+                let start = chunk_results.len();
+                predictor.predict_rows(
+                    features,
+                    row_indices,
+                    self.base_score,
+                    config,
+                    chunk_results,
+                );
+                for score in &mut chunk_results[start..] {
+                    *score = self.objective.compute_score(*score);
                 }
-                DataType::Int64 => {
-                    let array = array.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
-                        ArrowError::InvalidArgumentError("Expected Int64Array".into())
-                    })?;
-
-                    if array.nulls().is_none() {
-                        array.values().iter().map(|&x| x as f32).collect()
-                    } else {
-                        let values_slice = array.values();
-                        let null_bitmap = array.nulls().unwrap();
-
-                        values_slice
-                            .iter()
-                            .enumerate()
-                            .map(|(i, &val)| {
-                                if null_bitmap.is_null(i) {
-                                    f32::NAN
-                                } else {
-                                    val as f32
-                                }
-                            })
-                            .collect()
-                    }
+            });
+
+        Ok(Float32Array::from(predictions))
+    }
+
+    fn predict_quickscorer<F: FeatureSource>(
+        &self,
+        features: &F,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        let num_features = features.num_features();
+        let scorer = self.engine_cache.quickscorer(&self.trees);
+
+        let predictions =
+            config.map_row_chunks(features.num_rows(), |row_indices, chunk_results| {
+                let mut rows = vec![0.0; row_indices.len() * num_features];
+                let mut bitvectors = vec![0; scorer.bitvector_len()];
+                features.gather_rows(row_indices, num_features, &mut rows);
+
+                for row_idx in 0..row_indices.len() {
+                    let score = scorer.predict_row(
+                        &rows[row_idx * num_features..(row_idx + 1) * num_features],
+                        self.base_score,
+                        config,
+                        &mut bitvectors,
+                    );
+                    chunk_results.push(self.objective.compute_score(score));
                 }
-                DataType::Boolean => {
-                    let array = array
-                        .as_any()
-                        .downcast_ref::<BooleanArray>()
-                        .ok_or_else(|| {
-                            ArrowError::InvalidArgumentError("Expected BooleanArray".into())
-                        })?;
-
-                    if array.nulls().is_none() {
-                        array
-                            .values()
-                            .iter()
-                            .map(|x| if x { 1.0 } else { 0.0 })
-                            .collect()
-                    } else {
-                        let null_bitmap = array.nulls().unwrap();
-
-                        (0..num_rows)
-                            .map(|i| {
-                                if null_bitmap.is_null(i) {
-                                    f32::NAN
-                                } else if array.value(i) {
-                                    1.0
-                                } else {
-                                    0.0
-                                }
-                            })
-                            .collect()
+            });
+
+        Ok(Float32Array::from(predictions))
+    }
+
+    fn predict_traversal<F: FeatureSource>(
+        &self,
+        features: &F,
+        config: &PredictorConfig,
+    ) -> Result<Float32Array, ArrowError> {
+        let num_features = features.num_features();
+
+        let predictions =
+            config.map_row_chunks(features.num_rows(), |row_indices, chunk_results| {
+                // Rows of the chunk are gathered once and reused for every tree chunk.
+                let mut rows = vec![0.0; row_indices.len() * num_features];
+                features.gather_rows(row_indices, num_features, &mut rows);
+                let mut chunk_scores = vec![self.base_score as f64; row_indices.len()];
+                let accumulation = config.accumulation;
+
+                for tree_chunk in self.trees.chunks(config.tree_chunk_size) {
+                    for (chunk_idx, score) in chunk_scores.iter_mut().enumerate() {
+                        let row_features =
+                            &rows[chunk_idx * num_features..(chunk_idx + 1) * num_features];
+                        *score = accumulation.add_chunk(
+                            *score,
+                            tree_chunk.iter().map(|tree| tree.predict(row_features)),
+                        );
                     }
                 }
-                actual_type => {
-                    return Err(ArrowError::InvalidArgumentError(format!(
-                        "Unsupported data type: {:?}",
-                        actual_type
-                    )));
-                }
-            };
 
-            feature_values.push(values);
-        }
+                chunk_results.extend(
+                    chunk_scores
+                        .into_iter()
+                        .map(|score| self.objective.compute_score(score as f32)),
+                );
+            });
 
-        Ok(feature_values)
+        let mut builder = Float32Builder::with_capacity(predictions.len());
+        builder.append_slice(&predictions);
+        Ok(builder.finish())
     }
 
     pub fn num_trees(&self) -> usize {
@@ -661,12 +1072,22 @@ impl GradientBoostedDecisionTrees {
     }
 
     pub fn prune(&self, predicate: &Predicate) -> Self {
-        let pruned_trees: Vec<VecTreeNodes> = self
+        let original_indices = self.original_feature_indices();
+        let tree_feature_names = self.tree_feature_names();
+
+        let mut pruned_trees: Vec<VecTreeNodes> = self
             .trees
             .iter()
-            .filter_map(|tree| tree.prune(predicate, &self.feature_names))
+            .filter_map(|tree| tree.prune(predicate, &tree_feature_names))
             .collect();
 
+        // Map the compacted indices back to `feature_names` positions so that the
+        // pruned model can be compacted again from scratch.
+        let restore_map: HashMap<usize, usize> = original_indices.into_iter().enumerate().collect();
+        for tree in &mut pruned_trees {
+            tree.update_feature_metadata(&restore_map);
+        }
+
         let required_features = Self::collect_required_features(&pruned_trees);
 
         let mut model = GradientBoostedDecisionTrees {
@@ -675,8 +1096,10 @@ impl GradientBoostedDecisionTrees {
             feature_types: self.feature_types.clone(),
             base_score: self.base_score,
             objective: self.objective.clone(),
+            objective_name: self.objective_name.clone(),
             config: self.config.clone(),
             required_features,
+            engine_cache: EngineCache::default(),
         };
 
         model.update_feature_metadata();
@@ -697,10 +1120,20 @@ impl GradientBoostedDecisionTrees {
             for tree in &mut self.trees {
                 tree.update_feature_metadata(&feature_index_map);
             }
+            self.reset_engine_cache();
         }
     }
 }
 
+/// Arrow type the prediction engines read features of `feature_type` as.
+fn arrow_type(feature_type: &FeatureType) -> DataType {
+    match feature_type {
+        FeatureType::Float => DataType::Float32,
+        FeatureType::Int => DataType::Int64,
+        FeatureType::Indicator => DataType::Boolean,
+    }
+}
+
 impl std::fmt::Display for GradientBoostedDecisionTrees {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let depths = self.tree_depths();
@@ -731,13 +1164,33 @@ impl ModelLoader for GradientBoostedDecisionTrees {
 
     fn json_loads(json: &Value) -> Result<Self, ModelError> {
         let objective_type = XGBoostParser::parse_objective(json)?;
+        let mut model = Self::from_xgboost_trees(json, objective_type, |_| true)?;
+        model.objective_name = Some(XGBoostParser::parse_objective_name(json)?.to_string());
+
+        if let Some(tuning) = read_cached_tuning(json)? {
+            model = tuning.apply(&model);
+        }
+
+        Ok(model)
+    }
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Builds a model from the trees of an XGBoost JSON model whose index satisfies `keep`.
+    pub(crate) fn from_xgboost_trees(
+        json: &Value,
+        objective: Objective,
+        keep: impl Fn(usize) -> bool,
+    ) -> Result<Self, ModelError> {
         let (feature_names, feature_types) = XGBoostParser::parse_feature_metadata(json)?;
         let base_score = XGBoostParser::parse_base_score(json)?;
         let trees_json = XGBoostParser::parse_trees(json)?;
 
         let trees = trees_json
             .iter()
-            .map(|tree_json| {
+            .enumerate()
+            .filter(|(index, _)| keep(*index))
+            .map(|(_, tree_json)| {
                 let arrays = XGBoostParser::parse_tree_arrays(tree_json)?;
 
                 let tree = FeatureTreeBuilder::new()
@@ -746,27 +1199,39 @@ impl ModelLoader for GradientBoostedDecisionTrees {
                     .children(arrays.left_children, arrays.right_children)
                     .base_weights(arrays.base_weights)
                     .default_left(arrays.default_left)
+                    .loss_changes(arrays.loss_changes)
+                    .sum_hessians(arrays.sum_hessian)
                     .build()
                     .map_err(ModelError::from)?;
                 Ok::<VecTreeNodes, ModelError>(tree)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
+        if feature_names.len() != feature_types.len() {
+            return Err(FeatureTreeError::LengthMismatch.into());
+        }
         let required_features = Self::collect_required_features(&trees);
+        if let Some(&index) = required_features
+            .iter()
+            .find(|&&index| index >= feature_names.len())
+        {
+            return Err(FeatureTreeError::InvalidFeatureIndex(index).into());
+        }
 
         let mut model = Self {
             base_score,
             trees,
             feature_names: Arc::new(feature_names),
             feature_types: Arc::new(feature_types),
-            objective: objective_type,
+            objective,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features,
+            engine_cache: EngineCache::default(),
         };
 
         // Update feature indices and metadata
         model.update_feature_metadata();
-
         Ok(model)
     }
 }
@@ -774,7 +1239,7 @@ impl ModelLoader for GradientBoostedDecisionTrees {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::array::Float32Array;
+    use arrow::array::{Array, BooleanArray, Float32Array, Float64Array, Int64Array, StringArray};
     use arrow::datatypes::Field;
     use arrow::datatypes::Schema;
     use std::sync::Arc;
@@ -914,8 +1379,10 @@ mod tests {
             feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Float]),
             base_score: 0.5,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::from([0, 1]),
+            engine_cache: EngineCache::default(),
         };
 
         let batch = create_sample_record_batch();
@@ -1063,6 +1530,55 @@ mod tests {
         assert!(matches!(result, Err(FeatureTreeError::InvalidStructure(_))));
     }
 
+    fn single_split_model_json(
+        split_index: i64,
+        right_child: i64,
+        feature_types: &[&str],
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "learner": {
+                "feature_names": ["f0", "f1"],
+                "feature_types": feature_types,
+                "learner_model_param": { "base_score": "0.5" },
+                "objective": { "name": "reg:squarederror" },
+                "gradient_booster": { "model": { "trees": [{
+                    "split_indices": [split_index, 0, 0],
+                    "split_conditions": [0.5, -1.0, 1.0],
+                    "left_children": [1, -1, -1],
+                    "right_children": [right_child, -1, -1],
+                    "base_weights": [0.0, -1.0, 1.0],
+                    "default_left": [1, 0, 0],
+                    "sum_hessian": [2.0, 1.0, 1.0]
+                }]}}
+            }
+        })
+    }
+
+    #[test]
+    fn test_loader_rejects_invalid_models() {
+        let model =
+            GradientBoostedDecisionTrees::json_loads(&single_split_model_json(1, 2, &["float"; 2]))
+                .unwrap();
+        assert_eq!(model.original_feature_indices(), [1]);
+
+        let err =
+            GradientBoostedDecisionTrees::json_loads(&single_split_model_json(1, 7, &["float"; 2]))
+                .unwrap_err();
+        assert!(err.to_string().contains("Tree construction error"));
+        assert!(matches!(
+            GradientBoostedDecisionTrees::json_loads(&single_split_model_json(2, 2, &["float"; 2])),
+            Err(ModelError::TreeConstruction(
+                FeatureTreeError::InvalidFeatureIndex(2)
+            ))
+        ));
+        assert!(matches!(
+            GradientBoostedDecisionTrees::json_loads(&single_split_model_json(1, 2, &["float"])),
+            Err(ModelError::TreeConstruction(
+                FeatureTreeError::LengthMismatch
+            ))
+        ));
+    }
+
     fn create_mixed_type_record_batch() -> RecordBatch {
         let schema = Schema::new(vec![
             Field::new("f0", DataType::Float32, false), // float feature
@@ -1101,11 +1617,42 @@ mod tests {
                 vec![4, 3, u32::MAX, u32::MAX, 6, u32::MAX, u32::MAX],
             )
             .base_weights(vec![0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 2.0])
-            .default_left(vec![false, false, false, false, false, false, false, false])
+            .default_left(vec![false, false, false, false, false, false, false])
             .build()
             .unwrap()
     }
 
+    #[test]
+    fn test_map_row_chunks_parallelism() {
+        let thread_names = |config: &PredictorConfig, num_rows: usize| {
+            let names = std::sync::Mutex::new(HashSet::new());
+            let results = config.map_row_chunks(num_rows, |row_indices, out| {
+                let name = std::thread::current().name().map(String::from);
+                names.lock().unwrap().insert(name);
+                out.extend(row_indices.iter().map(|&row| row as f32));
+            });
+            assert_eq!(
+                results,
+                (0..num_rows).map(|row| row as f32).collect::<Vec<_>>()
+            );
+            names.into_inner().unwrap()
+        };
+        let caller = HashSet::from([std::thread::current().name().map(String::from)]);
+
+        let pooled = PredictorConfig::default().with_parallelism(Parallelism::threads(2).unwrap());
+        let names = thread_names(&pooled, 1000);
+        assert!(names.iter().all(|name| name
+            .as_deref()
+            .is_some_and(|name| name.starts_with("trusty-predict-"))));
+        // Small batches stay on the calling thread.
+        assert_eq!(thread_names(&pooled, 10), caller);
+
+        let sequential =
+            PredictorConfig::default().with_parallelism(Parallelism::threads(1).unwrap());
+        assert!(matches!(sequential.parallelism, Parallelism::Sequential));
+        assert_eq!(thread_names(&sequential, 1000), caller);
+    }
+
     #[test]
     fn test_predict_arrays_mixed_types() {
         let tree = create_mixed_type_tree();
@@ -1121,8 +1668,10 @@ mod tests {
             ]),
             base_score: 0.0,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::from([0, 1, 2]),
+            engine_cache: EngineCache::default(),
         };
 
         let predictions = gbdt.predict_arrays(batch.columns()).unwrap();
@@ -1178,8 +1727,10 @@ mod tests {
             feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Float]),
             base_score: 0.0,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::from([0, 1]),
+            engine_cache: EngineCache::default(),
         };
 
         let predictions = gbdt.predict_arrays(batch.columns()).unwrap();
@@ -1208,14 +1759,191 @@ mod tests {
             feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Float]),
             base_score: 0.0,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::from([1, 2]),
+            engine_cache: EngineCache::default(),
         };
 
         let result = gbdt.predict_arrays(batch.columns());
         assert!(matches!(result, Err(ArrowError::InvalidArgumentError(_))));
     }
 
+    #[test]
+    fn test_predictor_config_rejects_zero_chunk_sizes() {
+        assert_eq!(
+            PredictorConfig::new(0, 8).unwrap_err(),
+            PredictorConfigError::ZeroRowChunkSize
+        );
+        assert_eq!(
+            PredictorConfig::default()
+                .with_tree_chunk_size(0)
+                .unwrap_err(),
+            PredictorConfigError::ZeroTreeChunkSize
+        );
+        let config = PredictorConfig::new(1, 1).unwrap();
+        assert_eq!((config.row_chunk_size(), config.tree_chunk_size()), (1, 1));
+    }
+
+    #[test]
+    fn test_accumulation_modes() {
+        let outputs = [1e8, 1.0, -1e8, 1.0];
+        let sum = |accumulation: Accumulation, tree_chunk_size: usize| {
+            let score = outputs.chunks(tree_chunk_size).fold(0.0, |score, chunk| {
+                accumulation.add_chunk(score, chunk.iter().copied())
+            });
+            score as f32
+        };
+        // In f32, 1e8 + 1 rounds back to 1e8.
+        assert_eq!(sum(Accumulation::Chunked, 1), 1.0);
+        assert_eq!(sum(Accumulation::Chunked, 2), 0.0);
+        for tree_chunk_size in [1, 2, 3, 4] {
+            assert_eq!(sum(Accumulation::F64, tree_chunk_size), 2.0);
+        }
+        assert_eq!("F64".parse::<Accumulation>(), Ok(Accumulation::F64));
+        assert!("kahan".parse::<Accumulation>().is_err());
+    }
+
+    #[test]
+    fn test_predict_arrays_rejects_malformed_inputs() {
+        let gbdt = GradientBoostedDecisionTrees {
+            trees: vec![create_sample_tree()],
+            feature_names: Arc::new(vec!["f0".to_string(), "f1".to_string()]),
+            feature_types: Arc::new(vec![FeatureType::Float, FeatureType::Float]),
+            base_score: 0.0,
+            objective: Objective::SquaredError,
+            objective_name: None,
+            config: PredictorConfig::default(),
+            required_features: HashSet::from([0, 1]),
+            engine_cache: EngineCache::default(),
+        };
+        let f0: ArrayRef = Arc::new(Float32Array::from(vec![0.1, 0.9]));
+        let short: ArrayRef = Arc::new(Float32Array::from(vec![0.1]));
+
+        let invalid_inputs: [&[ArrayRef]; 3] =
+            [&[], std::slice::from_ref(&f0), &[f0.clone(), short]];
+        for arrays in invalid_inputs {
+            let result = gbdt.predict_arrays(arrays);
+            assert!(
+                matches!(result, Err(ArrowError::InvalidArgumentError(_))),
+                "{} arrays",
+                arrays.len()
+            );
+        }
+        assert_eq!(gbdt.predict_arrays(&[f0.clone(), f0]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_feature_arrays_by_name() {
+        let tree = create_mixed_type_tree();
+        let model = GradientBoostedDecisionTrees {
+            trees: vec![tree],
+            feature_names: Arc::new(vec!["f0".into(), "f1".into(), "f2".into()]),
+            feature_types: Arc::new(vec![
+                FeatureType::Float,
+                FeatureType::Int,
+                FeatureType::Indicator,
+            ]),
+            required_features: HashSet::from([0, 1, 2]),
+            ..Default::default()
+        };
+        let expected = model
+            .predict_batches(&[create_mixed_type_record_batch()])
+            .unwrap();
+
+        // Reordered columns of wider types, plus one the model does not use.
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("f2", DataType::Int64, false),
+                Field::new("extra", DataType::Float64, false),
+                Field::new("f1", DataType::Int64, false),
+                Field::new("f0", DataType::Float64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 0, 1, 0])),
+                Arc::new(Float64Array::from(vec![9.0; 4])),
+                Arc::new(Int64Array::from(vec![100, 50, 75, 25])),
+                Arc::new(Float64Array::from(vec![0.5, 0.3, 0.7, 0.4])),
+            ],
+        )
+        .unwrap();
+        let arrays = model.feature_arrays_by_name(&batch).unwrap();
+        assert_eq!(model.predict_arrays(&arrays).unwrap(), expected);
+        assert!(Arc::ptr_eq(&arrays[1], batch.column(2)));
+
+        // Integer features given as floats keep their fractions and large values.
+        let floats = RecordBatch::try_from_iter([
+            (
+                "f0",
+                Arc::new(Float32Array::from(vec![0.5, 0.5])) as ArrayRef,
+            ),
+            (
+                "f1",
+                Arc::new(Float64Array::from(vec![59.5, 1e20])) as ArrayRef,
+            ),
+            (
+                "f2",
+                Arc::new(BooleanArray::from(vec![true, false])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let arrays = model.feature_arrays_by_name(&floats).unwrap();
+        let f1 = arrays[1].as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(f1.values(), &[59.5, 1e20]);
+        assert_eq!(f1.null_count(), 0);
+
+        let strings = RecordBatch::try_from_iter([
+            ("f0", Arc::new(Float32Array::from(vec![0.5])) as ArrayRef),
+            ("f1", Arc::new(StringArray::from(vec!["many"])) as ArrayRef),
+            ("f2", Arc::new(BooleanArray::from(vec![true])) as ArrayRef),
+        ])
+        .unwrap();
+        assert!(model.feature_arrays_by_name(&strings).is_err());
+
+        let missing = batch.project(&[0, 1, 2]).unwrap();
+        let err = model.feature_arrays_by_name(&missing).unwrap_err();
+        assert!(err.to_string().contains("Missing feature column: f0"));
+
+        let schema = model.input_schema();
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            types,
+            [&DataType::Float32, &DataType::Int64, &DataType::Boolean]
+        );
+        assert_eq!(schema.field(2).name(), "f2");
+    }
+
+    #[test]
+    fn test_predict_batches_without_required_features() {
+        let leaf = FeatureTreeBuilder::new()
+            .split_indices(vec![-1])
+            .split_conditions(vec![0.0])
+            .children(vec![u32::MAX], vec![u32::MAX])
+            .base_weights(vec![1.5])
+            .default_left(vec![false])
+            .build()
+            .unwrap();
+        let gbdt = GradientBoostedDecisionTrees {
+            trees: vec![leaf],
+            feature_names: Arc::new(vec!["f0".to_string()]),
+            base_score: 0.5,
+            ..Default::default()
+        };
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new(
+                "f0",
+                DataType::Float32,
+                false,
+            )])),
+            vec![Arc::new(Float32Array::from(vec![1.0, 2.0, 3.0]))],
+        )
+        .unwrap();
+
+        let predictions = gbdt.predict_batches(&[batch]).unwrap();
+        assert_eq!(predictions.values(), &[2.0, 2.0, 2.0]);
+        assert!(gbdt.predict_arrays(&[]).is_err());
+    }
+
     #[test]
     fn test_prune_with_default_direction_and_nulls() {
         // Create a deeper tree:
@@ -1265,8 +1993,10 @@ mod tests {
             ]),
             base_score: 0.0,
             objective: Objective::SquaredError,
+            objective_name: None,
             config: PredictorConfig::default(),
             required_features: HashSet::from([0]),
+            engine_cache: EngineCache::default(),
         };
 
         let required = gbdt.get_required_features();
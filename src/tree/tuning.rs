@@ -0,0 +1,247 @@
+use super::layout::NodeLayout;
+use super::trees::{Accumulation, GradientBoostedDecisionTrees, PredictionEngine, PredictorConfig};
+use crate::loader::ModelError;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Key in the model's `learner.attributes` under which a tuning result is cached. XGBoost keeps
+/// unknown attributes when it loads and saves a model.
+pub const TUNING_ATTRIBUTE: &str = "trusty_predictor_config";
+
+/// Candidates benchmarked by [`GradientBoostedDecisionTrees::autotune`].
+#[derive(Debug, Clone)]
+pub struct TuningOptions {
+    pub row_chunk_sizes: Vec<usize>,
+    pub tree_chunk_sizes: Vec<usize>,
+    pub engines: Vec<PredictionEngine>,
+    /// `None` keeps the nodes in their current order.
+    pub layouts: Vec<Option<NodeLayout>>,
+    /// Timed runs per candidate; the fastest one counts.
+    pub repetitions: usize,
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        Self {
+            row_chunk_sizes: vec![8, 32, 128, 512],
+            tree_chunk_sizes: vec![8, 32, 128],
+            engines: vec![
+                PredictionEngine::Traversal,
+                PredictionEngine::QuickScorer,
+                PredictionEngine::Simd,
+            ],
+            layouts: vec![None, Some(NodeLayout::default())],
+            repetitions: 3,
+        }
+    }
+}
+
+/// Fastest configuration found by [`GradientBoostedDecisionTrees::autotune`].
+#[derive(Debug, Clone)]
+pub struct TuningResult {
+    pub config: PredictorConfig,
+    pub layout: Option<NodeLayout>,
+    /// Best time to predict the sample batch.
+    pub elapsed: Duration,
+}
+
+impl TuningResult {
+    /// Returns a copy of `model` using the tuned layout, chunk sizes and engine, and the
+    /// accumulation mode the result was tuned with. Threading settings are kept from `model`.
+    pub fn apply(&self, model: &GradientBoostedDecisionTrees) -> GradientBoostedDecisionTrees {
+        let mut tuned = match self.layout {
+            Some(layout) => model.with_layout(layout),
+            None => model.clone(),
+        };
+        tuned.set_config(
+            model
+                .config
+                .clone()
+                .with_row_chunk_size(self.config.row_chunk_size())
+                .and_then(|config| config.with_tree_chunk_size(self.config.tree_chunk_size()))
+                .expect("tuned chunk sizes are valid")
+                .with_engine(self.config.engine())
+                .with_accumulation(self.config.accumulation()),
+        );
+        tuned
+    }
+
+    /// Caches the result in an XGBoost JSON model, so that loading it with
+    /// [`ModelLoader`](crate::loader::ModelLoader) applies the tuned configuration. Threading
+    /// settings depend on the machine and are not cached.
+    pub fn write_to_model_json(&self, model_json: &mut Value) {
+        let cached = CachedTuning {
+            row_chunk_size: self.config.row_chunk_size(),
+            tree_chunk_size: self.config.tree_chunk_size(),
+            engine: self.config.engine().to_string(),
+            accumulation: Some(self.config.accumulation().to_string()),
+            layout: self.layout.map(|layout| CachedLayout {
+                bfs_levels: layout.bfs_levels,
+                block_size: layout.block_size,
+                use_cover: layout.use_cover,
+            }),
+        };
+        let learner = &mut model_json["learner"];
+        if !learner["attributes"].is_object() {
+            learner["attributes"] = Value::Object(Default::default());
+        }
+        learner["attributes"][TUNING_ATTRIBUTE] = Value::String(
+            serde_json::to_string(&cached).expect("tuning result serializes to JSON"),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedTuning {
+    row_chunk_size: usize,
+    tree_chunk_size: usize,
+    engine: String,
+    /// Missing from older cached results, which load with the default accumulation.
+    #[serde(default)]
+    accumulation: Option<String>,
+    layout: Option<CachedLayout>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedLayout {
+    bfs_levels: usize,
+    block_size: usize,
+    use_cover: bool,
+}
+
+/// Reads a result cached by [`TuningResult::write_to_model_json`], if the model has one.
+pub(crate) fn read_cached_tuning(model_json: &Value) -> Result<Option<TuningResult>, ModelError> {
+    let Some(attribute) = model_json["learner"]["attributes"].get(TUNING_ATTRIBUTE) else {
+        return Ok(None);
+    };
+    let attribute = attribute
+        .as_str()
+        .ok_or_else(|| ModelError::InvalidFieldType(TUNING_ATTRIBUTE.to_string()))?;
+    let cached: CachedTuning = serde_json::from_str(attribute)?;
+    let engine = cached
+        .engine
+        .parse::<PredictionEngine>()
+        .map_err(ModelError::InvalidFieldType)?;
+    let accumulation = cached
+        .accumulation
+        .map(|accumulation| accumulation.parse::<Accumulation>())
+        .transpose()
+        .map_err(ModelError::InvalidFieldType)?
+        .unwrap_or_default();
+    let config = PredictorConfig::new(cached.row_chunk_size, cached.tree_chunk_size)
+        .map_err(|e| ModelError::InvalidFieldType(format!("{}: {}", TUNING_ATTRIBUTE, e)))?
+        .with_engine(engine)
+        .with_accumulation(accumulation);
+
+    Ok(Some(TuningResult {
+        config,
+        layout: cached.layout.map(|layout| NodeLayout {
+            bfs_levels: layout.bfs_levels,
+            block_size: layout.block_size,
+            use_cover: layout.use_cover,
+        }),
+        elapsed: Duration::ZERO,
+    }))
+}
+
+impl GradientBoostedDecisionTrees {
+    /// Benchmarks every combination of the candidates in `options` on `sample` and returns the
+    /// fastest. Threading settings and the accumulation mode are kept from the current
+    /// configuration.
+    ///
+    /// With [`Accumulation::Chunked`], the tree chunk size sets the order in which tree outputs
+    /// are summed, so predictions of the tuned model may differ from the current ones in the
+    /// last bits. With [`Accumulation::F64`] they are identical.
+    pub fn autotune(
+        &self,
+        sample: &RecordBatch,
+        options: &TuningOptions,
+    ) -> Result<TuningResult, ArrowError> {
+        if sample.num_rows() == 0 {
+            return Err(ArrowError::InvalidArgumentError(
+                "Autotuning needs a non-empty sample batch".into(),
+            ));
+        }
+        let columns = self.required_columns(sample);
+        let mut best: Option<TuningResult> = None;
+
+        for &layout in &options.layouts {
+            let mut candidate = match layout {
+                Some(layout) => self.with_layout(layout),
+                None => self.clone(),
+            };
+            for &engine in &options.engines {
+                for &row_chunk_size in &options.row_chunk_sizes {
+                    for &tree_chunk_size in &options.tree_chunk_sizes {
+                        let config = self
+                            .config
+                            .clone()
+                            .with_row_chunk_size(row_chunk_size)
+                            .and_then(|config| config.with_tree_chunk_size(tree_chunk_size))
+                            .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?
+                            .with_engine(engine);
+                        candidate.set_config(config);
+                        let mut elapsed = Duration::MAX;
+                        for _ in 0..options.repetitions.max(1) {
+                            let start = Instant::now();
+                            candidate.predict_arrays(&columns)?;
+                            elapsed = elapsed.min(start.elapsed());
+                        }
+                        if best.as_ref().is_none_or(|best| elapsed < best.elapsed) {
+                            best = Some(TuningResult {
+                                config: candidate.config.clone(),
+                                layout,
+                                elapsed,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        best.ok_or_else(|| ArrowError::InvalidArgumentError("No tuning candidates given".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cached_tuning_round_trip() {
+        let result = TuningResult {
+            config: PredictorConfig::new(128, 32)
+                .unwrap()
+                .with_engine(PredictionEngine::Simd),
+            layout: Some(NodeLayout::breadth_first()),
+            elapsed: Duration::from_millis(1),
+        };
+        let mut model_json = json!({ "learner": { "attributes": { "best_iteration": "9" } } });
+        result.write_to_model_json(&mut model_json);
+        assert_eq!(model_json["learner"]["attributes"]["best_iteration"], "9");
+
+        let cached = read_cached_tuning(&model_json).unwrap().unwrap();
+        assert_eq!(cached.config.row_chunk_size(), 128);
+        assert_eq!(cached.config.tree_chunk_size(), 32);
+        assert_eq!(cached.config.engine(), PredictionEngine::Simd);
+        assert_eq!(cached.config.accumulation(), Accumulation::Chunked);
+        assert_eq!(cached.layout, Some(NodeLayout::breadth_first()));
+
+        let mut f64_result = result.clone();
+        f64_result.config = result.config.clone().with_accumulation(Accumulation::F64);
+        f64_result.write_to_model_json(&mut model_json);
+        let cached = read_cached_tuning(&model_json).unwrap().unwrap();
+        assert_eq!(cached.config.accumulation(), Accumulation::F64);
+
+        assert!(read_cached_tuning(&json!({ "learner": {} }))
+            .unwrap()
+            .is_none());
+        model_json["learner"]["attributes"][TUNING_ATTRIBUTE] =
+            json!(r#"{"row_chunk_size":0,"tree_chunk_size":1,"engine":"simd","layout":null}"#);
+        assert!(read_cached_tuning(&model_json).is_err());
+    }
+}
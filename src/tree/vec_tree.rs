@@ -210,13 +210,27 @@ impl Traversable for TreeNode {
     }
 }
 
+/// Training statistics XGBoost records for a node.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct NodeStats {
+    /// Loss reduction of the split (`loss_changes`).
+    pub gain: f32,
+    /// Sum of the hessians of the training rows reaching the node (`sum_hessian`).
+    pub cover: f32,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct VecTree<N: Traversable> {
     pub nodes: Vec<N>,
+    /// Per-node statistics, parallel to `nodes`. Empty when the tree was built without them.
+    pub stats: Vec<NodeStats>,
 }
 impl<N: Traversable> VecTree<N> {
     pub fn new() -> Self {
-        VecTree { nodes: Vec::new() }
+        VecTree {
+            nodes: Vec::new(),
+            stats: Vec::new(),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -235,6 +249,10 @@ impl<N: Traversable> VecTree<N> {
         self.nodes.get_mut(index)
     }
 
+    pub fn get_stats(&self, index: usize) -> Option<&NodeStats> {
+        self.stats.get(index)
+    }
+
     pub fn get_left_child(&self, node: &N) -> Option<&N> {
         if node.left() == 0 {
             None
@@ -0,0 +1,246 @@
+/*
+ * Exercises the C ABI declared in include/trusty.h. Run by tests/capi_test.rs, which compiles
+ * it against the trusty cdylib:
+ *
+ *   capi_test MODEL_JSON
+ *
+ * Scores NUM_ROWS rows with every feature set to feature_value(feature, row) and prints one
+ * prediction per line.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "trusty.h"
+
+#define NUM_ROWS 8
+
+#define CHECK(cond)                                                                 \
+  do {                                                                              \
+    if (!(cond)) {                                                                  \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond);      \
+      exit(1);                                                                      \
+    }                                                                               \
+  } while (0)
+
+#define CHECK_OK(call)                                                              \
+  do {                                                                              \
+    TrustyStatus status_ = (call);                                                  \
+    if (status_ != TRUSTY_STATUS_OK) {                                              \
+      fprintf(stderr, "%s:%d: %s returned %d: %s\n", __FILE__, __LINE__, #call,     \
+              (int)status_, trusty_last_error());                                   \
+      exit(1);                                                                      \
+    }                                                                               \
+  } while (0)
+
+static float feature_value(size_t feature, size_t row) {
+  return (float)((row * 7 + feature * 3) % 11) * 0.25f;
+}
+
+static char *copy_string(const char *s) {
+  char *copy = malloc(strlen(s) + 1);
+  strcpy(copy, s);
+  return copy;
+}
+
+static void release_column_schema(struct ArrowSchema *schema) {
+  free((char *)schema->name);
+  schema->release = NULL;
+}
+
+static void release_batch_schema(struct ArrowSchema *schema) {
+  for (int64_t i = 0; i < schema->n_children; i++) {
+    struct ArrowSchema *child = schema->children[i];
+    if (child->release != NULL) {
+      child->release(child);
+    }
+    free(child);
+  }
+  free(schema->children);
+  schema->release = NULL;
+}
+
+static void release_column(struct ArrowArray *array) {
+  free((void *)array->buffers[1]);
+  free(array->buffers);
+  array->release = NULL;
+}
+
+static void release_batch(struct ArrowArray *array) {
+  for (int64_t i = 0; i < array->n_children; i++) {
+    struct ArrowArray *child = array->children[i];
+    if (child->release != NULL) {
+      child->release(child);
+    }
+    free(child);
+  }
+  free(array->children);
+  free(array->buffers);
+  array->release = NULL;
+}
+
+/* Exports `num_columns` float32 columns named `names`, holding features `first_feature`
+ * onwards, as a struct array. */
+static void export_batch(char **names, size_t num_columns, size_t first_feature,
+                         struct ArrowArray *array, struct ArrowSchema *schema) {
+  *schema = (struct ArrowSchema){
+      .format = "+s",
+      .name = "",
+      .n_children = (int64_t)num_columns,
+      .children = calloc(num_columns + 1, sizeof(struct ArrowSchema *)),
+      .release = release_batch_schema,
+  };
+  *array = (struct ArrowArray){
+      .length = NUM_ROWS,
+      .n_buffers = 1,
+      .n_children = (int64_t)num_columns,
+      .buffers = calloc(1, sizeof(void *)),
+      .children = calloc(num_columns + 1, sizeof(struct ArrowArray *)),
+      .release = release_batch,
+  };
+
+  for (size_t i = 0; i < num_columns; i++) {
+    struct ArrowSchema *child_schema = malloc(sizeof(struct ArrowSchema));
+    *child_schema = (struct ArrowSchema){
+        .format = "f",
+        .name = copy_string(names[i]),
+        .flags = ARROW_FLAG_NULLABLE,
+        .release = release_column_schema,
+    };
+    schema->children[i] = child_schema;
+
+    float *values = malloc(NUM_ROWS * sizeof(float));
+    for (size_t row = 0; row < NUM_ROWS; row++) {
+      values[row] = feature_value(first_feature + i, row);
+    }
+    const void **buffers = malloc(2 * sizeof(void *));
+    buffers[0] = NULL;
+    buffers[1] = values;
+    struct ArrowArray *child = malloc(sizeof(struct ArrowArray));
+    *child = (struct ArrowArray){
+        .length = NUM_ROWS,
+        .n_buffers = 2,
+        .buffers = buffers,
+        .release = release_column,
+    };
+    array->children[i] = child;
+  }
+}
+
+static TrustyStatus predict(const TrustyModel *model, char **names, size_t num_features,
+                            float *predictions) {
+  struct ArrowArray array;
+  struct ArrowSchema schema;
+  export_batch(names, num_features, 0, &array, &schema);
+
+  struct ArrowArray out_array;
+  struct ArrowSchema out_schema;
+  TrustyStatus status = trusty_model_predict(model, &array, &schema, &out_array, &out_schema);
+  CHECK(array.release == NULL);
+  schema.release(&schema);
+  if (status != TRUSTY_STATUS_OK) {
+    return status;
+  }
+
+  CHECK(strcmp(out_schema.format, "f") == 0);
+  CHECK(out_array.length == NUM_ROWS);
+  CHECK(out_array.null_count == 0);
+  const float *values = out_array.buffers[1];
+  memcpy(predictions, values + out_array.offset, NUM_ROWS * sizeof(float));
+  out_array.release(&out_array);
+  out_schema.release(&out_schema);
+  return status;
+}
+
+static uint8_t *read_file(const char *path, size_t *len) {
+  FILE *file = fopen(path, "rb");
+  CHECK(file != NULL);
+  CHECK(fseek(file, 0, SEEK_END) == 0);
+  *len = (size_t)ftell(file);
+  rewind(file);
+  uint8_t *data = malloc(*len);
+  CHECK(fread(data, 1, *len, file) == *len);
+  fclose(file);
+  return data;
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s MODEL_JSON\n", argv[0]);
+    return 2;
+  }
+  const char *path = argv[1];
+
+  TrustyModel *model = NULL;
+  CHECK(trusty_model_load("missing.json", &model) == TRUSTY_STATUS_IO);
+  CHECK(trusty_last_error() != NULL);
+  CHECK(model == NULL);
+  CHECK_OK(trusty_model_load(path, &model));
+  CHECK(trusty_last_error() == NULL);
+
+  size_t len;
+  uint8_t *data = read_file(path, &len);
+  TrustyModel *from_bytes = NULL;
+  CHECK_OK(trusty_model_load_bytes(data, len, &from_bytes));
+  free(data);
+  TrustyModel *invalid = NULL;
+  CHECK(trusty_model_load_bytes((const uint8_t *)"{}", 2, &invalid) ==
+        TRUSTY_STATUS_INVALID_MODEL);
+  CHECK(invalid == NULL);
+  CHECK(trusty_model_num_trees(model) > 0);
+  CHECK(trusty_model_num_trees(model) == trusty_model_num_trees(from_bytes));
+  CHECK(trusty_model_num_nodes(model) == trusty_model_num_nodes(from_bytes));
+
+  size_t num_features = trusty_model_num_features(model);
+  CHECK(num_features > 1);
+  char **names = malloc(num_features * sizeof(char *));
+  for (size_t i = 0; i < num_features; i++) {
+    size_t name_len = trusty_model_feature_name(model, i, NULL, 0);
+    names[i] = malloc(name_len + 1);
+    CHECK(trusty_model_feature_name(model, i, names[i], name_len + 1) == name_len);
+    CHECK(strlen(names[i]) == name_len);
+  }
+
+  float predictions[NUM_ROWS];
+  float from_bytes_predictions[NUM_ROWS];
+  CHECK_OK(predict(model, names, num_features, predictions));
+  CHECK_OK(predict(from_bytes, names, num_features, from_bytes_predictions));
+  CHECK(memcmp(predictions, from_bytes_predictions, sizeof(predictions)) == 0);
+
+  /* Without the first feature's column. */
+  struct ArrowArray array;
+  struct ArrowSchema schema;
+  export_batch(names + 1, num_features - 1, 1, &array, &schema);
+  struct ArrowArray out_array;
+  struct ArrowSchema out_schema;
+  CHECK(trusty_model_predict(model, &array, &schema, &out_array, &out_schema) ==
+        TRUSTY_STATUS_ARROW);
+  CHECK(array.release == NULL);
+  CHECK(strstr(trusty_last_error(), "Missing feature column") != NULL);
+  schema.release(&schema);
+
+  TrustyCondition conditions[] = {{names[0], TRUSTY_CONDITION_KIND_LESS_THAN, 0.3}};
+  TrustyModel *pruned = NULL;
+  CHECK_OK(trusty_model_prune(model, conditions, 1, &pruned));
+  CHECK(trusty_model_num_nodes(pruned) < trusty_model_num_nodes(model));
+  TrustyCondition unknown[] = {{"no such feature", TRUSTY_CONDITION_KIND_GREATER_THAN_OR_EQUAL, 1.0}};
+  CHECK(trusty_model_prune(model, unknown, 1, &invalid) == TRUSTY_STATUS_INVALID_ARGUMENT);
+  CHECK(invalid == NULL);
+  TrustyCondition bad_kind[] = {{names[0], 7, 1.0}};
+  CHECK(trusty_model_prune(model, bad_kind, 1, &invalid) == TRUSTY_STATUS_INVALID_ARGUMENT);
+  CHECK(invalid == NULL);
+
+  for (size_t row = 0; row < NUM_ROWS; row++) {
+    printf("%.9g\n", predictions[row]);
+  }
+
+  for (size_t i = 0; i < num_features; i++) {
+    free(names[i]);
+  }
+  free(names);
+  trusty_model_free(pruned);
+  trusty_model_free(from_bytes);
+  trusty_model_free(model);
+  trusty_model_free(NULL);
+  return 0;
+}
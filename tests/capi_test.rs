@@ -0,0 +1,76 @@
+#![cfg(all(feature = "capi", unix))]
+use arrow::array::{ArrayRef, Float32Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use trusty::{GradientBoostedDecisionTrees, ModelLoader};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+    const NUM_ROWS: usize = 8;
+
+    /// Same values as `feature_value` in `tests/capi/capi_test.c`.
+    fn feature_value(feature: usize, row: usize) -> f32 {
+        ((row * 7 + feature * 3) % 11) as f32 * 0.25
+    }
+
+    /// Cargo builds the cdylib into the `deps` directory next to this test's executable.
+    fn library_dir() -> PathBuf {
+        let exe = std::env::current_exe().unwrap();
+        exe.parent().unwrap().to_path_buf()
+    }
+
+    #[test]
+    fn test_c_program() -> Result<(), Box<dyn Error>> {
+        let lib_dir = library_dir();
+        let program = std::env::temp_dir().join(format!("trusty-capi-{}", std::process::id()));
+        let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = Command::new(compiler)
+            .args(["-std=c11", "-Wall", "-Werror", "-Iinclude"])
+            .arg("tests/capi/capi_test.c")
+            .arg("-o")
+            .arg(&program)
+            .arg(format!("-L{}", lib_dir.display()))
+            .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+            .arg("-ltrusty")
+            .status()?;
+        assert!(status.success(), "failed to compile tests/capi/capi_test.c");
+
+        let output = Command::new(&program).arg(MODEL).output()?;
+        std::fs::remove_file(&program)?;
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let predictions = String::from_utf8(output.stdout)?
+            .lines()
+            .map(|line| line.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let model = GradientBoostedDecisionTrees::json_load(MODEL)?;
+        let fields: Vec<Field> = model
+            .feature_names
+            .iter()
+            .map(|name| Field::new(name, DataType::Float32, true))
+            .collect();
+        let columns: Vec<ArrayRef> = (0..fields.len())
+            .map(|feature| {
+                let values: Float32Array = (0..NUM_ROWS)
+                    .map(|row| Some(feature_value(feature, row)))
+                    .collect();
+                Arc::new(values) as ArrayRef
+            })
+            .collect();
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+        let expected = model.predict_arrays(&model.feature_arrays_by_name(&batch)?)?;
+        assert_eq!(predictions, expected.values().to_vec());
+        Ok(())
+    }
+}
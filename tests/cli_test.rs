@@ -0,0 +1,236 @@
+#![cfg(feature = "cli")]
+pub mod common;
+use arrow::array::{Array, Float32Array};
+use arrow::ipc::reader::FileReader;
+use arrow::record_batch::RecordBatch;
+use common::{DatasetType, ModelTester};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::error::Error;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use trusty::loader::ModelLoader;
+use trusty::{Condition, GradientBoostedDecisionTrees, Predicate};
+
+const DIAMONDS_MODEL: &str = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+const DIAMONDS_DATA: &str =
+    "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trusty(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_trusty"))
+            .args(args)
+            .output()
+            .expect("failed to run the trusty binary")
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("trusty-cli-{}-{}", std::process::id(), name))
+    }
+
+    fn read_column(batches: &[RecordBatch], name: &str) -> Vec<f32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column_by_name(name).unwrap();
+                let values = column.as_any().downcast_ref::<Float32Array>().unwrap();
+                values.values().to_vec()
+            })
+            .collect()
+    }
+
+    fn expected_predictions(
+        model: &GradientBoostedDecisionTrees,
+        data_path: &str,
+    ) -> Result<Vec<f32>, Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let (batches, _) = tester.load_dataset(data_path, 1024, DatasetType::Diamonds)?;
+        Ok(model.predict_batches(&batches)?.values().to_vec())
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() <= 1e-5 * e.abs().max(1.0), "{} != {}", a, e);
+        }
+    }
+
+    #[test]
+    fn test_predict_parquet_and_ipc() -> Result<(), Box<dyn Error>> {
+        let model = GradientBoostedDecisionTrees::json_load(DIAMONDS_MODEL)?;
+        let expected = expected_predictions(&model, DIAMONDS_DATA)?;
+
+        let parquet_path = temp_path("predictions.parquet");
+        let output = trusty(&[
+            "predict",
+            DIAMONDS_MODEL,
+            DIAMONDS_DATA,
+            parquet_path.to_str().unwrap(),
+            "--threads",
+            "2",
+            "--row-chunk-size",
+            "32",
+            "--tree-chunk-size",
+            "4",
+            "--batch-size",
+            "100",
+        ]);
+        assert!(output.status.success(), "{:?}", output);
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&parquet_path)?)?
+            .build()?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batches[0].num_columns(), 1);
+        assert_close(&read_column(&batches, "prediction"), &expected);
+
+        // Predictions of the parquet file, appended to its columns.
+        let ipc_path = temp_path("predictions.arrow");
+        let output = trusty(&[
+            "predict",
+            DIAMONDS_MODEL,
+            DIAMONDS_DATA,
+            ipc_path.to_str().unwrap(),
+            "--append",
+            "--column",
+            "score",
+        ]);
+        assert!(output.status.success(), "{:?}", output);
+        let batches =
+            FileReader::try_new(File::open(&ipc_path)?, None)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batches[0].num_columns(), 26);
+        assert_close(&read_column(&batches, "score"), &expected);
+
+        std::fs::remove_file(parquet_path)?;
+        std::fs::remove_file(ipc_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_margin() -> Result<(), Box<dyn Error>> {
+        let model_path = "tests/models/binary_logistic/diamonds_model_trees_100_mixed.json";
+        let data_path = "tests/data/binary_logistic/diamonds_data_filtered_trees_100_mixed.csv";
+        let model = GradientBoostedDecisionTrees::json_load(model_path)?;
+        let expected = expected_predictions(&model, data_path)?;
+
+        let csv_path = temp_path("margins.csv");
+        let output = trusty(&[
+            "predict",
+            model_path,
+            data_path,
+            csv_path.to_str().unwrap(),
+            "--margin",
+        ]);
+        assert!(output.status.success(), "{:?}", output);
+        let margins: Vec<f32> = std::fs::read_to_string(&csv_path)?
+            .lines()
+            .skip(1)
+            .map(|line| line.parse().unwrap())
+            .collect();
+        let probabilities: Vec<f32> = margins
+            .iter()
+            .map(|margin| 1.0 / (1.0 + (-margin).exp()))
+            .collect();
+        assert_close(&probabilities, &expected);
+
+        std::fs::remove_file(csv_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_missing_feature() {
+        let output = trusty(&[
+            "predict",
+            DIAMONDS_MODEL,
+            "tests/data/reg_squarederror/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+            temp_path("missing.csv").to_str().unwrap(),
+        ]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Missing feature column: carat"),
+            "{}",
+            stderr
+        );
+    }
+
+    #[test]
+    fn test_inspect() {
+        let output = trusty(&["inspect", DIAMONDS_MODEL, "--importance", "weight"]);
+        assert!(output.status.success(), "{:?}", output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Total number of trees: 100"));
+        assert!(stdout.contains("\n  99: depth "));
+        assert!(stdout.contains("Feature importance (weight):\n  carat: "));
+
+        let output = trusty(&["inspect", DIAMONDS_MODEL, "--trees"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("split_0: carat"));
+        assert!(stdout.contains("VecTree:"));
+    }
+
+    #[test]
+    fn test_prune() -> Result<(), Box<dyn Error>> {
+        let pruned_path = temp_path("pruned.json");
+        let output = trusty(&[
+            "prune",
+            DIAMONDS_MODEL,
+            pruned_path.to_str().unwrap(),
+            "--predicate",
+            "carat<0.2",
+            "-p",
+            "depth >= 61",
+        ]);
+        assert!(output.status.success(), "{:?}", output);
+
+        let model = GradientBoostedDecisionTrees::json_load(DIAMONDS_MODEL)?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition("carat".to_string(), Condition::LessThan(0.2));
+        predicate.add_condition("depth".to_string(), Condition::GreaterThanOrEqual(61.0));
+        let expected = model.prune(&predicate);
+        let pruned = GradientBoostedDecisionTrees::json_load(pruned_path.to_str().unwrap())?;
+        assert_eq!(pruned.num_trees(), expected.num_trees());
+        assert_eq!(
+            expected_predictions(&pruned, DIAMONDS_DATA)?,
+            expected_predictions(&expected, DIAMONDS_DATA)?
+        );
+
+        for invalid in ["carat<=0.2", "carat>0.2", "carat<big", "price<1"] {
+            let output = trusty(&[
+                "prune",
+                DIAMONDS_MODEL,
+                pruned_path.to_str().unwrap(),
+                "-p",
+                invalid,
+            ]);
+            assert!(!output.status.success(), "{} was accepted", invalid);
+        }
+
+        std::fs::remove_file(pruned_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), Box<dyn Error>> {
+        let output = trusty(&["validate", DIAMONDS_MODEL]);
+        assert!(output.status.success(), "{:?}", output);
+        assert!(String::from_utf8_lossy(&output.stdout).contains(": ok (100 trees"));
+
+        // A child index past the end of the tree.
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(DIAMONDS_MODEL)?)?;
+        json["learner"]["gradient_booster"]["model"]["trees"][3]["left_children"][0] =
+            100_000.into();
+        let invalid_path = temp_path("invalid.json");
+        std::fs::write(&invalid_path, json.to_string())?;
+
+        let output = trusty(&["validate", DIAMONDS_MODEL, invalid_path.to_str().unwrap()]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("out of bounds"));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 2 models are invalid"));
+
+        std::fs::remove_file(invalid_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+pub mod common;
+use common::ModelTester;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use trusty::{Condition, GradientBoostedDecisionTrees, Predicate};
+
+const RUST_HARNESS: &str = r#"
+mod model;
+use std::io::Read;
+
+fn main() {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).unwrap();
+    for line in input.lines() {
+        let row: Vec<f32> = line.split_whitespace().map(|v| v.parse().unwrap()).collect();
+        println!("{:?}", model::predict(&row));
+    }
+}
+"#;
+
+const C_HARNESS: &str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+
+float predict(const float* features);
+
+int main(int argc, char** argv) {
+    int num_features = atoi(argv[1]);
+    float* row = malloc(sizeof(float) * num_features);
+    char token[64];
+    while (scanf("%63s", token) == 1) {
+        row[0] = strtof(token, NULL);
+        for (int i = 1; i < num_features; i++) {
+            if (scanf("%63s", token) != 1) return 1;
+            row[i] = strtof(token, NULL);
+        }
+        printf("%.9g\n", predict(row));
+    }
+    free(row);
+    return 0;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn work_dir(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "trusty_codegen_{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    fn run(mut command: Command, stdin: &str) -> Result<Vec<f32>, Box<dyn Error>> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or("stdin not captured")?
+            .write_all(stdin.as_bytes())?;
+        let output = child.wait_with_output()?;
+        assert!(output.status.success(), "generated program failed");
+        String::from_utf8(output.stdout)?
+            .lines()
+            .map(|line| line.parse::<f32>().map_err(|e| e.into()))
+            .collect()
+    }
+
+    fn compile(command: &mut Command, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let status = command.current_dir(dir).status()?;
+        assert!(status.success(), "failed to compile generated source");
+        Ok(())
+    }
+
+    fn predict_with_rust_source(
+        model: &GradientBoostedDecisionTrees,
+        input: &str,
+    ) -> Result<Vec<f32>, Box<dyn Error>> {
+        let dir = work_dir("rust")?;
+        fs::write(dir.join("model.rs"), model.to_rust_source())?;
+        fs::write(dir.join("main.rs"), RUST_HARNESS)?;
+
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        compile(
+            Command::new(rustc).args(["--edition", "2021", "main.rs", "-o", "predict"]),
+            &dir,
+        )?;
+        let predictions = run(Command::new(dir.join("predict")), input);
+        fs::remove_dir_all(&dir)?;
+        predictions
+    }
+
+    fn predict_with_c_source(
+        model: &GradientBoostedDecisionTrees,
+        input: &str,
+    ) -> Result<Vec<f32>, Box<dyn Error>> {
+        let dir = work_dir("c")?;
+        fs::write(dir.join("model.c"), model.to_c_source())?;
+        fs::write(dir.join("main.c"), C_HARNESS)?;
+
+        let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        compile(
+            Command::new(cc).args(["-O1", "model.c", "main.c", "-o", "predict", "-lm"]),
+            &dir,
+        )?;
+        let mut command = Command::new(dir.join("predict"));
+        command.arg(model.feature_names.len().to_string());
+        let predictions = run(command, input);
+        fs::remove_dir_all(&dir)?;
+        predictions
+    }
+
+    /// Draws rows around the model's split thresholds, including exact hits and NaNs.
+    fn random_rows(model: &GradientBoostedDecisionTrees, num_rows: usize) -> Vec<Vec<f32>> {
+        let original_indices = model.original_feature_indices();
+        let mut thresholds = vec![Vec::new(); model.feature_names.len()];
+        for tree in &model.trees {
+            for node in tree.nodes.iter().filter(|node| !node.value.is_leaf()) {
+                let feature = original_indices[node.value.feature_index() as usize];
+                thresholds[feature].push(node.value.split_value());
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..num_rows)
+            .map(|_| {
+                thresholds
+                    .iter()
+                    .map(|candidates| {
+                        if candidates.is_empty() || rng.gen_bool(0.1) {
+                            return f32::NAN;
+                        }
+                        let threshold = candidates[rng.gen_range(0..candidates.len())];
+                        if rng.gen_bool(0.2) {
+                            threshold
+                        } else {
+                            threshold + rng.gen_range(-1.0..1.0) * threshold.abs().max(1.0)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn reference_predictions(model: &GradientBoostedDecisionTrees, rows: &[Vec<f32>]) -> Vec<f32> {
+        let original_indices = model.original_feature_indices();
+        rows.iter()
+            .map(|row| {
+                let compact_row: Vec<f32> = original_indices.iter().map(|&i| row[i]).collect();
+                let score = model.trees.iter().fold(model.base_score, |score, tree| {
+                    score + tree.predict(&compact_row)
+                });
+                model.objective.compute_score(score)
+            })
+            .collect()
+    }
+
+    fn assert_generated_sources_match(
+        model: &GradientBoostedDecisionTrees,
+    ) -> Result<(), Box<dyn Error>> {
+        let rows = random_rows(model, 500);
+        let input: String = rows
+            .iter()
+            .map(|row| {
+                let values: Vec<String> = row.iter().map(|v| format!("{:?}", v)).collect();
+                values.join(" ") + "\n"
+            })
+            .collect();
+        let expected = reference_predictions(model, &rows);
+
+        for predictions in [
+            predict_with_rust_source(model, &input)?,
+            predict_with_c_source(model, &input)?,
+        ] {
+            assert_eq!(predictions.len(), expected.len());
+            for (i, (&actual, &expected)) in predictions.iter().zip(&expected).enumerate() {
+                assert!(
+                    (actual - expected).abs() <= 1e-6 * expected.abs().max(1.0),
+                    "Row {}: generated={}, reference={}",
+                    i,
+                    actual,
+                    expected
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_source_diamonds_logistic() -> Result<(), Box<dyn Error>> {
+        let model = ModelTester::new(1e-6)
+            .load_model("tests/models/binary_logistic/diamonds_model_trees_100_mixed.json")?;
+        assert_generated_sources_match(&model)
+    }
+
+    #[test]
+    fn test_generated_source_pruned_airline() -> Result<(), Box<dyn Error>> {
+        let model = ModelTester::new(1e-6).load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition("age".to_string(), Condition::LessThan(40.0));
+
+        assert_generated_sources_match(&model)?;
+        assert_generated_sources_match(&model.prune(&predicate))
+    }
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "datafusion")]
+pub mod common;
+use arrow::array::{Array, Float32Array};
+use common::{DatasetType, ModelTester};
+use datafusion::prelude::SessionContext;
+use std::error::Error;
+use trusty::datafusion::register_model_dir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registered_models_match_predict_batches() -> Result<(), Box<dyn Error>> {
+        let ctx = SessionContext::new();
+        let names = register_model_dir(&ctx, "tests/models/reg_squarederror")?;
+        assert_eq!(
+            names,
+            [
+                "airline_satisfaction_model_trees_100_mixed",
+                "diamonds_model_trees_100_mixed"
+            ]
+        );
+
+        let tester = ModelTester::new(0.0);
+        let model = tester
+            .load_model("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Diamonds,
+        )?;
+        ctx.register_batch("diamonds", batches[0].clone())?;
+
+        let args: Vec<String> = model
+            .original_feature_indices()
+            .into_iter()
+            .map(|index| format!("\"{}\"", model.feature_names[index]))
+            .collect();
+        let sql = format!(
+            "SELECT diamonds_model_trees_100_mixed({}) FROM diamonds",
+            args.join(", ")
+        );
+        let results = ctx.sql(&sql).await?.collect().await?;
+        let actual: Vec<f32> = results
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0);
+                let predictions = column.as_any().downcast_ref::<Float32Array>().unwrap();
+                predictions.values().to_vec()
+            })
+            .collect();
+
+        let expected = model.predict_batches(&batches[..1])?;
+        assert_eq!(actual, expected.values().to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+pub mod common;
+use common::{DatasetType, ModelTester};
+use std::error::Error;
+use trusty::loader::ModelLoader;
+use trusty::tree::{NodeLayout, TuningOptions};
+use trusty::{
+    Accumulation, Condition, GradientBoostedDecisionTrees, Parallelism, Predicate,
+    PredictionEngine, PredictorConfig,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECTIVES: [&str; 3] = ["reg_squarederror", "reg_logistic", "binary_logistic"];
+    const ENGINES: [PredictionEngine; 2] = [PredictionEngine::QuickScorer, PredictionEngine::Simd];
+
+    fn assert_engines_match(
+        model: &GradientBoostedDecisionTrees,
+        data_path: &str,
+        dataset_type: DatasetType,
+    ) -> Result<(), Box<dyn Error>> {
+        let (batches, _) = ModelTester::new(0.0).load_dataset(data_path, 1024, dataset_type)?;
+
+        for tree_chunk_size in [1, 8, 64] {
+            let mut traversal = model.clone();
+            traversal.set_config(PredictorConfig::default().with_tree_chunk_size(tree_chunk_size)?);
+            for engine in ENGINES {
+                let mut candidate = model.clone();
+                // Uneven row chunks leave partially filled SIMD lane groups.
+                let config = PredictorConfig::new(21, tree_chunk_size)?.with_engine(engine);
+                candidate.set_config(config.clone());
+
+                for batch in &batches {
+                    let expected = traversal.predict_batches(std::slice::from_ref(batch))?;
+                    let actual = candidate.predict_batches(std::slice::from_ref(batch))?;
+                    assert_eq!(
+                        actual, expected,
+                        "{} with {} trees per chunk",
+                        engine, tree_chunk_size
+                    );
+                    let per_call =
+                        model.predict_batches_with_config(std::slice::from_ref(batch), &config)?;
+                    assert_eq!(per_call, actual);
+                }
+            }
+
+            let mut quantized = model.quantize()?;
+            quantized.set_config(PredictorConfig::new(21, tree_chunk_size)?);
+            for batch in &batches {
+                let expected = traversal.predict_batches(std::slice::from_ref(batch))?;
+                let actual = quantized.predict_batches(std::slice::from_ref(batch))?;
+                assert_eq!(
+                    actual, expected,
+                    "quantized with {} trees per chunk",
+                    tree_chunk_size
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_engines_match_traversal_diamonds() -> Result<(), Box<dyn Error>> {
+        for objective in OBJECTIVES {
+            let model = ModelTester::new(0.0).load_model(&format!(
+                "tests/models/{}/diamonds_model_trees_100_mixed.json",
+                objective
+            ))?;
+            assert_engines_match(
+                &model,
+                &format!(
+                    "tests/data/{}/diamonds_data_filtered_trees_100_mixed.csv",
+                    objective
+                ),
+                DatasetType::Diamonds,
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallelism_preserves_predictions() -> Result<(), Box<dyn Error>> {
+        let model = ModelTester::new(0.0)
+            .load_model("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")?;
+        let (batches, _) = ModelTester::new(0.0).load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Diamonds,
+        )?;
+        let expected = model.predict_batches(&batches)?;
+
+        for parallelism in [
+            Parallelism::Sequential,
+            Parallelism::threads(1)?,
+            Parallelism::threads(3)?,
+        ] {
+            for sequential_threshold in [0, usize::MAX] {
+                let mut candidate = model.clone();
+                candidate.set_config(
+                    PredictorConfig::default()
+                        .with_parallelism(parallelism.clone())
+                        .with_sequential_threshold(sequential_threshold),
+                );
+                assert_eq!(candidate.predict_batches(&batches)?, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_f64_accumulation_is_reproducible() -> Result<(), Box<dyn Error>> {
+        for dataset in ["diamonds", "airline_satisfaction"] {
+            let tester = ModelTester::new(0.0);
+            let model = tester.load_model(&format!(
+                "tests/models/reg_squarederror/{}_model_trees_100_mixed.json",
+                dataset
+            ))?;
+            let dataset_type = match dataset {
+                "diamonds" => DatasetType::Diamonds,
+                _ => DatasetType::Airline,
+            };
+            let (batches, expected_results) = tester.load_dataset(
+                &format!(
+                    "tests/data/reg_squarederror/{}_data_filtered_trees_100_mixed.csv",
+                    dataset
+                ),
+                1024,
+                dataset_type,
+            )?;
+            let reference = tester.extract_expected_predictions(&expected_results)?;
+
+            let mut f64_model = model.clone();
+            f64_model.set_config(PredictorConfig::default().with_accumulation(Accumulation::F64));
+            let expected: Vec<_> = batches
+                .iter()
+                .map(|batch| f64_model.predict_batches(std::slice::from_ref(batch)))
+                .collect::<Result<_, _>>()?;
+
+            // XGBoost sums in f32, so only the last few bits may differ from its predictions.
+            for (predictions, reference) in expected.iter().zip(&reference) {
+                for (&actual, &reference) in predictions.values().iter().zip(reference.values()) {
+                    assert!(
+                        (actual - reference).abs() <= 2e-6 * reference.abs().max(1.0),
+                        "{}: {} vs {}",
+                        dataset,
+                        actual,
+                        reference
+                    );
+                }
+            }
+
+            for engine in [
+                PredictionEngine::Traversal,
+                PredictionEngine::QuickScorer,
+                PredictionEngine::Simd,
+            ] {
+                for (row_chunk_size, tree_chunk_size) in [(1, 1), (21, 7), (128, 1000)] {
+                    for parallelism in [Parallelism::Sequential, Parallelism::threads(3)?] {
+                        let config = PredictorConfig::new(row_chunk_size, tree_chunk_size)?
+                            .with_engine(engine)
+                            .with_accumulation(Accumulation::F64)
+                            .with_parallelism(parallelism)
+                            .with_sequential_threshold(0);
+                        let mut candidate = model.clone();
+                        candidate.set_config(config.clone());
+                        let mut quantized = model.quantize()?;
+                        quantized.set_config(config);
+                        for (batch, expected) in batches.iter().zip(&expected) {
+                            let batch = std::slice::from_ref(batch);
+                            assert_eq!(&candidate.predict_batches(batch)?, expected, "{}", engine);
+                            assert_eq!(&quantized.predict_batches(batch)?, expected);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_autotune_and_cache_in_model_file() -> Result<(), Box<dyn Error>> {
+        let model_path = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+        let model = ModelTester::new(0.0).load_model(model_path)?;
+        let (batches, _) = ModelTester::new(0.0).load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Diamonds,
+        )?;
+        let options = TuningOptions {
+            row_chunk_sizes: vec![16, 64],
+            tree_chunk_sizes: vec![8, 32],
+            layouts: vec![None, Some(NodeLayout::breadth_first())],
+            repetitions: 1,
+            ..TuningOptions::default()
+        };
+        let tuning = model.autotune(&batches[0], &options)?;
+        assert!(options
+            .row_chunk_sizes
+            .contains(&tuning.config.row_chunk_size()));
+        assert!(options
+            .tree_chunk_sizes
+            .contains(&tuning.config.tree_chunk_size()));
+
+        let tuned = tuning.apply(&model);
+        let mut reference = model.clone();
+        reference.set_config(
+            PredictorConfig::default().with_tree_chunk_size(tuning.config.tree_chunk_size())?,
+        );
+        assert_eq!(
+            tuned.predict_batches(&batches)?,
+            reference.predict_batches(&batches)?
+        );
+
+        let mut model_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(model_path)?)?;
+        tuning.write_to_model_json(&mut model_json);
+        let reloaded = GradientBoostedDecisionTrees::json_loads(&model_json)?;
+        assert_eq!(
+            reloaded.config.row_chunk_size(),
+            tuning.config.row_chunk_size()
+        );
+        assert_eq!(
+            reloaded.config.tree_chunk_size(),
+            tuning.config.tree_chunk_size()
+        );
+        assert_eq!(reloaded.config.engine(), tuning.config.engine());
+        assert_eq!(
+            reloaded.predict_batches(&batches)?,
+            tuned.predict_batches(&batches)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_autotune_keeps_f64_accumulation_across_reloads() -> Result<(), Box<dyn Error>> {
+        let model_path = "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json";
+        let mut model = ModelTester::new(0.0).load_model(model_path)?;
+        model.set_config(PredictorConfig::default().with_accumulation(Accumulation::F64));
+        let (batches, _) = ModelTester::new(0.0).load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Diamonds,
+        )?;
+        let options = TuningOptions {
+            row_chunk_sizes: vec![16],
+            tree_chunk_sizes: vec![8, 32],
+            repetitions: 1,
+            ..TuningOptions::default()
+        };
+        let tuning = model.autotune(&batches[0], &options)?;
+        assert_eq!(tuning.config.accumulation(), Accumulation::F64);
+
+        let mut model_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(model_path)?)?;
+        tuning.write_to_model_json(&mut model_json);
+        let reloaded = GradientBoostedDecisionTrees::json_loads(&model_json)?;
+        assert_eq!(reloaded.config.accumulation(), Accumulation::F64);
+        assert_eq!(reloaded.config.engine(), tuning.config.engine());
+        // F64 sums are independent of the tuned chunk sizes, engine and layout.
+        let expected = model.predict_batches(&batches)?;
+        assert_eq!(tuning.apply(&model).predict_batches(&batches)?, expected);
+        assert_eq!(reloaded.predict_batches(&batches)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_engines_match_traversal_airline() -> Result<(), Box<dyn Error>> {
+        for objective in OBJECTIVES {
+            let model = ModelTester::new(0.0).load_model(&format!(
+                "tests/models/{}/airline_satisfaction_model_trees_100_mixed.json",
+                objective
+            ))?;
+            let data_path = format!(
+                "tests/data/{}/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+                objective
+            );
+            assert_engines_match(&model, &data_path, DatasetType::Airline)?;
+
+            let mut predicate = Predicate::new();
+            predicate.add_condition("age".to_string(), Condition::LessThan(40.0));
+            assert_engines_match(&model.prune(&predicate), &data_path, DatasetType::Airline)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+pub mod common;
+use common::ModelTester;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use trusty::{Condition, ImportanceType, Predicate};
+
+const KINDS: [ImportanceType; 5] = [
+    ImportanceType::Weight,
+    ImportanceType::Gain,
+    ImportanceType::Cover,
+    ImportanceType::TotalGain,
+    ImportanceType::TotalCover,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reimplements XGBoost's `Booster.get_score` directly on the model JSON.
+    fn xgboost_scores(json: &Value, kind: ImportanceType) -> HashMap<String, f64> {
+        let names = json["learner"]["feature_names"].as_array().unwrap();
+        let mut totals: HashMap<String, (f64, f64, f64)> = HashMap::new();
+        for tree in json["learner"]["gradient_booster"]["model"]["trees"]
+            .as_array()
+            .unwrap()
+        {
+            let lefts = tree["left_children"].as_array().unwrap();
+            for (node, left) in lefts.iter().enumerate() {
+                if left.as_i64() == Some(-1) {
+                    continue;
+                }
+                let feature = tree["split_indices"][node].as_u64().unwrap() as usize;
+                let entry = totals
+                    .entry(names[feature].as_str().unwrap().to_string())
+                    .or_default();
+                entry.0 += 1.0;
+                entry.1 += tree["loss_changes"][node].as_f64().unwrap() as f32 as f64;
+                entry.2 += tree["sum_hessian"][node].as_f64().unwrap() as f32 as f64;
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(name, (weight, gain, cover))| {
+                let score = match kind {
+                    ImportanceType::Weight => weight,
+                    ImportanceType::Gain => gain / weight,
+                    ImportanceType::Cover => cover / weight,
+                    ImportanceType::TotalGain => gain,
+                    ImportanceType::TotalCover => cover,
+                };
+                (name, score)
+            })
+            .collect()
+    }
+
+    fn assert_scores_match(actual: &HashMap<String, f64>, expected: &HashMap<String, f64>) {
+        assert_eq!(actual.len(), expected.len());
+        for (name, &score) in expected {
+            let value = actual[name];
+            assert!(
+                (value - score).abs() <= 1e-9 * score.abs().max(1.0),
+                "{}: {} != {}",
+                name,
+                value,
+                score
+            );
+        }
+    }
+
+    #[test]
+    fn test_feature_importance_matches_xgboost() -> Result<(), Box<dyn Error>> {
+        let path = "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json";
+        let model = ModelTester::new(0.0).load_model(path)?;
+        let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        for kind in KINDS {
+            assert_scores_match(
+                &model.feature_importance(kind),
+                &xgboost_scores(&json, kind),
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_importance_pruned_uses_original_names() -> Result<(), Box<dyn Error>> {
+        let model = ModelTester::new(0.0).load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition(
+            "inflight_wifi_service".to_string(),
+            Condition::LessThan(1.0),
+        );
+        let pruned = model.prune(&predicate);
+        // Pruning drops features, so the compacted indices no longer line up with the original ones.
+        assert!(pruned.required_features.len() < model.required_features.len());
+
+        let json = pruned.to_xgboost_json();
+        for kind in KINDS {
+            assert_scores_match(
+                &pruned.feature_importance(kind),
+                &xgboost_scores(&json, kind),
+            );
+        }
+
+        let full = model.feature_importance(ImportanceType::Weight);
+        for (name, weight) in pruned.feature_importance(ImportanceType::Weight) {
+            assert!(weight <= full[&name], "{}", name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "flight")]
+pub mod common;
+use arrow::array::{Array, Float32Array};
+use arrow::record_batch::RecordBatch;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::{FlightClient, FlightDescriptor};
+use common::{DatasetType, ModelTester};
+use futures::{stream, TryStreamExt};
+use std::error::Error;
+use tokio::net::TcpListener;
+use tonic::transport::Channel;
+use trusty::flight::{serve, TrustyFlightService};
+use trusty::GradientBoostedDecisionTrees;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_diamonds() -> Result<(GradientBoostedDecisionTrees, Vec<RecordBatch>), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let model = tester
+            .load_model("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            32,
+            DatasetType::Diamonds,
+        )?;
+        Ok((model, batches))
+    }
+
+    /// Starts a server with the diamonds and airline models and connects a client to it.
+    async fn start_server() -> Result<FlightClient, Box<dyn Error>> {
+        let (model, _) = load_diamonds()?;
+        let airline = ModelTester::new(0.0).load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let service = TrustyFlightService::new([
+            ("diamonds".to_string(), model),
+            ("airline".to_string(), airline),
+        ]);
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(listener, service));
+
+        let channel = Channel::from_shared(format!("http://{}", addr))?
+            .connect()
+            .await?;
+        Ok(FlightClient::new(channel))
+    }
+
+    async fn exchange(
+        client: &mut FlightClient,
+        path: Vec<String>,
+        batches: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>, FlightError> {
+        let input = FlightDataEncoderBuilder::new()
+            .with_flight_descriptor(Some(FlightDescriptor::new_path(path)))
+            .build(stream::iter(batches.into_iter().map(Ok)));
+        client.do_exchange(input).await?.try_collect().await
+    }
+
+    fn predictions(batches: &[RecordBatch]) -> Vec<f32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column_by_name("prediction").unwrap();
+                let values = column.as_any().downcast_ref::<Float32Array>().unwrap();
+                values.values().to_vec()
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_list_flights_reports_input_schemas() -> Result<(), Box<dyn Error>> {
+        let (model, _) = load_diamonds()?;
+        let mut client = start_server().await?;
+
+        let infos: Vec<_> = client.list_flights("").await?.try_collect().await?;
+        let paths: Vec<Vec<String>> = infos
+            .iter()
+            .map(|info| info.flight_descriptor.clone().unwrap().path)
+            .collect();
+        assert_eq!(paths, [vec!["airline"], vec!["diamonds"]]);
+        assert_eq!(infos[1].clone().try_decode_schema()?, model.input_schema());
+
+        let schema = client
+            .get_schema(FlightDescriptor::new_path(vec!["diamonds".into()]))
+            .await?;
+        assert_eq!(schema, model.input_schema());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exchange_matches_predict_batches() -> Result<(), Box<dyn Error>> {
+        let (model, batches) = load_diamonds()?;
+        let expected = model.predict_batches(&batches)?;
+        let mut client = start_server().await?;
+
+        let output = exchange(&mut client, vec!["diamonds".into()], batches.clone()).await?;
+        assert_eq!(output.len(), batches.len());
+        assert_eq!(predictions(&output), expected.values().to_vec());
+
+        // Columns are matched by name, not position.
+        let reversed: Vec<usize> = (0..batches[0].num_columns()).rev().collect();
+        let reordered = batches
+            .iter()
+            .map(|batch| batch.project(&reversed))
+            .collect::<Result<Vec<_>, _>>()?;
+        let output = exchange(&mut client, vec!["diamonds".into()], reordered).await?;
+        assert_eq!(predictions(&output), expected.values().to_vec());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exchange_errors() -> Result<(), Box<dyn Error>> {
+        let (_, batches) = load_diamonds()?;
+        let mut client = start_server().await?;
+
+        let err = exchange(&mut client, vec!["unknown".into()], batches.clone())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Unknown model: unknown"),
+            "{}",
+            err
+        );
+
+        let err = exchange(&mut client, vec![], batches.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("descriptor path"), "{}", err);
+
+        let without_carat = batches
+            .iter()
+            .map(|batch| {
+                let indices: Vec<usize> = (1..batch.num_columns()).collect();
+                batch.project(&indices)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let err = exchange(&mut client, vec!["diamonds".into()], without_carat)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Missing feature column: carat"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 pub mod common;
-use arrow::array::Float32Array;
+use arrow::array::{Array, Float32Array, Int64Array};
+use arrow::datatypes::DataType;
 use arrow::record_batch::RecordBatch;
 use common::{DatasetType, ModelTester, PredictionComparator};
 use std::error::Error;
+use trusty::tree::NodeLayout;
 use trusty::{Condition, Predicate};
 
 #[cfg(test)]
@@ -65,6 +67,44 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_pruned_airline_matches_unpruned() -> Result<(), Box<dyn Error>> {
+        // The airline model only splits on a subset of its features, so its trees use
+        // compacted feature indices. Pruning must still resolve names and columns correctly.
+        let tester = ModelTester::new(1e-6);
+        let trees = tester.load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition(
+            "online_boarding".to_string(),
+            Condition::GreaterThanOrEqual(4.0),
+        );
+        let pruned_trees = trees.prune(&predicate);
+
+        let (preprocessed_batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Airline,
+        )?;
+
+        for batch in &preprocessed_batches {
+            let expected = trees.predict_batches(&[batch.clone()])?;
+            let actual = pruned_trees.predict_batches(&[batch.clone()])?;
+            let online_boarding = batch
+                .column_by_name("online_boarding")
+                .and_then(|col| col.as_any().downcast_ref::<Int64Array>())
+                .ok_or("Column 'online_boarding' not found")?;
+
+            for row in 0..batch.num_rows() {
+                if online_boarding.value(row) >= 4 {
+                    assert!((expected.value(row) - actual.value(row)).abs() < 1e-6);
+                }
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_model_results_airline() -> Result<(), Box<dyn Error>> {
         let epsilon = 1e-1;
@@ -150,6 +190,102 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_node_layouts_preserve_predictions() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        for (model_path, data_path, dataset_type) in [
+            (
+                "tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json",
+                "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+                DatasetType::Diamonds,
+            ),
+            (
+                "tests/models/binary_logistic/airline_satisfaction_model_trees_100_mixed.json",
+                "tests/data/binary_logistic/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+                DatasetType::Airline,
+            ),
+        ] {
+            let trees = tester.load_model(model_path)?;
+            let (batches, _) = tester.load_dataset(data_path, 1024, dataset_type)?;
+            let expected = trees.predict_batches(&batches)?;
+
+            for layout in [
+                NodeLayout::breadth_first(),
+                NodeLayout::default(),
+                NodeLayout {
+                    use_cover: false,
+                    ..NodeLayout::default()
+                },
+            ] {
+                let reordered = trees.with_layout(layout);
+                assert_eq!(
+                    reordered.predict_batches(&batches)?,
+                    expected,
+                    "{:?}",
+                    layout
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dense_and_sparse_rows_match_batches() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let trees = tester.load_model(
+            "tests/models/binary_logistic/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/binary_logistic/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Airline,
+        )?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition("age".to_string(), Condition::LessThan(40.0));
+
+        // The pruned model uses a subset of the columns, still passed in the original order.
+        for model in [trees.clone(), trees.prune(&predicate)] {
+            for batch in &batches {
+                let expected = model.predict_batches(std::slice::from_ref(batch))?;
+                let (n_rows, n_cols) = (batch.num_rows(), batch.num_columns());
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|column| arrow::compute::cast(column, &DataType::Float32))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let columns: Vec<&Float32Array> = columns
+                    .iter()
+                    .map(|column| column.as_any().downcast_ref::<Float32Array>().unwrap())
+                    .collect();
+
+                let mut dense = Vec::with_capacity(n_rows * n_cols);
+                let (mut indptr, mut indices, mut values) = (vec![0], Vec::new(), Vec::new());
+                for row in 0..n_rows {
+                    for (col, column) in columns.iter().enumerate() {
+                        if column.is_null(row) {
+                            dense.push(f32::NAN);
+                        } else {
+                            dense.push(column.value(row));
+                            indices.push(col);
+                            values.push(column.value(row));
+                        }
+                    }
+                    indptr.push(indices.len());
+                }
+
+                assert_eq!(model.predict_dense(&dense, n_rows, n_cols)?, expected);
+                assert_eq!(
+                    model.predict_csr(&indptr, &indices, &values, n_cols)?,
+                    expected
+                );
+                for (row, &prediction) in dense.chunks(n_cols).zip(expected.values()).take(50) {
+                    assert_eq!(model.predict_row(row)?, prediction);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn compare_prediction_results(
         trusty_predictions: &[Float32Array],
         expected_predictions: &[&Float32Array],
@@ -0,0 +1,228 @@
+#![cfg(feature = "server")]
+pub mod common;
+use arrow::array::{Array, Float32Array};
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use common::{DatasetType, ModelTester};
+use serde_json::Value;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use trusty::server::{router, serve, BatchOptions, ARROW_STREAM};
+use trusty::GradientBoostedDecisionTrees;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_diamonds(
+        batch_size: usize,
+    ) -> Result<(GradientBoostedDecisionTrees, Vec<RecordBatch>), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let model = tester
+            .load_model("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            batch_size,
+            DatasetType::Diamonds,
+        )?;
+        Ok((model, batches))
+    }
+
+    async fn start_server(options: BatchOptions) -> Result<SocketAddr, Box<dyn Error>> {
+        let (model, _) = load_diamonds(100)?;
+        let tester = ModelTester::new(0.0);
+        let airline = tester.load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let app = router(
+            [
+                ("diamonds".to_string(), model),
+                ("airline".to_string(), airline),
+            ],
+            options,
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(listener, app));
+        Ok(addr)
+    }
+
+    /// Minimal HTTP/1.1 client; returns the status code and the body.
+    async fn request(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<(u16, Vec<u8>), Box<dyn Error>> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let head = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            path,
+            content_type,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+
+        let split = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or("incomplete response")?;
+        let head = String::from_utf8_lossy(&response[..split]);
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .ok_or("missing status")?
+            .parse()?;
+        Ok((status, response[split + 4..].to_vec()))
+    }
+
+    async fn get_json(addr: SocketAddr, path: &str) -> Result<(u16, Value), Box<dyn Error>> {
+        let (status, body) = request(addr, "GET", path, "application/json", b"").await?;
+        Ok((status, serde_json::from_slice(&body)?))
+    }
+
+    fn json_rows(batch: &RecordBatch) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+        writer.write(batch)?;
+        writer.finish()?;
+        let rows = String::from_utf8(writer.into_inner())?;
+        Ok(format!("{{\"rows\": {}}}", rows).into_bytes())
+    }
+
+    async fn predict_json(addr: SocketAddr, body: &[u8]) -> Result<Vec<f32>, Box<dyn Error>> {
+        let (status, body) = request(
+            addr,
+            "POST",
+            "/models/diamonds/predict",
+            "application/json",
+            body,
+        )
+        .await?;
+        assert_eq!(status, 200, "{}", String::from_utf8_lossy(&body));
+        let response: Value = serde_json::from_slice(&body)?;
+        Ok(serde_json::from_value(response["predictions"].clone())?)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_metadata_and_health() -> Result<(), Box<dyn Error>> {
+        let addr = start_server(BatchOptions::default()).await?;
+
+        let (status, health) = get_json(addr, "/health").await?;
+        assert_eq!(status, 200);
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["models"], 2);
+
+        let (_, models) = get_json(addr, "/models").await?;
+        let names: Vec<&str> = models["models"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|model| model["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["airline", "diamonds"]);
+
+        let (status, diamonds) = get_json(addr, "/models/diamonds").await?;
+        assert_eq!(status, 200);
+        assert_eq!(diamonds["feature_names"][0], "carat");
+        assert_eq!(diamonds["feature_types"][0], "float");
+        assert_eq!(diamonds["feature_types"][6], "i");
+        assert_eq!(diamonds["num_trees"], 100);
+
+        let (status, error) = get_json(addr, "/models/unknown").await?;
+        assert_eq!(status, 404);
+        assert_eq!(error["error"], "Unknown model: unknown");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_predict_json_and_arrow() -> Result<(), Box<dyn Error>> {
+        let (model, batches) = load_diamonds(100)?;
+        let addr = start_server(BatchOptions::default()).await?;
+        let batch = &batches[0];
+        let expected = model.predict_batches(std::slice::from_ref(batch))?;
+
+        let predictions = predict_json(addr, &json_rows(batch)?).await?;
+        assert_eq!(predictions, expected.values().to_vec());
+
+        let mut writer = StreamWriter::try_new(Vec::new(), &batch.schema())?;
+        writer.write(batch)?;
+        writer.finish()?;
+        let (status, body) = request(
+            addr,
+            "POST",
+            "/models/diamonds/predict",
+            ARROW_STREAM,
+            &writer.into_inner()?,
+        )
+        .await?;
+        assert_eq!(status, 200);
+        let output =
+            StreamReader::try_new(body.as_slice(), None)?.collect::<Result<Vec<_>, _>>()?;
+        let column = output[0].column_by_name("prediction").unwrap();
+        let predictions = column.as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(predictions, &expected);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_invalid_requests() -> Result<(), Box<dyn Error>> {
+        let addr = start_server(BatchOptions::default()).await?;
+        let predict = |content_type: &'static str, body: &'static [u8]| {
+            request(addr, "POST", "/models/diamonds/predict", content_type, body)
+        };
+
+        let (status, _) = predict("text/csv", b"carat\n0.2").await?;
+        assert_eq!(status, 415);
+        let (status, body) =
+            predict("application/json", br#"{"rows": [{"carat": "big"}]}"#).await?;
+        assert_eq!(status, 400);
+        assert!(String::from_utf8_lossy(&body).contains("Feature carat must be a number"));
+        let (status, _) = predict("application/json", b"[1, 2]").await?;
+        assert_eq!(status, 400);
+        let (status, _) = predict(ARROW_STREAM, b"not arrow").await?;
+        assert_eq!(status, 400);
+
+        // Missing features are missing values.
+        let (status, _) = predict("application/json", br#"{"rows": [{}, {"carat": 0.3}]}"#).await?;
+        assert_eq!(status, 200);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_requests_are_batched() -> Result<(), Box<dyn Error>> {
+        let (model, batches) = load_diamonds(5)?;
+        let options = BatchOptions {
+            max_delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let addr = start_server(options).await?;
+
+        let mut requests = Vec::new();
+        for batch in batches.iter().take(16) {
+            let body = json_rows(batch)?;
+            let expected = model.predict_batches(std::slice::from_ref(batch))?;
+            requests.push(tokio::spawn(async move {
+                let predictions = predict_json(addr, &body).await.unwrap();
+                assert_eq!(predictions, expected.values().to_vec());
+            }));
+        }
+        for request in requests {
+            request.await?;
+        }
+
+        let (_, diamonds) = get_json(addr, "/models/diamonds").await?;
+        let stats = &diamonds["stats"];
+        assert_eq!(stats["requests"], 16);
+        assert!(stats["batches"].as_u64().unwrap() < 16, "{}", stats);
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+pub mod common;
+use arrow::array::{Array, BooleanArray, Float32Array, Int64Array};
+use arrow::record_batch::RecordBatch;
+use common::{DatasetType, ModelTester};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use std::error::Error;
+use trusty::export::SqlDialect;
+use trusty::{Accumulation, Condition, GradientBoostedDecisionTrees, Predicate, PredictorConfig};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sqlite_with_exp() -> Result<Connection, Box<dyn Error>> {
+        let conn = Connection::open_in_memory()?;
+        conn.create_scalar_function(
+            "exp",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| Ok(ctx.get::<f64>(0)?.exp()),
+        )?;
+        Ok(conn)
+    }
+
+    fn sql_value(array: &dyn Array, row: usize) -> SqlValue {
+        if array.is_null(row) {
+            return SqlValue::Null;
+        }
+        if let Some(array) = array.as_any().downcast_ref::<Float32Array>() {
+            return SqlValue::Real(array.value(row) as f64);
+        }
+        if let Some(array) = array.as_any().downcast_ref::<Int64Array>() {
+            return SqlValue::Integer(array.value(row));
+        }
+        if let Some(array) = array.as_any().downcast_ref::<BooleanArray>() {
+            return SqlValue::Integer(array.value(row) as i64);
+        }
+        panic!("unsupported type: {}", array.data_type())
+    }
+
+    fn load_table(conn: &Connection, batches: &[RecordBatch]) -> Result<(), Box<dyn Error>> {
+        let schema = batches[0].schema();
+        let columns: Vec<String> = schema
+            .fields()
+            .iter()
+            .map(|field| format!("\"{}\"", field.name()))
+            .collect();
+        conn.execute(&format!("CREATE TABLE data ({})", columns.join(", ")), [])?;
+
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert = conn.prepare(&format!(
+            "INSERT INTO data ({}) VALUES ({})",
+            columns.join(", "),
+            placeholders
+        ))?;
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                let values = batch.columns().iter().map(|col| sql_value(col, row));
+                insert.execute(params_from_iter(values))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the ANSI rendering in SQLite. SQLite evaluates REAL in doubles, so the model is
+    /// switched to `Accumulation::F64`, whose rendering adds the trees up in doubles.
+    fn assert_sql_matches_predictions(
+        model: &GradientBoostedDecisionTrees,
+        batches: &[RecordBatch],
+    ) -> Result<(), Box<dyn Error>> {
+        let conn = sqlite_with_exp()?;
+        load_table(&conn, batches)?;
+
+        let mut f64_model = model.clone();
+        f64_model.set_config(PredictorConfig::default().with_accumulation(Accumulation::F64));
+        let sql = f64_model.to_sql(SqlDialect::Ansi);
+        let mut query = conn.prepare(&format!("SELECT {} FROM data ORDER BY rowid", sql))?;
+        let sql_predictions: Vec<f64> = query
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        let predictions = f64_model.predict_batches(batches)?;
+        assert_close(predictions.values(), &sql_predictions);
+        Ok(())
+    }
+
+    fn assert_close(expected: &[f32], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (i, (&expected, &actual)) in expected.iter().zip(actual).enumerate() {
+            // Only the final rounding to f32 and the link function, which SQL evaluates in
+            // doubles, differ.
+            let tolerance = 1e-6 * (expected.abs() as f64).max(1.0);
+            assert!(
+                (expected as f64 - actual).abs() <= tolerance,
+                "Row {}: predict_batches={}, sql={}",
+                i,
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_sql_matches_predictions_diamonds() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(1e-6);
+        for objective in ["reg_squarederror", "binary_logistic"] {
+            let model = tester.load_model(&format!(
+                "tests/models/{}/diamonds_model_trees_100_mixed.json",
+                objective
+            ))?;
+            let (batches, _) = tester.load_dataset(
+                &format!(
+                    "tests/data/{}/diamonds_data_filtered_trees_100_mixed.csv",
+                    objective
+                ),
+                1024,
+                DatasetType::Diamonds,
+            )?;
+            assert_sql_matches_predictions(&model, &batches)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_matches_predictions_pruned_airline() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(1e-6);
+        let model = tester.load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/airline_satisfaction_data_filtered_trees_100_mixed.csv",
+            1024,
+            DatasetType::Airline,
+        )?;
+
+        let mut predicate = Predicate::new();
+        predicate.add_condition(
+            "online_boarding".to_string(),
+            Condition::GreaterThanOrEqual(4.0),
+        );
+        let pruned = model.prune(&predicate);
+
+        assert_sql_matches_predictions(&model, &batches)?;
+        assert_sql_matches_predictions(&pruned, &batches)
+    }
+
+    /// DataFusion evaluates REAL in `f32` and orders NaN above every number, as DuckDB does, so
+    /// it runs the default DuckDB rendering against the default `predict_batches`.
+    #[cfg(feature = "datafusion")]
+    #[tokio::test]
+    async fn test_duckdb_sql_matches_predict_batches() -> Result<(), Box<dyn Error>> {
+        use arrow::array::{ArrayRef, Float64Array};
+        use arrow::compute::cast;
+        use arrow::datatypes::DataType;
+        use datafusion::prelude::SessionContext;
+        use std::sync::Arc;
+
+        let tester = ModelTester::new(1e-6);
+        for objective in ["reg_squarederror", "binary_logistic"] {
+            let model = tester.load_model(&format!(
+                "tests/models/{}/diamonds_model_trees_100_mixed.json",
+                objective
+            ))?;
+            let (batches, _) = tester.load_dataset(
+                &format!(
+                    "tests/data/{}/diamonds_data_filtered_trees_100_mixed.csv",
+                    objective
+                ),
+                1024,
+                DatasetType::Diamonds,
+            )?;
+            let batch = &batches[0];
+
+            // Row 0 gets a NaN depth, which some splits send left by default. Row 1 gets a
+            // DOUBLE carat just below the root threshold of 1.0 that rounds up to it in f32.
+            let mut f32_columns = batch.columns().to_vec();
+            let mut f64_columns = batch.columns().to_vec();
+            for (name, row, value) in [("depth", 0, f64::NAN), ("carat", 1, 1.0 - 2f64.powi(-30))] {
+                let index = batch.schema().index_of(name)?;
+                let column = cast(&batch.columns()[index], &DataType::Float64)?;
+                let mut values: Vec<Option<f64>> = column
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .unwrap()
+                    .iter()
+                    .collect();
+                values[row] = Some(value);
+                let column: ArrayRef = Arc::new(Float64Array::from(values));
+                f32_columns[index] = cast(&column, &DataType::Float32)?;
+                f64_columns[index] = column;
+            }
+            let expected =
+                model.predict_batches(&[RecordBatch::try_new(batch.schema(), f32_columns)?])?;
+
+            let names = batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone());
+            let ctx = SessionContext::new();
+            ctx.register_batch(
+                "diamonds",
+                RecordBatch::try_from_iter(names.zip(f64_columns))?,
+            )?;
+            let sql = format!("SELECT {} FROM diamonds", model.to_sql(SqlDialect::DuckDb));
+            let mut actual = Vec::new();
+            for result in ctx.sql(&sql).await?.collect().await? {
+                let column = cast(result.column(0), &DataType::Float64)?;
+                let column = column.as_any().downcast_ref::<Float64Array>().unwrap();
+                actual.extend(column.values().iter().copied());
+            }
+            assert_close(expected.values(), &actual);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+pub mod common;
+use arrow::array::{Array, Float32Array};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchIterator};
+use common::{DatasetType, ModelTester};
+use std::error::Error;
+use std::fs::File;
+use std::path::PathBuf;
+use trusty::{GradientBoostedDecisionTrees, OutputColumns};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_diamonds(
+        batch_size: usize,
+    ) -> Result<(GradientBoostedDecisionTrees, Vec<RecordBatch>), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let model = tester
+            .load_model("tests/models/reg_squarederror/diamonds_model_trees_100_mixed.json")?;
+        let (batches, _) = tester.load_dataset(
+            "tests/data/reg_squarederror/diamonds_data_filtered_trees_100_mixed.csv",
+            batch_size,
+            DatasetType::Diamonds,
+        )?;
+        Ok((model, batches))
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("trusty-stream-{}-{}", std::process::id(), name))
+    }
+
+    fn predictions(batches: &[RecordBatch]) -> Vec<f32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column_by_name("prediction").unwrap();
+                let values = column.as_any().downcast_ref::<Float32Array>().unwrap();
+                values.values().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_matches_predict_batches() -> Result<(), Box<dyn Error>> {
+        let (model, batches) = load_diamonds(100)?;
+        let expected = model.predict_batches(&batches)?;
+
+        let reader =
+            RecordBatchIterator::new(batches.clone().into_iter().map(Ok), batches[0].schema());
+        let output = model
+            .predict_stream(reader, OutputColumns::Prediction)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(output.len(), batches.len());
+        for (output, input) in output.iter().zip(&batches) {
+            assert_eq!(output.num_rows(), input.num_rows());
+        }
+        assert_eq!(predictions(&output), expected.values().to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_ipc_file() -> Result<(), Box<dyn Error>> {
+        let (model, batches) = load_diamonds(256)?;
+        let expected = model.predict_batches(&batches)?;
+
+        let input = temp_path("diamonds.arrow");
+        let output = temp_path("predictions.arrow");
+        let mut writer = FileWriter::try_new(File::create(&input)?, &batches[0].schema())?;
+        for batch in &batches {
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+
+        let num_rows = model.predict_ipc_file(&input, &output, OutputColumns::Append)?;
+        assert_eq!(num_rows, expected.len());
+        let written =
+            FileReader::try_new(File::open(&output)?, None)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(written.len(), batches.len());
+        assert_eq!(written[0].num_columns(), batches[0].num_columns() + 1);
+        assert_eq!(predictions(&written), expected.values().to_vec());
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_predict_parquet_file() -> Result<(), Box<dyn Error>> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use parquet::arrow::ArrowWriter;
+
+        let (model, batches) = load_diamonds(1024)?;
+        let expected = model.predict_batches(&batches)?;
+
+        let input = temp_path("diamonds.parquet");
+        let output = temp_path("predictions.parquet");
+        let mut writer = ArrowWriter::try_new(File::create(&input)?, batches[0].schema(), None)?;
+        for batch in &batches {
+            writer.write(batch)?;
+        }
+        writer.close()?;
+
+        let num_rows =
+            model.predict_parquet_file(&input, &output, 64, OutputColumns::Prediction)?;
+        assert_eq!(num_rows, expected.len());
+        let written = ParquetRecordBatchReaderBuilder::try_new(File::open(&output)?)?
+            .build()?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(written[0].num_columns(), 1);
+        assert_eq!(predictions(&written), expected.values().to_vec());
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+pub mod common;
+use common::{DatasetType, ModelTester};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use trusty::loader::ModelLoader;
+use trusty::{Condition, GradientBoostedDecisionTrees, Predicate};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trip(
+        model: &GradientBoostedDecisionTrees,
+        data_path: &str,
+        dataset_type: DatasetType,
+    ) -> Result<(), Box<dyn Error>> {
+        let exported = serde_json::to_string(&model.to_xgboost_json())?;
+        let reloaded = GradientBoostedDecisionTrees::json_loads(&serde_json::from_str(&exported)?)?;
+
+        assert_eq!(reloaded.feature_names, model.feature_names);
+        assert_eq!(reloaded.base_score, model.base_score);
+        assert_eq!(
+            reloaded.original_feature_indices(),
+            model.original_feature_indices()
+        );
+        assert_eq!(reloaded.num_trees(), model.num_trees());
+        for (reloaded_tree, tree) in reloaded.trees.iter().zip(&model.trees) {
+            assert_eq!(reloaded_tree.num_nodes(), tree.num_nodes());
+        }
+
+        let tester = ModelTester::new(0.0);
+        let (batches, _) = tester.load_dataset(data_path, 1024, dataset_type)?;
+        let expected = model.predict_batches(&batches)?;
+        let actual = reloaded.predict_batches(&batches)?;
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    /// Checks that `exported` has every field of `original`, with the same JSON type, so that
+    /// XGBoost finds everything it reads.
+    fn assert_has_fields(original: &Value, exported: &Value, path: &str) {
+        match (original, exported) {
+            (Value::Object(original), Value::Object(exported)) => {
+                for (key, value) in original {
+                    let field = exported
+                        .get(key)
+                        .unwrap_or_else(|| panic!("{}/{} is missing", path, key));
+                    assert_has_fields(value, field, &format!("{}/{}", path, key));
+                }
+            }
+            (Value::Array(original), Value::Array(exported)) => {
+                if let (Some(original), Some(exported)) = (original.first(), exported.first()) {
+                    assert_has_fields(original, exported, &format!("{}/0", path));
+                }
+            }
+            (Value::Number(_), Value::Number(_))
+            | (Value::String(_), Value::String(_))
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::Null, Value::Null) => {}
+            _ => panic!("{} is {} in the export, not {}", path, exported, original),
+        }
+    }
+
+    #[test]
+    fn test_export_keeps_xgboost_fields() -> Result<(), Box<dyn Error>> {
+        for objective in ["reg_squarederror", "reg_logistic", "binary_logistic"] {
+            for dataset in ["diamonds", "airline_satisfaction"] {
+                let path = format!(
+                    "tests/models/{}/{}_model_trees_100_mixed.json",
+                    objective, dataset
+                );
+                let original: Value = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+                let exported =
+                    GradientBoostedDecisionTrees::json_loads(&original)?.to_xgboost_json();
+                assert_has_fields(&original, &exported, "");
+                assert_eq!(
+                    exported["learner"]["objective"]["name"],
+                    original["learner"]["objective"]["name"]
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_diamonds() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        for objective in ["reg_squarederror", "binary_logistic"] {
+            let model = tester.load_model(&format!(
+                "tests/models/{}/diamonds_model_trees_100_mixed.json",
+                objective
+            ))?;
+            assert_round_trip(
+                &model,
+                &format!(
+                    "tests/data/{}/diamonds_data_filtered_trees_100_mixed.csv",
+                    objective
+                ),
+                DatasetType::Diamonds,
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_pruned_airline() -> Result<(), Box<dyn Error>> {
+        let tester = ModelTester::new(0.0);
+        let model = tester.load_model(
+            "tests/models/reg_squarederror/airline_satisfaction_model_trees_100_mixed.json",
+        )?;
+        let mut predicate = Predicate::new();
+        predicate.add_condition(
+            "online_boarding".to_string(),
+            Condition::GreaterThanOrEqual(4.0),
+        );
+        let pruned = model.prune(&predicate);
+
+        let data_path =
+            "tests/data/reg_squarederror/airline_satisfaction_data_filtered_trees_100_mixed.csv";
+        assert_round_trip(&model, data_path, DatasetType::Airline)?;
+        assert_round_trip(&pruned, data_path, DatasetType::Airline)
+    }
+}
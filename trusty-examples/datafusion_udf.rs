@@ -1,15 +1,13 @@
-use arrow::array::{ArrayRef, Float32Array};
+use arrow::array::Float32Array;
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
+use datafusion::logical_expr::ScalarUDF;
 use datafusion::prelude::*;
-use datafusion_common::Result as DataFusionResult;
-use datafusion_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
 use serde_json::Value;
-use std::any::Any;
 use std::error::Error;
 use std::sync::Arc;
+use trusty::datafusion::TrustyUdf;
 use trusty::loader::ModelLoader;
-use trusty::tree::FeatureType;
 use trusty::GradientBoostedDecisionTrees;
 
 const MODEL_JSON: &str = r#"{
@@ -39,70 +37,6 @@ const MODEL_JSON: &str = r#"{
     }
 }"#;
 
-#[derive(Debug, Clone)]
-struct TrustyUDF {
-    signature: Signature,
-    trees: GradientBoostedDecisionTrees,
-    return_type: DataType,
-}
-
-impl TrustyUDF {
-    fn new() -> Result<Self, Box<dyn Error>> {
-        let model_data: Value = serde_json::from_str(MODEL_JSON)?;
-        let model: GradientBoostedDecisionTrees =
-            GradientBoostedDecisionTrees::json_loads(&model_data)?;
-        let mut arg_types = Vec::new();
-        for feature_types in model.feature_types.iter() {
-            match feature_types {
-                FeatureType::Float => arg_types.push(DataType::Float32),
-                FeatureType::Int => arg_types.push(DataType::Int64),
-                FeatureType::Indicator => arg_types.push(DataType::Boolean),
-            }
-        }
-        Ok(Self {
-            signature: Signature::exact(arg_types, Volatility::Immutable),
-            trees: model,
-            return_type: DataType::Float32,
-        })
-    }
-}
-
-impl ScalarUDFImpl for TrustyUDF {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
-    fn name(&self) -> &str {
-        "predict"
-    }
-
-    fn signature(&self) -> &Signature {
-        &self.signature
-    }
-
-    fn return_type(&self, _arg_types: &[DataType]) -> DataFusionResult<DataType> {
-        Ok(self.return_type.clone())
-    }
-
-    fn invoke(&self, args: &[ColumnarValue]) -> DataFusionResult<ColumnarValue> {
-        let arrays: Vec<ArrayRef> = args
-            .iter()
-            .map(|arg| match arg {
-                ColumnarValue::Array(arr) => Ok(Arc::clone(arr)),
-                ColumnarValue::Scalar(s) => s.to_array(),
-            })
-            .collect::<DataFusionResult<Vec<_>>>()?;
-
-        let predictions = self.trees.predict_arrays(&arrays)?;
-        Ok(ColumnarValue::Array(Arc::new(predictions)))
-    }
-}
-
-fn create_trusty_udf() -> Result<ScalarUDF, Box<dyn Error>> {
-    let udf = TrustyUDF::new()?;
-    Ok(ScalarUDF::from(udf))
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let ctx = SessionContext::new();
@@ -117,16 +51,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let batch = RecordBatch::try_new(schema.clone(), vec![feature0, feature1])?;
 
     ctx.register_batch("test_table", batch)?;
-    let predict_udf = create_trusty_udf()?;
-    ctx.register_udf(predict_udf);
+    let model_data: Value = serde_json::from_str(MODEL_JSON)?;
+    let model = GradientBoostedDecisionTrees::json_loads(&model_data)?;
+    let predict_udf = TrustyUdf::new("predict", model);
+    ctx.register_udf(ScalarUDF::from(predict_udf.clone()));
 
+    // The trees only split on feature0, so that is the UDF's only argument.
     let df = ctx
         .sql(
-            "SELECT *, predict(feature0, feature1) as prediction 
+            "SELECT *, predict(feature0) as prediction 
              FROM test_table",
         )
         .await?;
+    df.show().await?;
 
+    // The same call, binding the arguments to the columns named after the features.
+    let df = ctx
+        .table("test_table")
+        .await?
+        .with_column("prediction", predict_udf.call_by_name())?;
     df.show().await?;
     Ok(())
 }